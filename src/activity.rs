@@ -0,0 +1,96 @@
+use strum_macros::Display;
+
+/// The kinds of log line the log pane picks out with an icon and a colour, named as in the
+/// `[log_icons]` and `[log_colors]` sections of the config, e.g. `backup = "B"`.
+#[derive(Clone, Copy, Debug, PartialEq, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum Activity {
+    Backup,
+    Restore,
+    Delete,
+    Skip,
+}
+
+impl Activity {
+    /// What a log line is about, from its tag. Warnings are about something we couldn't or
+    /// wouldn't do, whatever their tag, so they count as skips.
+    pub fn of(line: &str) -> Option<Activity> {
+        if line.contains("[WARN]") || line.contains("[ERROR]") || line.contains("[REVIEW] rejected")
+        {
+            Some(Activity::Skip)
+        } else if line.contains("[BACKUP]") {
+            Some(Activity::Backup)
+        } else if line.contains("[RESTORE]") || line.contains("[RECOVER]") {
+            Some(Activity::Restore)
+        } else if line.contains("[DELETE]") || line.contains("[PRUNE]") {
+            Some(Activity::Delete)
+        } else {
+            None
+        }
+    }
+
+    /// The icon to use unless the config sets one; `ascii` for terminals whose font doesn't
+    /// have the arrows.
+    pub fn default_icon(self, ascii: bool) -> &'static str {
+        match (self, ascii) {
+            (Activity::Backup, false) => "↑",
+            (Activity::Restore, false) => "↓",
+            (Activity::Delete, false) => "✗",
+            (Activity::Skip, false) => "⚠",
+            (Activity::Backup, true) => "^",
+            (Activity::Restore, true) => "v",
+            (Activity::Delete, true) => "x",
+            (Activity::Skip, true) => "!",
+        }
+    }
+
+    /// The colour to use unless the config sets one, by name as `highlight` takes them.
+    pub fn default_color(self) -> &'static str {
+        match self {
+            Activity::Backup => "green",
+            Activity::Restore => "blue",
+            Activity::Delete => "red",
+            Activity::Skip => "yellow",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_what_a_log_line_is_about() {
+        let cases = [
+            ("12:00 [BACKUP] copying a => b", Some(Activity::Backup)),
+            ("12:00 [RESTORE] copying b => a", Some(Activity::Restore)),
+            ("12:00 [RECOVER] restored a", Some(Activity::Restore)),
+            ("12:00 [DELETE] removing a", Some(Activity::Delete)),
+            ("12:00 [PRUNE] removing b", Some(Activity::Delete)),
+            (
+                "12:00 [WARN] [BACKUP] could not copy a",
+                Some(Activity::Skip),
+            ),
+            ("12:00 [ERROR] could not sync", Some(Activity::Skip)),
+            ("12:00 [REVIEW] rejected: back up a", Some(Activity::Skip)),
+            ("12:00 [REVIEW] approved: back up a", None),
+            ("12:00 started", None),
+        ];
+        for (line, activity) in cases {
+            assert_eq!(Activity::of(line), activity, "{}", line);
+        }
+    }
+
+    #[test]
+    fn has_ascii_icons_for_plain_terminals() {
+        for activity in [
+            Activity::Backup,
+            Activity::Restore,
+            Activity::Delete,
+            Activity::Skip,
+        ] {
+            assert!(activity.default_icon(true).is_ascii());
+            assert!(!activity.default_icon(false).is_ascii());
+        }
+    }
+}
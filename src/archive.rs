@@ -0,0 +1,115 @@
+use crate::backup::PARTIAL_EXTENSION;
+use crate::Result;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::info;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/// Packs everything in `backup_dir` (saves, recordings, versions, config and so on) into the
+/// tar.gz at `out`, for `--archive`. Half-finished copies are left out. Returns how many files
+/// went in.
+pub fn create(backup_dir: &Path, out: &Path) -> Result<usize> {
+    let mut names = vec![];
+    walk(backup_dir, Path::new(""), &mut names)?;
+    names.sort();
+
+    info!(
+        "[ARCHIVE] packing {} files into {}",
+        names.len(),
+        out.display()
+    );
+    let gz = GzEncoder::new(File::create(out)?, Compression::default());
+    let mut tar = tar::Builder::new(gz);
+    for name in &names {
+        tar.append_path_with_name(backup_dir.join(name), name)?;
+    }
+    tar.into_inner()?.finish()?;
+    Ok(names.len())
+}
+
+/// Unpacks the tar.gz at `archive` into `backup_dir`, for `--restore-archive`. Files already
+/// there with the same name are replaced by the archived ones; anything that would land
+/// outside `backup_dir` is skipped.
+pub fn restore(archive: &Path, backup_dir: &Path) -> Result<()> {
+    info!(
+        "[ARCHIVE] unpacking {} into {}",
+        archive.display(),
+        backup_dir.display()
+    );
+    let mut tar = tar::Archive::new(GzDecoder::new(File::open(archive)?));
+    tar.set_overwrite(true);
+    tar.unpack(backup_dir)?;
+    Ok(())
+}
+
+// every file under `dir`, relative to where the walk started
+fn walk(dir: &Path, relative: &Path, names: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = relative.join(entry.file_name());
+        if path.is_dir() {
+            walk(&path, &name, names)?;
+        } else if path.extension() != Some(OsStr::new(PARTIAL_EXTENSION)) {
+            names.push(name);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{names, Dirs};
+
+    #[test]
+    fn restores_what_it_archived() {
+        let dirs = Dirs::new();
+        dirs.backup("Saved #1 at depth 3.broguesave", "game one");
+        dirs.backup("Saved #1 at depth 3.broguesave.sum", "1234");
+        std::fs::create_dir_all(dirs.backup_dir.join("versions")).unwrap();
+        std::fs::write(dirs.backup_dir.join("versions/old.broguesave"), "older").unwrap();
+        dirs.backup(&format!("copying.{}", PARTIAL_EXTENSION), "half");
+        let out = dirs.save_dir.join("backups.tar.gz");
+
+        assert_eq!(create(&dirs.backup_dir, &out).unwrap(), 3);
+
+        let unpacked = dirs.save_dir.join("unpacked");
+        std::fs::create_dir_all(&unpacked).unwrap();
+        restore(&out, &unpacked).unwrap();
+        assert_eq!(
+            names(&unpacked),
+            [
+                "Saved #1 at depth 3.broguesave",
+                "Saved #1 at depth 3.broguesave.sum"
+            ]
+        );
+        let read = |path: &str| std::fs::read_to_string(unpacked.join(path)).unwrap();
+        assert_eq!(read("Saved #1 at depth 3.broguesave"), "game one");
+        assert_eq!(read("versions/old.broguesave"), "older");
+    }
+
+    #[test]
+    fn restoring_replaces_files_with_the_same_name() {
+        let dirs = Dirs::new();
+        dirs.backup("settings.txt", "archived");
+        let out = dirs.save_dir.join("backups.tar.gz");
+        create(&dirs.backup_dir, &out).unwrap();
+
+        dirs.backup("settings.txt", "changed since");
+        restore(&out, &dirs.backup_dir).unwrap();
+        let restored = std::fs::read_to_string(dirs.backup_dir.join("settings.txt")).unwrap();
+        assert_eq!(restored, "archived");
+    }
+
+    #[test]
+    fn a_damaged_archive_is_an_error() {
+        let dirs = Dirs::new();
+        let out = dirs.save_dir.join("backups.tar.gz");
+        std::fs::write(&out, "not a tar.gz").unwrap();
+        assert!(restore(&out, &dirs.backup_dir).is_err());
+    }
+}
@@ -1,213 +1,6322 @@
-use crate::Result;
-use std::collections::HashMap;
+use crate::cache::{self, STATE_CACHE_FILE};
+use crate::config::{Config, CONFIG_FILE};
+use crate::crypt::{
+    encrypted_path, hash_contents, is_encrypted, plain_name, read_contents, Cipher,
+};
+use crate::disk::free_inodes;
+use crate::extras;
+use crate::hashes::HashCache;
+use crate::history;
+use crate::keymap::{Action, KeyMap};
+use crate::locale::Locale;
+use crate::long_names::{LongNames, LONG_NAMES_FILE};
+use crate::manifest::{self, Changes, Manifest, MANIFEST_FILE};
+use crate::metadata::{parse_save_name, split_comment, with_comment, Difficulty, SaveMetadata};
+use crate::notes::{Notes, NOTES_FILE};
+use crate::notification;
+use crate::pins::{Pins, PINS_FILE};
+use crate::snapshots::{self, new_snapshot_dir};
+use crate::tail::tail;
+use crate::throttle::{Throttle, Throughput};
+use crate::verify::{Verification, Verifier};
+use crate::versions::{
+    checksum_path, is_intact, new_version_path, store_version, versions, write_checksum,
+    CHECKSUM_EXTENSION, VERSIONS_DIR,
+};
+use crate::watch::{DirWatcher, WatchStrategy};
+use crate::{AppError, Result};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::ffi::OsStr;
 use std::fmt::{Display, Formatter};
+use std::io::{Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
-use std::time::{Duration, SystemTime};
+use std::process::Command;
+use std::sync::mpsc::{self, TryRecvError};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use strum_macros::{Display, EnumString};
+use tracing::{trace, trace_span};
 
-#[derive(PartialEq)]
+const ARCHIVE_DIR: &str = "archive";
+const TRASH_DIR: &str = "trash";
+// how many of the user's changes 'u' can take back
+const UNDO_LIMIT: usize = 20;
+const RECORDINGS_DIR: &str = "recordings";
+const SAVE_EXTENSION: &str = "broguesave";
+const RECORDING_EXTENSION: &str = "broguerec";
+pub const PARTIAL_EXTENSION: &str = "partial";
+// written and removed again to check the backup dir is still writable
+const WRITABLE_PROBE: &str = ".writable";
+const PARTIAL_MAX_AGE: Duration = Duration::from_secs(60);
+const RECENT_ACTION_WINDOW: Duration = Duration::from_secs(5);
+const STATUS_MESSAGE_WINDOW: Duration = Duration::from_secs(3);
+const LOG_TAIL_LINES: usize = 10;
+// how far back the log pane can be scrolled
+const LOG_HISTORY_LINES: usize = 200;
+const RESTORED_DIR: &str = "restored";
+// how many back-to-back passes to make at startup before settling into the normal interval
+const MAX_CATCH_UP_ROUNDS: usize = 100;
+// a scan slower than this is worth telling the user about
+const SLOW_SCAN: Duration = Duration::from_millis(500);
+// how long to wait before trying a failed restore again, doubling each time up to the max
+const RESTORE_RETRY_MIN: Duration = Duration::from_secs(1);
+const RESTORE_RETRY_MAX: Duration = Duration::from_secs(5 * 60);
+
+/// What to do when a save that was backed up disappears from the save dir.
+#[derive(Clone, Copy, Debug, PartialEq, EnumString, Display, Deserialize, Serialize)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum SyncMode {
+    /// copy the backup straight back into the save dir
+    Mirror,
+    /// assume brogue deleted it on purpose (death/victory) and move the backup to `archive/`
+    Archive,
+    /// leave it alone; only ever copy saves into the backup dir, never back out
+    BackupOnly,
+}
+
+impl SyncMode {
+    /// The mode after this one, for cycling through them from the UI.
+    pub fn next(self) -> SyncMode {
+        match self {
+            SyncMode::Mirror => SyncMode::Archive,
+            SyncMode::Archive => SyncMode::BackupOnly,
+            SyncMode::BackupOnly => SyncMode::Mirror,
+        }
+    }
+}
+
+/// What to do with backups that are byte-for-byte copies of another backup.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DedupMode {
+    /// leave them alone
+    Off,
+    /// move them into `trash/`
+    Trash,
+    /// delete them
+    Remove,
+}
+
+/// Which copy of each diverged save to keep when resolving them all at once; see
+/// `App::diverged`.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeepDiverged {
+    /// whichever was written last; the live save if they were written at the same time
+    Newest,
+    Live,
+    Backup,
+}
+
+/// One of the two copies of a save.
+#[derive(Clone, Copy, Debug, PartialEq, Display)]
+pub enum Side {
+    #[strum(serialize = "live save")]
+    Live,
+    #[strum(serialize = "backup")]
+    Backup,
+}
+
+/// Something `reconcile` did to a save.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Display)]
+pub enum ActionKind {
+    #[strum(serialize = "backed up")]
+    BackedUp,
+    #[strum(serialize = "restored")]
+    Restored,
+    #[strum(serialize = "archived")]
+    Archived,
+}
+
+/// Which kinds of file the saves list shows; cycled with TAB.
+#[derive(Clone, Copy, Debug, PartialEq, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum KindFilter {
+    All,
+    Saves,
+    Recordings,
+}
+
+/// One step of syncing the save and backup dirs; see `App::plan`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SyncAction {
+    /// copy a new save into the backup dir
+    Backup { save: PathBuf, backup: PathBuf },
+    /// keep the current backup as an older version and replace it with the changed save
+    UpdateBackup { save: PathBuf, backup: PathBuf },
+    /// bring the backup of a recording up to date with what's been added to it
+    AppendRecording { save: PathBuf, backup: PathBuf },
+    /// put a missing save back from its newest intact backup
+    Restore(Save),
+    /// move the backup of a save brogue deleted into `archive/`
+    Archive(PathBuf),
+    /// replace a backup that matches its save with a hard link to it, for `hardlink`
+    Link { save: PathBuf, backup: PathBuf },
+    /// give a save linked to its backup a separate copy again
+    Unlink { save: PathBuf, backup: PathBuf },
+    /// bring the copy of a backup in one of the `extra_backup_dirs` up to date
+    Replicate { backup: PathBuf, copy: PathBuf },
+}
+
+impl SyncAction {
+    /// A fixed name for the kind of action, for `--plan --json`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SyncAction::Backup { .. } => "backup",
+            SyncAction::UpdateBackup { .. } => "update-backup",
+            SyncAction::AppendRecording { .. } => "append-recording",
+            SyncAction::Restore(_) => "restore",
+            SyncAction::Archive(_) => "archive",
+            SyncAction::Link { .. } => "link",
+            SyncAction::Unlink { .. } => "unlink",
+            SyncAction::Replicate { .. } => "replicate",
+        }
+    }
+
+    /// The name of the save it acts on.
+    pub fn key(&self) -> String {
+        match self {
+            SyncAction::Backup { save, .. }
+            | SyncAction::UpdateBackup { save, .. }
+            | SyncAction::AppendRecording { save, .. }
+            | SyncAction::Link { save, .. }
+            | SyncAction::Unlink { save, .. } => key(save),
+            SyncAction::Restore(save) => save.key(),
+            SyncAction::Archive(backup) | SyncAction::Replicate { backup, .. } => key(backup),
+        }
+    }
+}
+
+impl Display for SyncAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyncAction::Backup { save, .. } => write!(f, "back up {}", key(save)),
+            SyncAction::UpdateBackup { save, .. } => write!(f, "update backup of {}", key(save)),
+            SyncAction::AppendRecording { save, .. } => {
+                write!(f, "update recording {}", key(save))
+            }
+            SyncAction::Restore(save) => write!(f, "restore {}", save.key()),
+            SyncAction::Archive(backup) => write!(f, "archive {}", key(backup)),
+            SyncAction::Link { save, .. } => write!(f, "link backup of {}", key(save)),
+            SyncAction::Unlink { save, .. } => write!(f, "unlink backup of {}", key(save)),
+            SyncAction::Replicate { backup, copy } => {
+                write!(f, "copy backup of {} to {}", key(backup), copy.display())
+            }
+        }
+    }
+}
+
+/// Whether we're syncing yet.
+#[derive(Debug, PartialEq)]
+pub enum AppMode {
+    /// showing what the first sync will do, and waiting for the go-ahead
+    Preview(Vec<SyncAction>),
+    Active,
+    /// nothing is synced until it's approved; each planned action waits in `App::review_queue`
+    Manual,
+}
+
+/// Picking two saves to compare side by side, by key.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Compare {
+    Off,
+    /// the first save is picked; waiting for the second
+    Picked(String),
+    Showing(String, String),
+}
+
+/// A question the UI is waiting on the user to answer.
+#[derive(PartialEq, Debug)]
+pub enum Prompt {
+    /// typing where to export the selected save
+    ExportPath { source: PathBuf, input: String },
+    /// the export destination exists; y/n to overwrite it
+    ConfirmOverwrite {
+        source: PathBuf,
+        destination: PathBuf,
+    },
+    /// typing which folder to restore the save called `key` into, rather than the save dir
+    /// `version` is the older version to restore, if one's selected, rather than the backup
+    RestorePath {
+        key: String,
+        version: Option<PathBuf>,
+        input: String,
+    },
+    /// typing a comment to add to the name of the save called `key`
+    Rename { key: String, input: String },
+    /// typing a note on the save called `key`
+    Note { key: String, input: String },
+    /// typing text to look for in save names and notes
+    Search { input: String },
+    /// typing the seed of the save to select; only digits are taken
+    GoToSeed { input: String },
+    /// saves from last session have gone from both dirs; y/n to put back the copies `found`
+    /// in the trash or archive, each with the save it's a copy of
+    Recover {
+        missing: Vec<String>,
+        found: Vec<(String, PathBuf)>,
+    },
+    /// y/n to resolve each diverged save by keeping the given copy
+    ResolveDiverged { resolutions: Vec<(String, Side)> },
+}
+
+/// Something the user did that 'u' can take back; see `App::undo`. The sync loop's own
+/// copies aren't recorded, since the next sync would only do them again.
+#[derive(Debug)]
+pub enum ReversibleAction {
+    /// a deleted save's files, moved into `trash/`, each with where it came from
+    Delete {
+        key: String,
+        moved: Vec<(PathBuf, PathBuf)>,
+    },
+    /// saves archived to cut down the save dir, with where each backup came from
+    Archive { moved: Vec<(PathBuf, PathBuf)> },
+    /// a save renamed from `from` to `to`
+    Rename { from: String, to: String },
+    /// a copy made outside the save dir, by exporting or restoring elsewhere
+    Copied(PathBuf),
+    /// something that can't be taken back, like overwriting a file on export; kept so undo
+    /// doesn't silently skip past it to something older
+    Irreversible(String),
+}
+
+impl Display for ReversibleAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReversibleAction::Delete { key, .. } => write!(f, "deleting {}", key),
+            ReversibleAction::Archive { moved } => write!(f, "archiving {} saves", moved.len()),
+            ReversibleAction::Rename { from, to } => write!(f, "renaming {} to {}", from, to),
+            ReversibleAction::Copied(path) => write!(f, "copying to {}", path.display()),
+            ReversibleAction::Irreversible(description) => write!(f, "{}", description),
+        }
+    }
+}
+
+/// A line of the save list: a visible save, by its index in `visible_saves`, or one of the
+/// older versions of an expanded save.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ListRow {
+    Save(usize),
+    Version(usize, PathBuf),
+}
+
+#[derive(Debug, PartialEq)]
 pub enum DeleteState {
     NotDeleting,
     AwaitingIndex,
+    // the save is only in the backup dir and `protect_last_copy` is on, so ask again
+    ConfirmLastCopy(usize),
     Delete(usize),
 }
 
 pub struct App {
     save_dir: PathBuf,
     backup_dir: PathBuf,
+    config: Config,
+    locale: Locale,
+    // encrypts new backups and decrypts existing ones; `None` if no passphrase is set
+    cipher: Option<Arc<Cipher>>,
+    // slows copies down if `max_copy_bytes_per_sec` is set
+    throttle: Option<Throttle>,
+    // how fast backups and restores have been copying
+    throughput: Throughput,
+    pub mode: AppMode,
     pub delete_state: DeleteState,
+    pub prompt: Option<Prompt>,
+    /// the comparison shown in the details pane, keyed by the save it belongs to
+    pub diff: Option<(String, DiffSummary)>,
+    /// show a single borderless list with a one-line status footer
+    pub compact: bool,
+    /// which key does what
+    pub keymap: KeyMap,
+    /// the `--profile` whose backups these are, if not the usual ones
+    pub profile: Option<String>,
+    /// only draw a single status line and ignore every key but 'q', for `--minimal`
+    pub minimal: bool,
+    /// when the next scheduled sync is due; set by the main loop
+    pub next_sync: Option<Instant>,
+    // in manual mode, the planned actions waiting to be approved or rejected, oldest first
+    review: VecDeque<SyncAction>,
+    // approved, to be done on the next sync
+    approved: Vec<SyncAction>,
+    // rejected, and not asked about again while they're still planned
+    rejected: Vec<SyncAction>,
+    /// show each save's full paths in the list rather than just its name
+    pub full_paths: bool,
+    /// index of the highlighted save in `visible_saves`
+    pub selected: usize,
+    // the version of the selected save that's highlighted, if it's expanded and one is
+    selected_version: Option<PathBuf>,
+    // the saves, by key, whose older versions are listed under them
+    expanded: HashSet<String>,
+    /// keep the most recently modified save selected
+    pub follow: bool,
+    /// list the newest saves first, rather than the oldest
+    pub sort_descending: bool,
+    /// difficulties to show; empty means show everything
+    pub difficulty_filter: BTreeSet<Difficulty>,
+    pub kind_filter: KindFilter,
+    /// only show saves whose name or note contains this, ignoring case; empty shows them all
+    pub search: String,
     pub state: State,
+    // the state as of the last tick, so we can see saves going from synced to backup-only
+    previous: State,
+    // what we last did to each save, keyed by file name; cleared once it's no longer recent
+    last_actions: HashMap<String, (ActionKind, Instant)>,
+    // a brief message for the status bar, e.g. when a key press is rejected
+    status_message: Option<(String, Instant)>,
+    // remembers file hashes so unchanged files aren't read again every tick
+    hashes: Arc<HashCache>,
+    verifier: Verifier,
+    // tells us when the save dir changes; `None` until it exists, or if watching failed
+    watcher: Option<DirWatcher>,
+    watch_failed: bool,
+    // the saves tracked at the end of last session, until they've been checked for
+    last_session: Option<Manifest>,
+    // passes made and changes so far while catching up at startup; `None` once caught up
+    catch_up: Option<(usize, usize)>,
+    // when we last finished syncing the two dirs
+    last_sync: Option<Instant>,
+    // whether the newest save was backed up as of the last tick
+    health: Health,
+    // the saves shown are from the cache of last session's, not a scan
+    stale: bool,
+    // the first scan, running on another thread; see `scan_in_background`
+    background_scan: Option<mpsc::Receiver<(Result<State>, Duration)>>,
+    // so a slow scan is only warned about once
+    warned_slow_scan: bool,
+    waiting_for_save_dir: bool,
+    // whether the backup dir is on a different volume to the save dir; `None` until the save
+    // dir exists to check
+    cross_volume: Option<bool>,
+    // the user's most recent changes, newest last, for 'u'
+    undo: VecDeque<ReversibleAction>,
+    // the tail of the log file, while the log pane is open
+    log_tail: Option<Vec<String>>,
+    // how many lines back from the newest the log pane is scrolled; while it's scrolled back,
+    // the log stays as it was so it can be read
+    log_scroll: usize,
+    // saves we couldn't back up or restore last tick, so we only complain about them once
+    failed_syncs: HashSet<String>,
+    // restores that failed, by save, until they work or aren't needed any more
+    failed_restores: BTreeMap<String, FailedRestore>,
+    // saves too big to back up, so each is only warned about once
+    oversized: HashSet<String>,
+    pins: Pins,
+    notes: Notes,
+    long_names: LongNames,
+    // how many of each action we've taken since starting
+    session_actions: BTreeMap<ActionKind, usize>,
+    /// show the stats popup
+    pub show_stats: bool,
+    /// what's changed since last session, until the popup showing it is closed
+    pub since_last_launch: Option<Changes>,
+    pub compare: Compare,
+    // when each save was last modified, as of the last snapshot check
+    snapshot_times: HashMap<PathBuf, SystemTime>,
+    // saves hard linked to their backups, and when they were last modified as of linking; a
+    // later time means brogue rewrote the save, and so the backup, in place
+    links: HashMap<PathBuf, SystemTime>,
+    // saves brogue has rewritten in place, which are never linked again
+    rewritten_in_place: HashSet<String>,
+    // why the backup dir couldn't be written to when we last checked; we only watch until
+    // it can be again
+    unwritable: Option<String>,
+    last_write_check: Option<Instant>,
+    // when the newest snapshot of the whole save dir was taken
+    last_full_snapshot: Option<SystemTime>,
 }
 
 impl App {
     pub fn update_state(&mut self) -> Result<()> {
+        if !self.prepare_scan()? {
+            return Ok(());
+        }
+        let started = Instant::now();
         let state = get_state(&self.save_dir, &self.backup_dir)?;
-        self.state = state;
+        self.apply_scan(state, started.elapsed());
         Ok(())
     }
 
-    pub fn new(save_dir: PathBuf, backup_dir: PathBuf) -> App {
+    // everything a scan needs before it looks at the dirs; false if there's no save dir to
+    // scan yet
+    fn prepare_scan(&mut self) -> Result<bool> {
+        // brogue creates its save dir the first time it runs, so it may not be there yet
+        if !self.save_dir.exists() {
+            if !self.waiting_for_save_dir {
+                info!("waiting for save dir {} to appear", self.save_dir.display());
+            }
+            self.waiting_for_save_dir = true;
+            return Ok(false);
+        }
+        if self.waiting_for_save_dir {
+            info!("found save dir {}", self.save_dir.display());
+            self.waiting_for_save_dir = false;
+        }
+        if self.cross_volume.is_none() {
+            let cross_volume = !same_volume(&self.save_dir, &self.backup_dir)?;
+            if cross_volume {
+                info!(
+                    "{} is on a different volume to {}; copies can't be moved into place atomically",
+                    self.backup_dir.display(),
+                    self.save_dir.display()
+                );
+            }
+            self.cross_volume = Some(cross_volume);
+        }
+
+        if self.watcher.is_none() && !self.watch_failed {
+            match DirWatcher::new(&self.save_dir, self.config.force_poll) {
+                Ok(watcher) => {
+                    info!(
+                        "watching {} using {}",
+                        self.save_dir.display(),
+                        watcher.strategy
+                    );
+                    self.watcher = Some(watcher);
+                }
+                Err(e) => {
+                    warn!("could not watch {}: {}", self.save_dir.display(), e);
+                    self.watch_failed = true;
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn apply_scan(&mut self, state: State, took: Duration) {
+        self.log_scan(took, state.scanned);
+        self.state = state;
+        self.record_long_names();
+        self.stale = false;
+        self.hashes.prune();
+        if let Some(last_session) = self.last_session.take() {
+            self.check_last_session(&last_session);
+        }
+        if self.follow {
+            self.select_newest();
+        }
+        self.clamp_selection();
+        self.verifier.submit(&self.state);
+
+        if self.is_previewing() {
+            let plan = self.plan();
+            // nothing to confirm, so go straight to syncing
+            self.mode = if plan.is_empty() {
+                AppMode::Active
+            } else {
+                AppMode::Preview(plan)
+            };
+        }
+    }
+
+    // a save whose name is too long to back up as-is is backed up under a shortened one;
+    // remember its own, so it's restored under that
+    fn record_long_names(&mut self) {
+        for save in &self.state.saves {
+            let live = match save {
+                Save::OriginalFileOnly(live) | Save::Both(live, _) => live,
+                Save::BackupFileOnly(_) => continue,
+            };
+            let (key, full) = (save.key(), plain_name(live));
+            match self.long_names.record(&key, &full) {
+                Ok(true) => info!(
+                    "[BACKUP] {} is too long a name to back up as-is; backed up as {}",
+                    full, key
+                ),
+                Ok(false) => {}
+                Err(e) => warn!("[BACKUP] could not record {}'s full name: {}", key, e),
+            }
+        }
+    }
+
+    /// The save's name in the save dir. A shortened one backed up under a shortened name only
+    /// has its own recorded in `long_names`.
+    fn full_name(&self, save: &Save) -> String {
+        match save {
+            Save::OriginalFileOnly(live) | Save::Both(live, _) => plain_name(live),
+            Save::BackupFileOnly(_) => self.full_name_of(&save.key()),
+        }
+    }
+
+    fn full_name_of(&self, key: &str) -> String {
+        self.long_names.get(key).unwrap_or(key).to_string()
+    }
+
+    /// Rescans the dirs, as `update_state`, but a scan that fails, e.g. on a save deleted part
+    /// way through, is logged and shown rather than returned; the next one tries again.
+    /// While a `scan_in_background` is still running this waits for it instead, picking up
+    /// what it found once it's done. Returns whether it worked, as there's no syncing from a
+    /// stale scan.
+    pub fn scan(&mut self) -> bool {
+        let scanned = match self.background_scan.take() {
+            Some(finished) => match finished.try_recv() {
+                Ok((state, took)) => state.map(|state| self.apply_scan(state, took)),
+                Err(TryRecvError::Empty) => {
+                    self.background_scan = Some(finished);
+                    return false;
+                }
+                Err(TryRecvError::Disconnected) => self.update_state(),
+            },
+            None => self.update_state(),
+        };
+        match scanned {
+            Ok(()) => true,
+            Err(e) => {
+                self.scan_failed(&e);
+                false
+            }
+        }
+    }
+
+    /// Starts a scan on another thread, so that with a big backup dir, last session's saves
+    /// are shown and keys handled straight away rather than after it. `scan` picks up the
+    /// result.
+    pub fn scan_in_background(&mut self) {
+        match self.prepare_scan() {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(e) => return self.scan_failed(&e),
+        }
+        let (save_dir, backup_dir) = (self.save_dir.clone(), self.backup_dir.clone());
+        let (finished, scanning) = mpsc::channel();
+        std::thread::spawn(move || {
+            let started = Instant::now();
+            let state = get_state(&save_dir, &backup_dir);
+            // nobody's listening if we've quit in the meantime
+            let _ = finished.send((state, started.elapsed()));
+        });
+        self.background_scan = Some(scanning);
+    }
+
+    /// Whether a `scan_in_background` hasn't been picked up yet.
+    pub fn is_scanning(&self) -> bool {
+        self.background_scan.is_some()
+    }
+
+    fn scan_failed(&mut self, e: &AppError) {
+        warn!("[SCAN] could not scan the save and backup dirs: {}", e);
+        self.set_status(format!("could not scan: {}", e));
+    }
+
+    pub fn new(
+        save_dir: PathBuf,
+        backup_dir: PathBuf,
+        config: Config,
+        cipher: Option<Cipher>,
+        pins: Pins,
+        notes: Notes,
+        long_names: LongNames,
+    ) -> App {
+        let follow = config.follow;
+        let sort_descending = config.sort_descending;
+        let cipher = cipher.map(Arc::new);
+        let locale = Locale::resolve(config.locale.as_deref());
+        let throttle = Throttle::new(config.max_copy_bytes_per_sec);
+        let hashes = Arc::new(HashCache::new(cipher.clone()));
+        // what was synced when we last shut down, so archive mode can tell a save brogue
+        // deleted while we weren't running from one that's never been in the save dir
+        let previous = cache::load(&save_dir, &backup_dir).unwrap_or_default();
+        let last_full_snapshot = snapshots::latest(&backup_dir);
         App {
             save_dir,
             backup_dir,
+            config,
+            locale,
+            throttle,
+            throughput: Throughput::default(),
+            verifier: Verifier::new(hashes.clone()),
+            hashes,
+            cipher,
+            mode: AppMode::Active,
             delete_state: DeleteState::NotDeleting,
+            prompt: None,
+            diff: None,
+            compact: false,
+            keymap: KeyMap::default(),
+            profile: None,
+            minimal: false,
+            next_sync: None,
+            review: VecDeque::new(),
+            approved: vec![],
+            rejected: vec![],
+            full_paths: false,
+            selected: 0,
+            selected_version: None,
+            expanded: HashSet::new(),
+            follow,
+            sort_descending,
+            difficulty_filter: BTreeSet::new(),
+            kind_filter: KindFilter::All,
+            search: String::new(),
             state: State::default(),
+            previous,
+            last_actions: HashMap::new(),
+            status_message: None,
+            failed_syncs: HashSet::new(),
+            failed_restores: BTreeMap::new(),
+            oversized: HashSet::new(),
+            watcher: None,
+            watch_failed: false,
+            warned_slow_scan: false,
+            catch_up: Some((0, 0)),
+            last_session: None,
+            waiting_for_save_dir: false,
+            cross_volume: None,
+            last_sync: None,
+            health: Health::Good,
+            stale: false,
+            background_scan: None,
+            undo: VecDeque::new(),
+            log_tail: None,
+            log_scroll: 0,
+            pins,
+            notes,
+            long_names,
+            session_actions: BTreeMap::new(),
+            show_stats: false,
+            since_last_launch: None,
+            compare: Compare::Off,
+            snapshot_times: HashMap::new(),
+            links: HashMap::new(),
+            rewritten_in_place: HashSet::new(),
+            unwritable: None,
+            last_write_check: None,
+            last_full_snapshot,
         }
     }
 
-    fn cp(from: &Path, to: &Path) -> Result<()> {
-        if !from.exists() {
-            return Ok(());
-        }
+    /// Whether the save dir has changed since we last asked, so it's worth syncing now.
+    pub fn files_changed(&self) -> bool {
+        self.watcher.as_ref().is_some_and(|w| w.changed())
+    }
 
-        if !to.exists() {
-            std::fs::copy(&from, &to)?;
-        }
+    pub fn watch_strategy(&self) -> Option<WatchStrategy> {
+        self.watcher.as_ref().map(|w| w.strategy)
+    }
 
-        Ok(())
+    /// Whether the backups are on a different volume to the saves, like an external drive.
+    pub fn is_cross_volume(&self) -> bool {
+        self.cross_volume.unwrap_or(false)
     }
 
-    fn rm(path: &Path) -> Result<()> {
-        if path.exists() {
-            std::fs::remove_file(path)?;
+    pub fn toggle_difficulty(&mut self, difficulty: Difficulty) {
+        if !self.difficulty_filter.remove(&difficulty) {
+            self.difficulty_filter.insert(difficulty);
         }
+        self.clamp_selection();
+    }
 
-        Ok(())
+    pub fn cycle_kind_filter(&mut self) {
+        self.kind_filter = match self.kind_filter {
+            KindFilter::All => KindFilter::Saves,
+            KindFilter::Saves => KindFilter::Recordings,
+            KindFilter::Recordings => KindFilter::All,
+        };
+        self.clamp_selection();
     }
 
-    fn reconcile(&mut self) -> Result<()> {
-        if let DeleteState::Delete(idx) = &self.delete_state {
-            // delete from both;
-            if let Some(save) = &self.state.saves.get(*idx) {
-                match save {
-                    Save::OriginalFileOnly(x) => {
-                        Self::rm(x)?;
-                    }
-                    Save::BackupFileOnly(x) => {
-                        Self::rm(x)?;
-                    }
-                    Save::Both(x, y) => {
-                        Self::rm(y)?;
-                        Self::rm(x)?;
-                    }
+    /// Moves down a row: into the selected save's versions if it's expanded, otherwise to the
+    /// next save.
+    pub fn select_next(&mut self) {
+        if let Some(save) = self.selected_save() {
+            if self.is_expanded(save) {
+                let versions = self.versions_of(save);
+                let next = match self.selected_version() {
+                    Some(current) => versions.iter().position(|v| v == current).map(|i| i + 1),
+                    None => Some(0),
+                };
+                if let Some(version) = next.and_then(|i| versions.get(i)) {
+                    self.selected_version = Some(version.clone());
+                    return;
                 }
-                self.delete_state = DeleteState::NotDeleting;
-                return Ok(());
             }
         }
+        if self.selected + 1 < self.visible_saves().len() {
+            self.selected += 1;
+            self.selected_version = None;
+        }
+    }
 
-        for save in &self.state.saves {
-            match save {
-                Save::OriginalFileOnly(save) => {
-                    let file_name = save.file_name().unwrap_or_default();
-                    let backup_destination = self.backup_dir.join(&file_name);
-                    Self::cp(save, &backup_destination)?;
-                }
-                Save::BackupFileOnly(backup) => {
-                    let file_name = backup.file_name().unwrap_or_default();
-                    let save_destination = self.save_dir.join(&file_name);
-                    Self::cp(backup, &save_destination)?;
-                }
-                Save::Both(_, _) => {}
+    /// Selects the `idx`th visible save, if there is one.
+    pub fn select(&mut self, idx: usize) {
+        if idx < self.visible_saves().len() {
+            self.selected = idx;
+            self.selected_version = None;
+        }
+    }
+
+    /// Selects a row of the list, e.g. one clicked on.
+    pub fn select_row(&mut self, row: ListRow) {
+        match row {
+            ListRow::Save(idx) => self.select(idx),
+            ListRow::Version(idx, version) => {
+                self.select(idx);
+                self.selected_version = Some(version);
             }
         }
-        Ok(())
     }
 
-    pub fn on_tick(&mut self) {
-        self.reconcile().unwrap();
+    /// Moves up a row: out of an expanded save's versions and into the one above's.
+    pub fn select_previous(&mut self) {
+        if let (Some(save), Some(current)) = (self.selected_save(), self.selected_version()) {
+            let versions = self.versions_of(save);
+            let previous = versions
+                .iter()
+                .position(|v| v == current)
+                .and_then(|i| i.checked_sub(1));
+            self.selected_version = previous.map(|i| versions[i].clone());
+            return;
+        }
+        if self.selected == 0 {
+            return;
+        }
+        self.selected -= 1;
+        self.selected_version = match self.selected_save() {
+            Some(save) if self.is_expanded(save) => self.versions_of(save).pop(),
+            _ => None,
+        };
     }
-}
-#[derive(Default)]
-pub struct State {
-    pub saves: Vec<Save>,
-}
 
-#[derive(Debug, PartialOrd, Ord, PartialEq, Eq)]
-pub enum Save {
-    OriginalFileOnly(PathBuf),
-    BackupFileOnly(PathBuf),
-    Both(PathBuf, PathBuf),
-}
+    /// The older versions of `save` in the backup dir, newest first.
+    pub fn versions_of(&self, save: &Save) -> Vec<PathBuf> {
+        versions(&self.backup_dir, &save.key())
+    }
 
-impl Save {
-    fn key(&self) -> String {
-        match self {
-            Save::OriginalFileOnly(x) => key(x),
-            Save::BackupFileOnly(x) => key(x),
-            Save::Both(x, _) => key(x),
+    pub fn is_expanded(&self, save: &Save) -> bool {
+        self.expanded.contains(&save.key())
+    }
+
+    /// The highlighted version of the selected save, if one is.
+    pub fn selected_version(&self) -> Option<&Path> {
+        let version = self.selected_version.as_deref()?;
+        let save = self.selected_save()?;
+        (self.is_expanded(save) && version.exists()).then_some(version)
+    }
+
+    /// Lists the selected save's older versions under it.
+    pub fn expand(&mut self) {
+        let save = match self.selected_save() {
+            Some(save) => save.clone(),
+            None => return,
+        };
+        if self.versions_of(&save).is_empty() {
+            self.set_status(format!("no older versions of {}", save.key()));
+            return;
         }
+        self.expanded.insert(save.key());
     }
 
-    fn sort_by(&self) -> Duration {
-        match self {
-            Save::OriginalFileOnly(x) => sort_by(x),
-            Save::BackupFileOnly(x) => sort_by(x),
-            Save::Both(x, y) => sort_by(x).max(sort_by(y)),
+    /// Hides the selected save's versions again, selecting the save itself.
+    pub fn collapse(&mut self) {
+        if let Some(key) = self.selected_save().map(|s| s.key()) {
+            self.expanded.remove(&key);
         }
+        self.selected_version = None;
     }
-}
 
-fn key(path: &Path) -> String {
-    path.file_name()
-        .unwrap_or_default()
-        .to_string_lossy()
-        .to_string()
-}
+    pub fn toggle_expanded(&mut self) {
+        match self.selected_save() {
+            Some(save) if self.is_expanded(save) => self.collapse(),
+            Some(_) => self.expand(),
+            None => {}
+        }
+    }
 
-fn sort_by(path: &Path) -> Duration {
-    let modified = path.metadata().unwrap().modified().expect("get file mod");
-    SystemTime::now()
-        .duration_since(modified)
-        .expect("duration_since")
-}
+    /// The rows of the list, in display order: every visible save, each expanded one followed
+    /// by its versions.
+    pub fn rows(&self) -> Vec<ListRow> {
+        let mut rows = vec![];
+        for (idx, save) in self.visible_saves().into_iter().enumerate() {
+            rows.push(ListRow::Save(idx));
+            if self.is_expanded(save) {
+                rows.extend(
+                    self.versions_of(save)
+                        .into_iter()
+                        .map(|version| ListRow::Version(idx, version)),
+                );
+            }
+        }
+        rows
+    }
 
-impl Display for Save {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let prefix = match self {
-            Save::OriginalFileOnly(_) => "S<-xB",
-            Save::BackupFileOnly(_) => "Sx->B",
-            Save::Both(_, _) => "S<->B",
+    /// Which of `rows` is highlighted.
+    pub fn selected_row(&self) -> usize {
+        let selected = match self.selected_version() {
+            Some(version) => ListRow::Version(self.selected, version.to_path_buf()),
+            None => ListRow::Save(self.selected),
         };
+        self.rows()
+            .iter()
+            .position(|row| *row == selected)
+            .unwrap_or(0)
+    }
 
-        let code = match self {
-            Save::OriginalFileOnly(_) => "SAVE",
-            Save::BackupFileOnly(_) => "BACK",
-            Save::Both(_, _) => "SYNC",
-        };
-        write!(f, "{} {} {}", code, prefix, self.key())
+    /// Flips the list between newest first and oldest first, keeping the same save selected.
+    pub fn toggle_sort_direction(&mut self) {
+        let selected = self.selected_save().map(|s| s.key());
+        self.sort_descending = !self.sort_descending;
+        if let Some(key) = selected {
+            if let Some(idx) = self.visible_saves().iter().position(|s| s.key() == key) {
+                self.selected = idx;
+            }
+        }
     }
-}
 
-pub fn get_state(save_dir: &Path, backup_dir: &Path) -> Result<State> {
-    let save_files = files(save_dir)?;
-    let backup_files = files(backup_dir)?;
-    let mut map: HashMap<String, Save> = HashMap::new();
+    pub fn toggle_follow(&mut self) {
+        self.follow = !self.follow;
+        if self.follow {
+            self.select_newest();
+        }
+    }
 
-    for save_file in save_files {
-        map.entry(key(&save_file))
-            .or_insert_with(|| Save::OriginalFileOnly(save_file.clone()));
+    fn select_newest(&mut self) {
+        let newest = self
+            .visible_saves()
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, s)| s.age())
+            .map(|(idx, _)| idx);
+        if let Some(idx) = newest {
+            self.selected = idx;
+        }
     }
 
-    for backup_file in backup_files {
-        map.entry(key(&backup_file))
-            .and_modify(|s| {
-                if let Save::OriginalFileOnly(p) = s {
-                    *s = Save::Both(p.clone(), backup_file.clone());
+    pub fn selected_save(&self) -> Option<&Save> {
+        self.visible_saves().get(self.selected).copied()
+    }
+
+    fn clamp_selection(&mut self) {
+        let count = self.visible_saves().len();
+        self.selected = self.selected.min(count.saturating_sub(1));
+    }
+
+    /// The saves shown in the list, in display order. Saves we can't read a difficulty
+    /// from are never hidden by the difficulty filter.
+    pub fn visible_saves(&self) -> Vec<&Save> {
+        // the state is always newest first
+        let mut saves: Vec<_> = self
+            .state
+            .saves
+            .iter()
+            .filter(|s| match s.difficulty() {
+                Some(d) if !self.difficulty_filter.is_empty() => {
+                    self.difficulty_filter.contains(&d)
                 }
+                _ => true,
             })
-            .or_insert(Save::BackupFileOnly(backup_file));
+            .filter(|s| match self.kind_filter {
+                KindFilter::All => true,
+                KindFilter::Saves => !s.is_recording(),
+                KindFilter::Recordings => s.is_recording(),
+            })
+            .filter(|s| self.matches_search(s))
+            .collect();
+        if !self.sort_descending {
+            saves.reverse();
+        }
+        saves
     }
 
-    let mut saves: Vec<Save> = map.into_values().collect();
-    saves.sort_by_key(|x| x.sort_by());
+    fn matches_search(&self, save: &Save) -> bool {
+        if self.search.is_empty() {
+            return true;
+        }
+        let search = self.search.to_lowercase();
+        let key = save.key();
+        key.to_lowercase().contains(&search)
+            || self
+                .notes
+                .get(&key)
+                .is_some_and(|note| note.to_lowercase().contains(&search))
+    }
 
-    // pop in a couple of test values
-    // saves.push(Save::SaveOnly(PathBuf::from("save-only.broguesave")));
-    // saves.push(Save::BackupOnly(PathBuf::from("backup-only.broguesave")));
+    pub fn toggle_log(&mut self) {
+        if self.log_tail.is_some() {
+            self.hide_log();
+        } else {
+            self.log_tail = Some(vec![]);
+            self.refresh_log();
+        }
+    }
 
-    Ok(State { saves })
-}
+    pub fn hide_log(&mut self) {
+        self.log_tail = None;
+        self.log_scroll = 0;
+    }
 
-fn files(dir: &Path) -> Result<Vec<PathBuf>> {
-    let mut res = vec![];
-    for entry in std::fs::read_dir(dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        if is_brogue_save(&path) {
-            res.push(path);
+    /// The lines of the log the log pane shows, if it's open: the last few, unless it's been
+    /// scrolled back.
+    pub fn log_tail(&self) -> Option<&[String]> {
+        let lines = self.log_tail.as_deref()?;
+        let end = lines.len().saturating_sub(self.log_scroll);
+        Some(&lines[end.saturating_sub(LOG_TAIL_LINES)..end])
+    }
+
+    /// Scrolls the log pane a page back, pausing it, or a page forward, following the log
+    /// again once back at the newest line.
+    pub fn scroll_log(&mut self, back: bool) {
+        let lines = match &self.log_tail {
+            Some(lines) => lines.len(),
+            None => return,
+        };
+        let max = lines.saturating_sub(LOG_TAIL_LINES);
+        self.log_scroll = if back {
+            (self.log_scroll + LOG_TAIL_LINES).min(max)
+        } else {
+            self.log_scroll.saturating_sub(LOG_TAIL_LINES)
+        };
+        self.refresh_log();
+    }
+
+    /// Whether the log pane is scrolled back, and so not showing new lines.
+    pub fn is_log_paused(&self) -> bool {
+        self.log_scroll > 0
+    }
+
+    fn refresh_log(&mut self) {
+        if self.log_tail.is_none() || self.is_log_paused() {
+            return;
         }
+        self.log_tail = Some(match tail(&self.config.log_file, LOG_HISTORY_LINES) {
+            Ok(lines) => lines,
+            Err(e) => vec![format!(
+                "could not read {}: {}",
+                self.config.log_file.display(),
+                e
+            )],
+        });
     }
-    Ok(res)
-}
 
-fn is_brogue_save(path: &Path) -> bool {
-    !path.is_dir()
-        && path.extension().unwrap_or_default() == OsStr::new("broguesave")
-        && path
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string()
-            .starts_with("Saved")
+    /// The save dir, if we're still waiting for brogue to create it.
+    pub fn awaited_save_dir(&self) -> Option<&Path> {
+        self.waiting_for_save_dir.then_some(self.save_dir.as_path())
+    }
+
+    fn log_scan(&mut self, elapsed: Duration, scanned: usize) {
+        debug!(
+            "[SCAN] files={} elapsed_ms={}",
+            scanned,
+            elapsed.as_millis()
+        );
+        if elapsed < SLOW_SCAN || self.warned_slow_scan {
+            return;
+        }
+        let advice = if self.config.force_poll {
+            "polling makes this worse; try without force_poll to use change events"
+        } else {
+            "raising reconcile_interval_ms will scan less often"
+        };
+        warn!(
+            "[SCAN] scanning {} files took {}ms; {}",
+            scanned,
+            elapsed.as_millis(),
+            advice
+        );
+        self.warned_slow_scan = true;
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Switches to the next sync mode for the rest of the session; the next reconcile uses
+    /// it. The config file is left alone.
+    pub fn cycle_mode(&mut self) {
+        self.config.mode = self.config.mode.next();
+        info!("sync mode is now {}", self.config.mode);
+        self.set_status(format!("sync mode: {}", self.config.mode));
+    }
+
+    pub fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    pub fn verification(&self, save: &Save) -> Verification {
+        self.verifier.status(save)
+    }
+
+    /// A backup that's sat unrestored for a while suggests restores aren't landing where
+    /// brogue looks, e.g. because the save dir is misconfigured.
+    pub fn is_stale_backup(&self, save: &Save) -> bool {
+        matches!(save, Save::BackupFileOnly(_))
+            && save.age() > Duration::from_secs(self.config.stale_backup_secs)
+    }
+
+    /// A live save bigger than `max_save_bytes` is left alone rather than filling the backup
+    /// volume.
+    pub fn is_oversized(&self, save: &Save) -> bool {
+        match save {
+            Save::OriginalFileOnly(live) | Save::Both(live, _) => self.is_too_big(live),
+            Save::BackupFileOnly(_) => false,
+        }
+    }
+
+    /// Whether brogue looks to be in the middle of writing the live copy of `save`, in which
+    /// case syncing it waits until it's settled.
+    pub fn is_writing(&self, save: &Save) -> bool {
+        match save {
+            Save::OriginalFileOnly(live) | Save::Both(live, _) => self.is_being_written(live),
+            Save::BackupFileOnly(_) => false,
+        }
+    }
+
+    fn is_being_written(&self, path: &Path) -> bool {
+        if !self.config.auto_defer_active_writes {
+            return false;
+        }
+        let settle = Duration::from_millis(self.config.write_settle_ms);
+        path.metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            // an mtime in the future is a clock problem, not a write; don't wait on it forever
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|since| since < settle)
+    }
+
+    fn is_too_big(&self, path: &Path) -> bool {
+        path.metadata()
+            .is_ok_and(|m| m.len() > self.config.max_save_bytes)
+    }
+
+    pub fn set_status(&mut self, message: impl Into<String>) {
+        self.status_message = Some((message.into(), Instant::now()));
+    }
+
+    pub fn status(&self) -> Option<&str> {
+        match &self.status_message {
+            Some((message, at)) if at.elapsed() < STATUS_MESSAGE_WINDOW => Some(message),
+            _ => None,
+        }
+    }
+
+    pub fn start_delete(&mut self) {
+        if !self.config.allow_delete {
+            return;
+        }
+        if let Some(version) = self.selected_version().map(Path::to_path_buf) {
+            self.delete_version(&version);
+            return;
+        }
+
+        if self.visible_saves().is_empty() {
+            self.set_status("nothing to delete");
+            self.delete_state = DeleteState::NotDeleting;
+        } else {
+            self.delete_state = DeleteState::AwaitingIndex;
+        }
+    }
+
+    pub fn choose_delete(&mut self, idx: usize) {
+        let count = self.visible_saves().len();
+        if idx < count && self.is_pinned(self.visible_saves()[idx]) {
+            self.delete_state = DeleteState::NotDeleting;
+            self.set_status(format!(
+                "'{}' is pinned; press {} to unpin it first",
+                self.delete_label(idx),
+                self.keymap.key(Action::Pin)
+            ));
+        } else if idx < count {
+            let last_copy = matches!(self.visible_saves()[idx], Save::BackupFileOnly(_));
+            self.delete_state = if last_copy && self.config.protect_last_copy {
+                DeleteState::ConfirmLastCopy(idx)
+            } else {
+                DeleteState::Delete(idx)
+            };
+        } else {
+            self.set_status(format!("no save '{}'", self.delete_label(idx)));
+        }
+    }
+
+    /// Picks the selected save to delete; the way to delete one listed past 'z'.
+    pub fn choose_selected_delete(&mut self) {
+        self.choose_delete(self.selected);
+    }
+
+    /// What to call the save at `idx` when deleting it: its letter, or its name when it's
+    /// listed past 'z'.
+    pub fn delete_label(&self, idx: usize) -> String {
+        match (crate::ui::letter(idx), self.visible_saves().get(idx)) {
+            (Some(letter), _) => letter.to_string(),
+            (None, Some(save)) => save.key(),
+            (None, None) => idx.to_string(),
+        }
+    }
+
+    /// Answers the "last copy" question: deletes the save if `yes`, otherwise keeps it.
+    pub fn confirm_delete(&mut self, yes: bool) {
+        if let DeleteState::ConfirmLastCopy(idx) = self.delete_state {
+            if yes {
+                self.delete_state = DeleteState::Delete(idx);
+            } else {
+                self.delete_state = DeleteState::NotDeleting;
+                self.set_status("kept the last copy");
+            }
+        }
+    }
+
+    pub fn is_pinned(&self, save: &Save) -> bool {
+        self.pins.contains(&save.key())
+    }
+
+    /// Pins or unpins the selected save.
+    pub fn toggle_pin(&mut self) {
+        let key = match self.selected_save() {
+            Some(save) => save.key(),
+            None => return,
+        };
+        match self.pins.toggle(&key) {
+            Ok(true) => self.set_status(format!("pinned {}", key)),
+            Ok(false) => self.set_status(format!("unpinned {}", key)),
+            Err(e) => self.set_status(format!("could not save pins: {}", e)),
+        }
+    }
+
+    pub fn note(&self, save: &Save) -> Option<&str> {
+        self.notes.get(&save.key())
+    }
+
+    /// Asks for a note on the selected save, starting from the one it has.
+    pub fn start_note(&mut self) {
+        let key = match self.selected_save() {
+            Some(save) => save.key(),
+            None => {
+                self.set_status("nothing to add a note to");
+                return;
+            }
+        };
+        let input = self.notes.get(&key).unwrap_or_default().to_string();
+        self.prompt = Some(Prompt::Note { key, input });
+    }
+
+    /// Asks for the seed of a save to jump to.
+    pub fn start_go_to_seed(&mut self) {
+        self.prompt = Some(Prompt::GoToSeed {
+            input: String::new(),
+        });
+    }
+
+    /// Selects the shown save whose seed is `input`, or failing that the first whose seed
+    /// starts with it. Turns off follow, which would only move the selection straight back.
+    fn go_to_seed(&mut self, input: &str) {
+        let seeds: Vec<_> = self
+            .visible_saves()
+            .iter()
+            .map(|s| s.metadata().map(|m| m.seed.to_string()))
+            .collect();
+        let found = seeds
+            .iter()
+            .position(|seed| seed.as_deref() == Some(input))
+            .or_else(|| {
+                seeds
+                    .iter()
+                    .position(|seed| seed.as_ref().is_some_and(|s| s.starts_with(input)))
+            });
+        match found {
+            Some(idx) => {
+                self.selected = idx;
+                self.follow = false;
+            }
+            None => self.set_status(format!("no save with seed {} shown", input)),
+        }
+    }
+
+    /// Asks for text to narrow the list down to, starting from the current search.
+    pub fn start_search(&mut self) {
+        let input = self.search.clone();
+        self.prompt = Some(Prompt::Search { input });
+    }
+
+    /// Picks the selected save for comparison; picking a second one shows them side by side.
+    pub fn pick_for_compare(&mut self) {
+        let key = match self.selected_save() {
+            Some(save) => save.key(),
+            None => return,
+        };
+        self.compare = match std::mem::replace(&mut self.compare, Compare::Off) {
+            Compare::Picked(first) if first == key => {
+                self.set_status("pick a different save to compare with");
+                Compare::Picked(first)
+            }
+            Compare::Picked(first) => Compare::Showing(first, key),
+            Compare::Off | Compare::Showing(_, _) => {
+                self.set_status(format!(
+                    "comparing {}; select another save and press {}",
+                    key,
+                    self.keymap.key(Action::Compare)
+                ));
+                Compare::Picked(key)
+            }
+        };
+    }
+
+    /// The two saves being compared, if both still exist.
+    pub fn compared_saves(&self) -> Option<(&Save, &Save)> {
+        match &self.compare {
+            Compare::Showing(a, b) => Some((self.save_by_key(a)?, self.save_by_key(b)?)),
+            _ => None,
+        }
+    }
+
+    fn save_by_key(&self, key: &str) -> Option<&Save> {
+        self.state.saves.iter().find(|s| s.key() == key)
+    }
+
+    /// Deletes one of the two saves being compared: the first if `first` is set, otherwise
+    /// the second.
+    pub fn delete_compared(&mut self, first: bool) {
+        let save = match self.compared_saves() {
+            Some((a, b)) => {
+                if first {
+                    a.clone()
+                } else {
+                    b.clone()
+                }
+            }
+            None => return,
+        };
+        self.compare = Compare::Off;
+
+        if !self.config.allow_delete {
+            self.set_status("deleting is disabled");
+        } else if self.is_pinned(&save) {
+            self.set_status(format!(
+                "{} is pinned; press {} to unpin it first",
+                save.key(),
+                self.keymap.key(Action::Pin)
+            ));
+        } else {
+            match self.delete(&save) {
+                Ok(()) => {
+                    info!("deleted {} after comparing", save.key());
+                    if self.config.git_commit {
+                        self.commit_history(&format!("delete: {}", save.key()));
+                    }
+                    self.set_status(format!(
+                        "deleted {} (press {} to undo)",
+                        save.key(),
+                        self.keymap.key(Action::Undo)
+                    ));
+                }
+                Err(e) => self.set_status(format!("could not delete {}: {}", save.key(), e)),
+            }
+        }
+    }
+
+    /// Compares the selected save with its backup, for the details pane.
+    pub fn toggle_diff(&mut self) {
+        if self.diff.take().is_some() {
+            return;
+        }
+
+        let save = match self.selected_save() {
+            Some(save) => save,
+            None => return,
+        };
+        match save.diff_summary(self.cipher.as_deref()) {
+            Some(diff) => self.diff = Some((save.key(), diff)),
+            None => self.set_status("only saves that are in both places can be compared"),
+        }
+    }
+
+    /// Asks where to export the selected save, suggesting the desktop.
+    pub fn start_export(&mut self) {
+        let source = match self.selected_save() {
+            Some(save) => save.path().to_path_buf(),
+            None => {
+                self.set_status("nothing to export");
+                return;
+            }
+        };
+
+        let input = dirs::home_dir()
+            .map(|home| home.join("Desktop").join(plain_name(&source)))
+            .map(|path| path.to_string_lossy().to_string())
+            .unwrap_or_default();
+        self.prompt = Some(Prompt::ExportPath { source, input });
+    }
+
+    /// Asks where to restore the selected save to, so a backup can be got back without
+    /// touching the save dir while brogue is running. Suggests `restored/` in the backup dir.
+    pub fn start_restore_to(&mut self) {
+        let key = match self.selected_save() {
+            Some(save @ (Save::BackupFileOnly(_) | Save::Both(_, _))) => save.key(),
+            Some(Save::OriginalFileOnly(_)) => {
+                self.set_status("not backed up yet; nothing to restore");
+                return;
+            }
+            None => {
+                self.set_status("nothing to restore");
+                return;
+            }
+        };
+
+        let input = self
+            .backup_dir
+            .join(RESTORED_DIR)
+            .to_string_lossy()
+            .to_string();
+        let version = self.selected_version().map(Path::to_path_buf);
+        self.prompt = Some(Prompt::RestorePath {
+            key,
+            version,
+            input,
+        });
+    }
+
+    /// Asks for a comment to add to the selected save's name, e.g. "almost won". Brogue only
+    /// cares about the start of the name, so the comment goes on the end in square brackets.
+    pub fn start_rename(&mut self) {
+        let key = match self.selected_save() {
+            Some(save) => save.key(),
+            None => {
+                self.set_status("nothing to rename");
+                return;
+            }
+        };
+
+        let stem = Path::new(&key)
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let input = split_comment(&stem).1.unwrap_or_default().to_string();
+        self.prompt = Some(Prompt::Rename { key, input });
+    }
+
+    /// Sets the comment on the save called `key`, renaming it in both dirs along with its
+    /// checksum, older versions, pin and note. Returns the new name.
+    fn rename(&mut self, key: &str, comment: &str) -> Result<String> {
+        if let Some(c) = comment.chars().find(|c| "[]/\\\0".contains(*c)) {
+            return Err(AppError::InvalidComment(c));
+        }
+        let save = self
+            .save_by_key(key)
+            .cloned()
+            .ok_or_else(|| AppError::NoIntactBackup(key.to_string()))?;
+        // a long name is shortened in the backup dir, so the comment goes on the full name, and
+        // the backup takes the shortened form of that
+        let full = self.full_name(&save);
+        let renamed_full = with_comment(&full, comment);
+        let renamed = short_name(renamed_full.clone());
+        if renamed == key {
+            return Ok(renamed);
+        }
+        let taken = self.save_dir.join(&renamed_full).exists()
+            || backup_path(&self.backup_dir, &renamed).exists()
+            || encrypted_path(&backup_path(&self.backup_dir, &renamed)).exists();
+        if taken {
+            return Err(AppError::NameTaken(renamed));
+        }
+        self.long_names.record(&renamed, &renamed_full)?;
+
+        let paths = match &save {
+            Save::OriginalFileOnly(path) | Save::BackupFileOnly(path) => vec![path],
+            Save::Both(live, backup) => vec![live, backup],
+        };
+        for path in paths {
+            // keep any `.age` suffix
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+            let file_name = match file_name.strip_prefix(full.as_str()) {
+                Some(suffix) => format!("{}{}", renamed_full, suffix),
+                None => file_name.replacen(key, &renamed, 1),
+            };
+            let destination = path.with_file_name(file_name);
+            info!("[RENAME] {} => {}", path.display(), destination.display());
+            std::fs::rename(path, &destination)?;
+            if checksum_path(path).exists() {
+                std::fs::rename(checksum_path(path), checksum_path(&destination))?;
+            }
+        }
+
+        let versions_dir = self.backup_dir.join(VERSIONS_DIR);
+        if versions_dir.join(key).exists() {
+            std::fs::rename(versions_dir.join(key), versions_dir.join(&renamed))?;
+        }
+        if self.pins.contains(key) {
+            self.pins.toggle(key)?;
+            self.pins.toggle(&renamed)?;
+        }
+        self.notes.rename(key, &renamed)?;
+        Ok(renamed)
+    }
+
+    pub fn prompt_char(&mut self, c: char) {
+        match &mut self.prompt {
+            Some(Prompt::ExportPath { input, .. })
+            | Some(Prompt::RestorePath { input, .. })
+            | Some(Prompt::Rename { input, .. })
+            | Some(Prompt::Note { input, .. })
+            | Some(Prompt::Search { input }) => input.push(c),
+            Some(Prompt::GoToSeed { input }) if c.is_ascii_digit() => input.push(c),
+            Some(Prompt::GoToSeed { .. }) => {}
+            Some(Prompt::ConfirmOverwrite {
+                source,
+                destination,
+            }) => {
+                if c == 'y' {
+                    let (source, destination) = (source.clone(), destination.clone());
+                    self.prompt = None;
+                    self.export(&source, &destination, true);
+                } else {
+                    self.prompt = None;
+                    self.set_status("export cancelled");
+                }
+            }
+            Some(Prompt::Recover { found, .. }) => {
+                let found = std::mem::take(found);
+                self.prompt = None;
+                if c == 'y' && !found.is_empty() {
+                    self.recover(&found);
+                }
+            }
+            Some(Prompt::ResolveDiverged { resolutions }) => {
+                let resolutions = std::mem::take(resolutions);
+                self.prompt = None;
+                if c == 'y' {
+                    self.resolve_diverged(&resolutions);
+                } else {
+                    self.set_status("left the diverged saves alone");
+                }
+            }
+            None => {}
+        }
+    }
+
+    pub fn prompt_backspace(&mut self) {
+        if let Some(Prompt::ExportPath { input, .. })
+        | Some(Prompt::RestorePath { input, .. })
+        | Some(Prompt::Rename { input, .. })
+        | Some(Prompt::Note { input, .. })
+        | Some(Prompt::Search { input })
+        | Some(Prompt::GoToSeed { input }) = &mut self.prompt
+        {
+            input.pop();
+        }
+    }
+
+    pub fn submit_prompt(&mut self) {
+        match self.prompt.take() {
+            Some(Prompt::ExportPath { source, input }) => {
+                let destination = expand_home(&input);
+                if destination.exists() {
+                    self.prompt = Some(Prompt::ConfirmOverwrite {
+                        source,
+                        destination,
+                    });
+                } else {
+                    self.export(&source, &destination, false);
+                }
+            }
+            Some(Prompt::RestorePath {
+                key,
+                version,
+                input,
+            }) => {
+                let dir = expand_home(&input);
+                let result = match (self.save_by_key(&key), version) {
+                    (_, Some(version)) => self.copy_into(&version, &self.full_name_of(&key), &dir),
+                    (Some(save), None) => self.restore_to(save, &dir),
+                    (None, None) => Err(AppError::NoIntactBackup(key)),
+                };
+                match result {
+                    Ok((restored, true)) => {
+                        self.set_status(format!("restored to {}", restored.display()));
+                        self.push_undo(ReversibleAction::Copied(restored));
+                    }
+                    Ok((restored, false)) => {
+                        self.set_status(format!("already restored to {}", restored.display()))
+                    }
+                    Err(e) => self.set_status(format!("restore failed: {}", e)),
+                }
+            }
+            Some(Prompt::Rename { key, input }) => match self.rename(&key, input.trim()) {
+                Ok(renamed) if renamed == key => {}
+                Ok(renamed) => {
+                    self.set_status(format!("renamed to {}", renamed));
+                    self.push_undo(ReversibleAction::Rename {
+                        from: key,
+                        to: renamed,
+                    });
+                }
+                Err(e) => self.set_status(format!("rename failed: {}", e)),
+            },
+            Some(Prompt::Note { key, input }) => {
+                if let Err(e) = self.notes.set(&key, input.trim()) {
+                    self.set_status(format!("could not save notes: {}", e));
+                }
+            }
+            Some(Prompt::Search { input }) => {
+                self.search = input.trim().to_string();
+                self.clamp_selection();
+            }
+            Some(Prompt::GoToSeed { input }) if input.is_empty() => {}
+            Some(Prompt::GoToSeed { input }) => self.go_to_seed(&input),
+            other => self.prompt = other,
+        }
+    }
+
+    fn export(&mut self, source: &Path, destination: &Path, overwrite: bool) {
+        let result = if overwrite {
+            Self::rm(destination).and_then(|_| self.cp(source, destination))
+        } else {
+            self.cp(source, destination)
+        };
+
+        match result {
+            Ok(_) => {
+                info!(
+                    "[EXPORT] copied {} => {}",
+                    source.display(),
+                    destination.display()
+                );
+                self.push_undo(if overwrite {
+                    ReversibleAction::Irreversible(format!("overwriting {}", destination.display()))
+                } else {
+                    ReversibleAction::Copied(destination.to_path_buf())
+                });
+                self.set_status(format!("exported to {}", destination.display()));
+            }
+            Err(e) => self.set_status(format!("export failed: {}", e)),
+        }
+    }
+
+    /// Totals for the stats popup, over every save regardless of the difficulty filter.
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats {
+            saves: self.state.saves.len(),
+            actions: self.session_actions.clone(),
+            copy_bytes_per_sec: self.throughput.bytes_per_sec(),
+            ..Stats::default()
+        };
+        for save in &self.state.saves {
+            *stats.by_difficulty.entry(save.difficulty()).or_default() += 1;
+
+            let backup = match save {
+                Save::BackupFileOnly(backup) | Save::Both(_, backup) => backup,
+                Save::OriginalFileOnly(_) => continue,
+            };
+            let metadata = match backup.metadata() {
+                Ok(metadata) => metadata,
+                Err(_) => continue,
+            };
+            stats.backup_bytes += metadata.len();
+            if let Ok(modified) = metadata.modified() {
+                let entry = (save.key(), modified);
+                if stats
+                    .oldest_backup
+                    .as_ref()
+                    .is_none_or(|(_, t)| modified < *t)
+                {
+                    stats.oldest_backup = Some(entry.clone());
+                }
+                if stats
+                    .newest_backup
+                    .as_ref()
+                    .is_none_or(|(_, t)| modified > *t)
+                {
+                    stats.newest_backup = Some(entry);
+                }
+            }
+        }
+        stats
+    }
+
+    /// What was done to this save in the last few seconds, and how long ago.
+    pub fn recent_action(&self, save: &Save) -> Option<(ActionKind, Duration)> {
+        let (kind, at) = self.last_actions.get(&save.key())?;
+        let elapsed = at.elapsed();
+        (elapsed < RECENT_ACTION_WINDOW).then_some((*kind, elapsed))
+    }
+
+    /// Copies `from` to `to` unless `to` already exists; returns whether anything was copied.
+    fn cp(&self, from: &Path, to: &Path) -> Result<bool> {
+        let _span = trace_span!("cp", from = %from.display(), to = %to.display()).entered();
+        let started = Instant::now();
+        let copied = copy_new(from, to, self.cipher.as_deref(), self.throttle.as_ref())?;
+        if copied {
+            self.record_copy(from, started.elapsed());
+        } else {
+            trace!("already there; nothing copied");
+        }
+        Ok(copied)
+    }
+
+    fn copy_atomic(&self, from: &Path, to: &Path) -> Result<()> {
+        let started = Instant::now();
+        copy_atomic(from, to, self.cipher.as_deref(), self.throttle.as_ref())?;
+        self.record_copy(from, started.elapsed());
+        Ok(())
+    }
+
+    fn record_copy(&self, from: &Path, took: Duration) {
+        if let Ok(metadata) = from.metadata() {
+            self.throughput.record(metadata.len(), took);
+        }
+    }
+
+    fn recording_changed(&self, live: &Path, backup: &Path) -> Result<bool> {
+        if is_encrypted(backup) {
+            Ok(self.hashes.hash(live)? != self.hashes.hash(backup)?)
+        } else {
+            Ok(live.metadata()?.len() != backup.metadata()?.len())
+        }
+    }
+
+    /// Brings an existing copy up to date. Recordings only ever grow, so when `to` is a prefix
+    /// of `from` we just append the new tail; anything else gets a full copy. Returns whether
+    /// anything was written.
+    fn copy_delta(&self, from: &Path, to: &Path) -> Result<bool> {
+        // there's no appending to an encrypted file, so re-encrypt the lot if it's changed
+        if is_encrypted(to) {
+            if self.hashes.hash(from)? == self.hashes.hash(to)? {
+                return Ok(false);
+            }
+            self.copy_atomic(from, to)?;
+            return Ok(true);
+        }
+
+        let from_len = from.metadata()?.len();
+        let to_len = to.metadata()?.len();
+        if from_len == to_len {
+            return Ok(false);
+        }
+
+        if to_len < from_len && is_prefix(to, from)? {
+            let mut source = std::fs::File::open(from)?;
+            source.seek(SeekFrom::Start(to_len))?;
+            let mut destination = std::fs::OpenOptions::new().append(true).open(to)?;
+            match &self.throttle {
+                Some(throttle) => throttle.copy(&mut source, &mut destination)?,
+                None => std::io::copy(&mut source, &mut destination)?,
+            };
+        } else {
+            self.copy_atomic(from, to)?;
+        }
+        Ok(true)
+    }
+
+    fn commit_history(&self, message: &str) {
+        match history::commit(&self.backup_dir, message) {
+            Ok(true) => info!("[GIT] committed '{}'", message.lines().next().unwrap_or("")),
+            Ok(false) => {}
+            Err(e) => warn!("[GIT] could not commit backups: {}", e.message()),
+        }
+    }
+
+    /// Deletes a save from both the save and backup dirs. The backup, or the live save if
+    /// there's no backup, goes to `trash/` rather than being removed, so 'u' can put it back.
+    fn delete(&mut self, save: &Save) -> Result<()> {
+        let original = match save {
+            Save::OriginalFileOnly(path) | Save::BackupFileOnly(path) => path,
+            Save::Both(live, backup) if self.config.protect_last_copy => {
+                // keep the backup out of the way of the sync, which would otherwise restore it
+                Self::rm(live)?;
+                let archived = Self::move_backup(backup, &self.backup_dir, ARCHIVE_DIR)?;
+                info!(
+                    "[DELETE] removed {} from the save dir; its backup is in {}",
+                    save.key(),
+                    archived.display()
+                );
+                self.push_undo(ReversibleAction::Delete {
+                    key: save.key(),
+                    moved: vec![(backup.clone(), archived)],
+                });
+                return Ok(());
+            }
+            Save::Both(live, backup) => {
+                // the backup is enough to put it back; the sync restores the live save from it
+                Self::rm(live)?;
+                backup
+            }
+        };
+        let trashed = Self::move_backup(original, &self.backup_dir, TRASH_DIR)?;
+        info!("[DELETE] moved {} to the trash", save.key());
+        self.push_undo(ReversibleAction::Delete {
+            key: save.key(),
+            moved: vec![(original.clone(), trashed)],
+        });
+        Ok(())
+    }
+
+    // an older version is never the last copy, so it goes straight to the trash
+    fn delete_version(&mut self, version: &Path) {
+        let name = version
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        match Self::move_backup(version, &self.backup_dir, TRASH_DIR) {
+            Ok(trashed) => {
+                info!("[DELETE] moved the version {} to the trash", name);
+                self.push_undo(ReversibleAction::Delete {
+                    key: name.clone(),
+                    moved: vec![(version.to_path_buf(), trashed)],
+                });
+                self.selected_version = None;
+                self.set_status(format!(
+                    "deleted {} (press {} to undo)",
+                    name,
+                    self.keymap.key(Action::Undo)
+                ));
+                if self.config.git_commit {
+                    self.commit_history(&format!("delete: {}", name));
+                }
+            }
+            Err(e) => self.set_status(format!("could not delete {}: {}", name, e)),
+        }
+    }
+
+    /// How many more saves than `max_live_saves` are in the save dir; 0 if it's within the
+    /// limit or there isn't one. Recordings don't count.
+    pub fn excess_live_saves(&self) -> usize {
+        if self.config.max_live_saves == 0 {
+            return 0;
+        }
+        let live = self
+            .state
+            .saves
+            .iter()
+            .filter(|s| !matches!(s, Save::BackupFileOnly(_)) && !s.is_recording())
+            .count();
+        live.saturating_sub(self.config.max_live_saves)
+    }
+
+    /// Archives the oldest saves until the save dir is back within `max_live_saves`: each
+    /// backup goes into `archive/` and the live copy is removed, so brogue stops listing it.
+    /// Pinned saves, and those not backed up yet, are left alone.
+    pub fn archive_oldest(&mut self) {
+        let excess = self.excess_live_saves();
+        if excess == 0 {
+            self.set_status("the save dir isn't over max_live_saves; nothing to archive");
+            return;
+        }
+
+        // the state is newest first
+        let oldest: Vec<_> = self
+            .state
+            .saves
+            .iter()
+            .rev()
+            .filter(|s| !s.is_recording() && !self.is_pinned(s) && !self.is_writing(s))
+            .filter_map(|s| match s {
+                Save::Both(live, backup) => Some((live.clone(), backup.clone())),
+                _ => None,
+            })
+            .take(excess)
+            .collect();
+
+        let mut moved = vec![];
+        let mut error = None;
+        for (live, backup) in oldest {
+            match Self::rm(&live)
+                .and_then(|_| Self::move_backup(&backup, &self.backup_dir, ARCHIVE_DIR))
+            {
+                Ok(archived) => {
+                    info!("[ARCHIVE] {} => {}", live.display(), archived.display());
+                    moved.push((backup, archived));
+                }
+                Err(e) => {
+                    error = Some(e);
+                    break;
+                }
+            }
+        }
+
+        let count = moved.len();
+        if count > 0 {
+            if self.config.git_commit {
+                self.commit_history(&format!("archive: {} oldest saves", count));
+            }
+            self.push_undo(ReversibleAction::Archive { moved });
+        }
+        match error {
+            None => self.set_status(format!(
+                "archived {} saves (press {} to undo)",
+                count,
+                self.keymap.key(Action::Undo)
+            )),
+            Some(e) => self.set_status(format!("archived {} saves, then failed: {}", count, e)),
+        }
+    }
+
+    fn push_undo(&mut self, action: ReversibleAction) {
+        if self.undo.len() == UNDO_LIMIT {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(action);
+    }
+
+    /// Takes back the most recent change the user made. One that can't be reversed is
+    /// reported and dropped, so the next 'u' gets to the one before.
+    pub fn undo(&mut self) {
+        let action = match self.undo.pop_back() {
+            Some(action) => action,
+            None => {
+                self.set_status("nothing to undo");
+                return;
+            }
+        };
+        let result = match &action {
+            ReversibleAction::Delete { moved, .. } | ReversibleAction::Archive { moved } => moved
+                .iter()
+                .try_for_each(|(original, trashed)| Self::reinstate(trashed, original)),
+            ReversibleAction::Rename { from, to } => {
+                let from = self.full_name_of(from);
+                let stem = Path::new(&from)
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy();
+                let comment = split_comment(&stem).1.unwrap_or_default().to_string();
+                self.rename(to, &comment).map(|_| ())
+            }
+            ReversibleAction::Copied(path) => Self::rm(path),
+            ReversibleAction::Irreversible(_) => {
+                self.set_status(format!("can't undo {}", action));
+                return;
+            }
+        };
+        match result {
+            Ok(()) => {
+                info!("[UNDO] undid {}", action);
+                if self.config.git_commit {
+                    self.commit_history(&format!("undo: {}", action));
+                }
+                self.set_status(format!("undid {}", action));
+            }
+            Err(e) => self.set_status(format!("could not undo {}: {}", action, e)),
+        }
+    }
+
+    // moves a file and its checksum back from the trash
+    fn reinstate(trashed: &Path, original: &Path) -> Result<()> {
+        if original.exists() {
+            return Err(AppError::NameTaken(key(original)));
+        }
+        move_file(trashed, original)?;
+        if checksum_path(trashed).exists() {
+            move_file(&checksum_path(trashed), &checksum_path(original))?;
+        }
+        Ok(())
+    }
+
+    fn rm(path: &Path) -> Result<()> {
+        let _span = trace_span!("rm", path = %path.display()).entered();
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+
+        let checksum = checksum_path(path);
+        if checksum.exists() {
+            std::fs::remove_file(checksum)?;
+        }
+
+        Ok(())
+    }
+
+    /// Moves a backup and its checksum into a subfolder of the backup dir, e.g. `archive/`.
+    /// Brogue reuses names, so one that's already there is kept and this one is numbered; see
+    /// `numbered_name`. Returns where it went.
+    fn move_backup(backup: &Path, backup_dir: &Path, subdir: &str) -> Result<PathBuf> {
+        let dir = backup_dir.join(subdir);
+        if !dir.exists() {
+            std::fs::create_dir_all(&dir)?;
+        }
+
+        let file_name = backup.file_name().unwrap_or_default().to_string_lossy();
+        let mut moved = dir.join(file_name.as_ref());
+        let mut n = 2;
+        while moved.exists() || checksum_path(&moved).exists() {
+            moved = dir.join(numbered_name(&file_name, n));
+            n += 1;
+        }
+        move_file(backup, &moved)?;
+        let checksum = checksum_path(backup);
+        if checksum.exists() {
+            move_file(&checksum, &checksum_path(&moved))?;
+        }
+        Ok(moved)
+    }
+
+    /// Finds backup-only saves with identical contents under different names, keeps the most
+    /// recently modified of each set and trashes or removes the rest. Returns whether anything
+    /// was removed. A backup that can't be read or removed is logged and left alone, so it
+    /// doesn't hold up the rest of the sync.
+    fn dedup(&self) -> bool {
+        let mut by_hash: HashMap<u64, Vec<&Save>> = HashMap::new();
+        for save in &self.state.saves {
+            if let Save::BackupFileOnly(backup) = save {
+                match self.hashes.hash(backup) {
+                    Ok(hash) => by_hash.entry(hash).or_default().push(save),
+                    Err(e) => warn!("[DEDUP] skipping {}: {}", save.key(), e),
+                }
+            }
+        }
+
+        let mut removed = false;
+        for mut duplicates in by_hash.into_values().filter(|saves| saves.len() > 1) {
+            duplicates.sort_by_key(|s| s.age());
+            let kept = duplicates[0];
+            for duplicate in &duplicates[1..] {
+                if self.is_pinned(duplicate) {
+                    continue;
+                }
+                // the hashes only say they're probably the same, and a collision mustn't cost
+                // a save
+                match same_contents(kept.path(), duplicate.path(), self.cipher.as_deref()) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        warn!(
+                            "[DEDUP] {} hashes the same as {} but differs; keeping both",
+                            duplicate.key(),
+                            kept.key()
+                        );
+                        continue;
+                    }
+                    Err(e) => {
+                        warn!(
+                            "[DEDUP] could not compare {} with {}: {}",
+                            duplicate.key(),
+                            kept.key(),
+                            e
+                        );
+                        continue;
+                    }
+                }
+                info!(
+                    "[DEDUP] {} has the same contents as {}; removing it ({:?})",
+                    duplicate.key(),
+                    kept.key(),
+                    self.config.dedup
+                );
+                let result = match self.config.dedup {
+                    DedupMode::Off => continue,
+                    DedupMode::Trash => {
+                        Self::move_backup(duplicate.path(), &self.backup_dir, TRASH_DIR).map(|_| ())
+                    }
+                    DedupMode::Remove => Self::rm(duplicate.path()),
+                };
+                match result {
+                    Ok(()) => removed = true,
+                    Err(e) => warn!("[DEDUP] could not remove {}: {}", duplicate.key(), e),
+                }
+            }
+        }
+        removed
+    }
+
+    /// Moves backed-up recordings that are no longer in the save dir to the trash, keeping the
+    /// newest `keep_recordings` of them and any that are pinned. Returns whether anything was
+    /// moved.
+    fn prune_recordings(&self) -> Result<bool> {
+        let keep = match self.config.keep_recordings {
+            Some(keep) => keep,
+            None => return Ok(false),
+        };
+
+        // saves are sorted newest first
+        let old = self
+            .state
+            .saves
+            .iter()
+            .filter(|s| matches!(s, Save::BackupFileOnly(_)) && s.is_recording())
+            .filter(|s| !self.is_pinned(s))
+            .skip(keep);
+        let mut pruned = false;
+        for recording in old {
+            info!(
+                "[PRUNE] keeping only {} recordings; moving {} to {}",
+                keep,
+                recording.key(),
+                TRASH_DIR
+            );
+            Self::move_backup(recording.path(), &self.backup_dir, TRASH_DIR)?;
+            pruned = true;
+        }
+        Ok(pruned)
+    }
+
+    /// Moves finished games older than their difficulty's prune policy allows to the trash:
+    /// anything in `archive/`, and backup-only saves unless we're mirroring, when a backup-only
+    /// save is usually one brogue deleted on loading it and is waiting to be restored. Pinned
+    /// saves are kept, and one that can't be moved is logged and left for next time. Returns
+    /// whether anything was moved.
+    fn prune_games(&self) -> Result<bool> {
+        let mut finished: Vec<PathBuf> = if self.config.mode == SyncMode::Mirror {
+            vec![]
+        } else {
+            self.state
+                .saves
+                .iter()
+                .filter(|s| matches!(s, Save::BackupFileOnly(_)) && !s.is_recording())
+                .map(|s| s.path().to_path_buf())
+                .collect()
+        };
+        let archive_dir = self.backup_dir.join(ARCHIVE_DIR);
+        if archive_dir.is_dir() {
+            finished.extend(
+                files(&archive_dir)?
+                    .into_iter()
+                    .filter(|p| is_brogue_save(p)),
+            );
+        }
+
+        let mut pruned = false;
+        for game in finished {
+            let name = key(&game);
+            if self.pins.contains(&name) {
+                continue;
+            }
+            let difficulty = parse_save_name(&name).and_then(|m| m.difficulty);
+            let max_age = match self.config.prune_policy(difficulty).max_age_days {
+                Some(days) => Duration::from_secs(days * 24 * 60 * 60),
+                None => continue,
+            };
+            if age(&game) <= max_age {
+                continue;
+            }
+
+            info!(
+                "[PRUNE] {} is older than {} days; moving it to {}",
+                game.display(),
+                max_age.as_secs() / (24 * 60 * 60),
+                TRASH_DIR
+            );
+            match Self::move_backup(&game, &self.backup_dir, TRASH_DIR) {
+                Ok(_) => pruned = true,
+                Err(e) => warn!("[PRUNE] couldn't move {}: {}", game.display(), e),
+            }
+        }
+        Ok(pruned)
+    }
+
+    /// Restores the newest backup of `save` that's still intact, falling back through older
+    /// versions if the latest one is truncated or corrupt. If a different save of the same name
+    /// has turned up in the save dir since we looked, it's left alone: if it's newer than the
+    /// backup, nothing is restored, since that would only set the player back; otherwise the
+    /// backup is restored under a new name. Returns the backup used and where it went.
+    pub fn restore_best(&self, save: &Save) -> Result<(PathBuf, PathBuf)> {
+        let best = self.best_backup(save)?;
+        let key = self.full_name(save);
+        let mut destination = self.save_dir.join(&key);
+        let mut n = 1;
+        while destination.exists() {
+            if self.hashes.hash(&destination)? == self.hashes.hash(&best)? {
+                return Ok((best, destination));
+            }
+            // the next scan sees both and backs the live one up instead
+            if n == 1 && is_newer(&destination, &best)? {
+                return Err(AppError::NewerLiveSave(key));
+            }
+            destination = self.save_dir.join(conflict_name(&key, n));
+            n += 1;
+        }
+        if n > 1 {
+            warn!(
+                "[RESTORE] '{}' already exists and differs from its backup; restoring as '{}'",
+                key,
+                destination
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+            );
+        }
+        self.copy_atomic(&best, &destination)?;
+        Ok((best, destination))
+    }
+
+    /// Restores `save` into `dir` instead of the save dir, to move across by hand once brogue
+    /// is closed. A file already in `dir` is never replaced: a different one of the same name
+    /// gets the backup restored beside it under a new name, as `restore_best` does. Returns the
+    /// restored file, and whether it was copied rather than already there.
+    pub fn restore_to(&self, save: &Save, dir: &Path) -> Result<(PathBuf, bool)> {
+        let best = self.best_backup(save)?;
+        self.copy_into(&best, &self.full_name(save), dir)
+    }
+
+    // copies a backup or version of the save called `key` into `dir`, under that name unless
+    // something else has it
+    fn copy_into(&self, backup: &Path, key: &str, dir: &Path) -> Result<(PathBuf, bool)> {
+        std::fs::create_dir_all(dir)?;
+        let mut destination = dir.join(key);
+        let mut n = 1;
+        while destination.exists() {
+            if self.hashes.hash(&destination)? == self.hashes.hash(backup)? {
+                return Ok((destination, false));
+            }
+            destination = dir.join(conflict_name(key, n));
+            n += 1;
+        }
+        self.copy_atomic(backup, &destination)?;
+        info!(
+            "[RESTORE] copying {} => {}",
+            backup.display(),
+            destination.display()
+        );
+        Ok((destination, true))
+    }
+
+    // the newest backup of `save` that's intact, falling back to its copies in the extra backup
+    // dirs, then to older versions
+    fn best_backup(&self, save: &Save) -> Result<PathBuf> {
+        let key = save.key();
+        let mut candidates = vec![];
+        if let Save::BackupFileOnly(backup) | Save::Both(_, backup) = save {
+            candidates.push(backup.clone());
+            candidates.extend(self.extra_copies(backup));
+        }
+        candidates.extend(versions(&self.backup_dir, &key));
+
+        let best = candidates
+            .iter()
+            .position(|candidate| is_intact(candidate))
+            .ok_or_else(|| AppError::NoIntactBackup(key.clone()))?;
+        if best > 0 {
+            warn!(
+                "[RESTORE] newest backup of '{}' is damaged; using {} instead",
+                key,
+                candidates[best].display()
+            );
+        }
+
+        Ok(candidates.swap_remove(best))
+    }
+
+    /// Keeps the current backup as an older version, then backs up the changed live save.
+    /// A snapshot may already have kept the current backup, in which case it isn't kept again.
+    fn update_backup(&self, save: &Path, backup: &Path) -> Result<()> {
+        if self.is_kept_as_version(backup)? {
+            info!(
+                "[BACKUP] previous backup is already a version; copying {} => {}",
+                save.display(),
+                backup.display()
+            );
+        } else {
+            let version = store_version(backup, &self.backup_dir)?;
+            info!(
+                "[BACKUP] kept previous backup as {}; copying {} => {}",
+                version.display(),
+                save.display(),
+                backup.display()
+            );
+        }
+        self.copy_atomic(save, backup)?;
+        write_checksum(backup)
+    }
+
+    /// The saves in both dirs whose live copy and backup differ, with which copy
+    /// `keep_diverged` keeps of each. The sync brings a backup up to date when its live save is
+    /// newer, so these are mostly backups written since, e.g. from another machine. Recordings,
+    /// saves linked to their backups, and saves brogue is still writing are left out.
+    pub fn diverged(&self) -> Vec<(String, Side)> {
+        let mut diverged = vec![];
+        for save in &self.state.saves {
+            let (live, backup) = match save {
+                Save::Both(live, backup) if !save.is_recording() && !self.is_writing(save) => {
+                    (live, backup)
+                }
+                _ => continue,
+            };
+            if same_file(live, backup).unwrap_or(true) {
+                continue;
+            }
+            let differ = matches!(
+                (self.hashes.hash(live), self.hashes.hash(backup)),
+                (Ok(a), Ok(b)) if a != b
+            );
+            if !differ {
+                continue;
+            }
+            let side = match self.config.keep_diverged {
+                KeepDiverged::Live => Side::Live,
+                KeepDiverged::Backup => Side::Backup,
+                KeepDiverged::Newest if is_newer(backup, live).unwrap_or(false) => Side::Backup,
+                KeepDiverged::Newest => Side::Live,
+            };
+            diverged.push((save.key(), side));
+        }
+        diverged
+    }
+
+    /// Asks to resolve every diverged save at once, listing which way each would go.
+    pub fn start_resolve_diverged(&mut self) {
+        let resolutions = self.diverged();
+        if resolutions.is_empty() {
+            self.set_status("no diverged saves to resolve");
+            return;
+        }
+        self.prompt = Some(Prompt::ResolveDiverged { resolutions });
+    }
+
+    // copies the kept side of each save over the other; whichever copy loses is kept as a
+    // version first, so it can still be got back
+    fn resolve_diverged(&mut self, resolutions: &[(String, Side)]) {
+        let mut resolved = 0;
+        for (key, side) in resolutions {
+            let result = match self.save_by_key(key) {
+                Some(Save::Both(live, backup)) => {
+                    let (live, backup) = (live.clone(), backup.clone());
+                    match side {
+                        Side::Live => self.update_backup(&live, &backup),
+                        Side::Backup => self.keep_backup(&live, &backup),
+                    }
+                }
+                _ => Err(AppError::NoIntactBackup(key.clone())),
+            };
+            match result {
+                Ok(()) => {
+                    info!("[RESOLVE] kept the {} of '{}'", side, key);
+                    resolved += 1;
+                }
+                Err(e) => warn!("[RESOLVE] could not resolve '{}': {}", key, e),
+            }
+        }
+        self.set_status(format!(
+            "resolved {} of {} diverged saves",
+            resolved,
+            resolutions.len()
+        ));
+    }
+
+    // replaces a live save with its backup, keeping the live one as a version
+    fn keep_backup(&self, live: &Path, backup: &Path) -> Result<()> {
+        let version = new_version_path(backup, &self.backup_dir)?;
+        self.copy_atomic(live, &version)?;
+        write_checksum(&version)?;
+        info!(
+            "[RESOLVE] kept {} as {}; copying {} => {}",
+            live.display(),
+            version.display(),
+            backup.display(),
+            live.display()
+        );
+        self.copy_atomic(backup, live)
+    }
+
+    // saves linked to their backups before this session are checked for rewrites from the
+    // first time we see them
+    fn note_links(&mut self) {
+        for save in &self.state.saves {
+            if let Save::Both(live, backup) = save {
+                if self.links.contains_key(live) || !same_file(live, backup).unwrap_or(false) {
+                    continue;
+                }
+                if let Ok(modified) = live.metadata().and_then(|m| m.modified()) {
+                    self.links.insert(live.clone(), modified);
+                }
+            }
+        }
+    }
+
+    // whether `backup` may be replaced by a hard link to `live`; only plain copies on the same
+    // volume, of saves brogue hasn't rewritten in place
+    fn can_link(&self, live: &Path, backup: &Path) -> Result<bool> {
+        Ok(self.config.hardlink
+            && self.cipher.is_none()
+            && !is_encrypted(backup)
+            && !self.is_cross_volume()
+            && !self.rewritten_in_place.contains(&key(live))
+            && live.metadata()?.len() == backup.metadata()?.len())
+    }
+
+    fn is_kept_as_version(&self, backup: &Path) -> Result<bool> {
+        let len = backup.metadata()?.len();
+        let mut hash = None;
+        for version in versions(&self.backup_dir, &key(backup)) {
+            // only hash what could possibly match
+            let comparable = is_encrypted(&version) || is_encrypted(backup);
+            if !comparable && version.metadata()?.len() != len {
+                continue;
+            }
+            let backup_hash = match hash {
+                Some(hash) => hash,
+                None => *hash.insert(self.hashes.hash(backup)?),
+            };
+            if self.hashes.hash(&version)? == backup_hash {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// What syncing the current state would do, without doing any of it. Every restore
+    /// (backup -> save) comes before any backup (save -> backup), so a save restored on launch
+    /// isn't redundantly backed up again in the same pass. Within a phase, saves keep their
+    /// usual age order. A save that can't be read is left out, so it doesn't hold up the rest.
+    pub fn plan(&self) -> Vec<SyncAction> {
+        self.plan_and_skipped().0
+    }
+
+    // the `plan`, and the saves left out of it because they couldn't be read, with why
+    fn plan_and_skipped(&self) -> (Vec<SyncAction>, Vec<(String, AppError)>) {
+        let mut plan = vec![];
+        let mut skipped = vec![];
+        if self.waiting_for_save_dir {
+            return (plan, skipped);
+        }
+
+        let mut saves: Vec<&Save> = self.state.saves.iter().collect();
+        saves.sort_by_key(|s| s.phase());
+
+        for save in saves {
+            if self.is_oversized(save) || self.is_writing(save) {
+                continue;
+            }
+            let planned = plan.len();
+            match save {
+                Save::OriginalFileOnly(live) => {
+                    let mut backup = backup_path(&self.backup_dir, &key(live));
+                    if self.cipher.is_some() {
+                        backup = encrypted_path(&backup);
+                    }
+                    if !backup.exists() {
+                        plan.push(SyncAction::Backup {
+                            save: live.clone(),
+                            backup,
+                        });
+                    }
+                }
+                Save::BackupFileOnly(_) if self.config.mode == SyncMode::BackupOnly => {}
+                Save::BackupFileOnly(backup) => {
+                    if self.config.mode == SyncMode::Archive
+                        && self.previous.is_synced(&key(backup))
+                    {
+                        plan.push(SyncAction::Archive(backup.clone()));
+                    } else if !self.save_dir.join(self.full_name_of(&key(backup))).exists() {
+                        plan.push(SyncAction::Restore(save.clone()));
+                    }
+                }
+                Save::Both(live, backup) => match self.plan_synced(live, backup) {
+                    Ok(action) => plan.extend(action),
+                    Err(e) => {
+                        skipped.push((save.key(), e));
+                        continue;
+                    }
+                },
+            }
+            // a backup that's about to change is copied once it has
+            if plan.len() == planned {
+                match self.replicate(save) {
+                    Ok(copies) => plan.extend(copies),
+                    Err(e) => skipped.push((save.key(), e)),
+                }
+            }
+        }
+        (plan, skipped)
+    }
+
+    // what to do with a save that's in both dirs, if anything
+    fn plan_synced(&self, live: &Path, backup: &Path) -> Result<Option<SyncAction>> {
+        let (save, backup) = (live.to_path_buf(), backup.to_path_buf());
+        let action = if is_brogue_recording(live) {
+            self.recording_changed(live, &backup)?
+                .then_some(SyncAction::AppendRecording { save, backup })
+        } else if same_file(live, &backup)? {
+            let rewritten = matches!(
+                self.links.get(live),
+                Some(linked) if *linked != live.metadata()?.modified()?
+            );
+            (rewritten || !self.config.hardlink).then_some(SyncAction::Unlink { save, backup })
+        } else if is_newer(live, &backup)?
+            && self.hashes.hash(live)? != self.hashes.hash(&backup)?
+        {
+            Some(SyncAction::UpdateBackup { save, backup })
+        } else if self.can_link(live, &backup)?
+            && self.hashes.hash(live)? == self.hashes.hash(&backup)?
+        {
+            Some(SyncAction::Link { save, backup })
+        } else {
+            None
+        };
+        Ok(action)
+    }
+
+    // the copies `save`'s backup is missing in the extra backup dirs, or that are out of date;
+    // dirs that aren't there are left for when they are
+    fn replicate(&self, save: &Save) -> Result<Vec<SyncAction>> {
+        let backup = match save {
+            Save::Both(_, backup) | Save::BackupFileOnly(backup) => backup,
+            Save::OriginalFileOnly(_) => return Ok(vec![]),
+        };
+        let mut actions = vec![];
+        for copy in self.extra_copies(backup) {
+            if !copy.exists() || is_newer(backup, &copy)? {
+                actions.push(SyncAction::Replicate {
+                    backup: backup.clone(),
+                    copy,
+                });
+            }
+        }
+        Ok(actions)
+    }
+
+    // where `backup` goes in each of the extra backup dirs that's there
+    fn extra_copies(&self, backup: &Path) -> Vec<PathBuf> {
+        let relative = match backup.strip_prefix(&self.backup_dir) {
+            Ok(relative) => relative,
+            Err(_) => return vec![],
+        };
+        self.config
+            .extra_backup_dirs
+            .iter()
+            .filter(|dir| dir.is_dir())
+            .map(|dir| dir.join(relative))
+            .collect()
+    }
+
+    /// How many of the backup dirs hold an up-to-date copy of `save`'s backup, the main one
+    /// included, and how many need to for it to count as backed up.
+    pub fn copies(&self, save: &Save) -> (usize, usize) {
+        let dirs = 1 + self.config.extra_backup_dirs.len();
+        let needed = match self.config.backup_quorum {
+            0 => dirs,
+            quorum => quorum.min(dirs),
+        };
+        let backup = match save {
+            Save::Both(_, backup) | Save::BackupFileOnly(backup) => backup,
+            Save::OriginalFileOnly(_) => return (0, needed),
+        };
+        let extra = self
+            .extra_copies(backup)
+            .iter()
+            .filter(|copy| matches!(is_newer(backup, copy), Ok(false)))
+            .count();
+        (1 + extra, needed)
+    }
+
+    // whether `save` is in enough of the backup dirs to count as backed up
+    fn has_quorum(&self, save: &Save) -> bool {
+        if self.config.extra_backup_dirs.is_empty() {
+            return !matches!(save, Save::OriginalFileOnly(_));
+        }
+        let (copies, needed) = self.copies(save);
+        copies >= needed
+    }
+
+    // restores the best backup of `save` into the save dir and runs the hook; returns where
+    // it went
+    fn restore(&self, save: &Save) -> Result<PathBuf> {
+        let (restored, save_destination) = self.restore_best(save)?;
+        info!(
+            "[RESTORE] copying {} => {}",
+            restored.display(),
+            save_destination.display()
+        );
+        run_hook(&self.config.post_restore_cmd, &save_destination);
+        Ok(save_destination)
+    }
+
+    /// Restores every save that's only in the backup dir, whatever the sync mode, so brogue
+    /// sees them straight away on a new machine; for `--prime-saves`. Creates the save dir if
+    /// brogue hasn't yet. Saves that can't be restored are logged and skipped. Returns how
+    /// many were restored.
+    pub fn prime_saves(&mut self) -> Result<usize> {
+        std::fs::create_dir_all(&self.save_dir)?;
+        self.update_state()?;
+
+        let backup_only: Vec<Save> = self
+            .state
+            .saves
+            .iter()
+            .filter(|s| matches!(s, Save::BackupFileOnly(_)))
+            .cloned()
+            .collect();
+        let mut restored = 0;
+        for save in &backup_only {
+            match self.restore(save) {
+                Ok(_) => restored += 1,
+                Err(e) => warn!("[RESTORE] could not restore '{}': {}", save.key(), e),
+            }
+        }
+        if restored > 0 {
+            *self
+                .session_actions
+                .entry(ActionKind::Restored)
+                .or_default() += restored;
+            if self.config.git_commit {
+                self.commit_history(&format!("prime: restored {} saves", restored));
+            }
+        }
+        self.set_status(format!("primed the save dir with {} saves", restored));
+        Ok(restored)
+    }
+
+    /// Brings the save and backup dirs back in line. Deletes and dedup go first and end the
+    /// pass; otherwise we carry out the `plan`. Returns how many changes were made.
+    fn reconcile(&mut self) -> Result<usize> {
+        let _span = trace_span!("reconcile", mode = %self.config.mode).entered();
+        if self.waiting_for_save_dir {
+            return Ok(0);
+        }
+
+        if let DeleteState::Delete(idx) = self.delete_state {
+            // delete from both;
+            if let Some(save) = self.visible_saves().get(idx).map(|s| (*s).clone()) {
+                self.delete(&save)?;
+                let kept = if self.config.protect_last_copy && matches!(save, Save::Both(..)) {
+                    "; its backup is in archive/"
+                } else {
+                    ""
+                };
+                self.set_status(format!(
+                    "deleted {}{} (press {} to undo)",
+                    save.key(),
+                    kept,
+                    self.keymap.key(Action::Undo)
+                ));
+                if self.config.git_commit {
+                    self.commit_history(&format!("delete: {}", save.key()));
+                }
+                self.delete_state = DeleteState::NotDeleting;
+                return Ok(1);
+            }
+        }
+
+        // duplicates have to go before we consider restoring them; the next scan will pick up
+        // what's left. None of this is a step that can be reviewed, so there's none of it in
+        // manual mode.
+        let manual = self.is_manual();
+        if !manual && self.config.dedup != DedupMode::Off && self.dedup() {
+            return Ok(1);
+        }
+        if !manual && (self.prune_recordings()? || self.prune_games()?) {
+            return Ok(1);
+        }
+
+        let mut actions = vec![];
+        self.note_links();
+        let (mut plan, skipped) = self.plan_and_skipped();
+        // reported like a failed backup, and tried again next time
+        let mut failures: Vec<_> = skipped
+            .into_iter()
+            .map(|(key, e)| (key, ActionKind::BackedUp, e.to_string()))
+            .collect();
+        // a failed restore that's no longer planned has been dealt with some other way
+        self.failed_restores.retain(|key, _| {
+            plan.iter()
+                .any(|action| matches!(action, SyncAction::Restore(save) if save.key() == *key))
+        });
+        if manual {
+            plan = self.review(plan);
+        }
+        trace!(actions = plan.len(), "planned");
+        for action in plan {
+            trace!(%action);
+            match action {
+                SyncAction::Backup { save, backup } => {
+                    let copied = self
+                        .cp(&save, &backup)
+                        .and_then(|copied| copied.then(|| write_checksum(&backup)).transpose());
+                    match copied {
+                        Ok(Some(())) => {
+                            info!(
+                                "[BACKUP] copying {} => {}",
+                                save.display(),
+                                backup.display()
+                            );
+                            run_hook(&self.config.post_backup_cmd, &backup);
+                            actions.push((key(&save), ActionKind::BackedUp));
+                        }
+                        Ok(None) => {}
+                        Err(e) => failures.push((key(&save), ActionKind::BackedUp, e.to_string())),
+                    }
+                }
+                SyncAction::UpdateBackup { save, backup } => {
+                    match self.update_backup(&save, &backup) {
+                        Ok(()) => {
+                            run_hook(&self.config.post_backup_cmd, &backup);
+                            actions.push((key(&save), ActionKind::BackedUp));
+                        }
+                        Err(e) => failures.push((key(&save), ActionKind::BackedUp, e.to_string())),
+                    }
+                }
+                SyncAction::AppendRecording { save, backup } => {
+                    let copied = self
+                        .copy_delta(&save, &backup)
+                        .and_then(|copied| copied.then(|| write_checksum(&backup)).transpose());
+                    match copied {
+                        Ok(Some(())) => {
+                            run_hook(&self.config.post_backup_cmd, &backup);
+                            actions.push((key(&save), ActionKind::BackedUp));
+                        }
+                        Ok(None) => {}
+                        Err(e) => failures.push((key(&save), ActionKind::BackedUp, e.to_string())),
+                    }
+                }
+                SyncAction::Restore(save) => {
+                    let name = save.key();
+                    if let Some(failed) = self.failed_restores.get(&name) {
+                        if !failed.is_due(Instant::now()) {
+                            continue;
+                        }
+                    }
+                    match self.restore(&save) {
+                        Ok(save_destination) => {
+                            if let Some(failed) = self.failed_restores.remove(&name) {
+                                info!(
+                                    "[RESTORE] restored '{}' after {} failed tries",
+                                    name, failed.attempts
+                                );
+                            }
+                            actions.push((key(&save_destination), ActionKind::Restored));
+                        }
+                        Err(e) => {
+                            self.restore_failed(&name, &e);
+                            failures.push((name, ActionKind::Restored, e.to_string()));
+                        }
+                    }
+                }
+                SyncAction::Archive(backup) => {
+                    match Self::move_backup(&backup, &self.backup_dir, ARCHIVE_DIR) {
+                        Ok(_) => actions.push((key(&backup), ActionKind::Archived)),
+                        Err(e) => {
+                            failures.push((key(&backup), ActionKind::Archived, e.to_string()))
+                        }
+                    }
+                }
+                SyncAction::Link { save, backup } => match link(&save, &backup) {
+                    Ok(modified) => {
+                        info!(
+                            "[LINK] {} is now a hard link to {}",
+                            backup.display(),
+                            save.display()
+                        );
+                        self.links.insert(save, modified);
+                    }
+                    Err(e) => warn!("[LINK] could not link {}: {}", backup.display(), e),
+                },
+                SyncAction::Unlink { save, backup } => {
+                    let rewritten = self.links.remove(&save).is_some() && self.config.hardlink;
+                    if rewritten {
+                        warn!(
+                            "[LINK] brogue rewrote {} in place, and its linked backup with it; \
+                             giving it a copy of its own from now on",
+                            save.display()
+                        );
+                        self.rewritten_in_place.insert(key(&save));
+                    }
+                    match self
+                        .copy_atomic(&save, &backup)
+                        .and_then(|_| write_checksum(&backup))
+                    {
+                        Ok(()) => actions.push((key(&save), ActionKind::BackedUp)),
+                        Err(e) => failures.push((key(&save), ActionKind::BackedUp, e.to_string())),
+                    }
+                }
+                SyncAction::Replicate { backup, copy } => {
+                    let copied = copy
+                        .parent()
+                        .map_or(Ok(()), std::fs::create_dir_all)
+                        .map_err(AppError::from)
+                        .and_then(|_| self.copy_atomic(&backup, &copy))
+                        .and_then(|_| write_checksum(&copy));
+                    match copied {
+                        Ok(()) => {
+                            info!(
+                                "[BACKUP] copying {} => {}",
+                                backup.display(),
+                                copy.display()
+                            );
+                            actions.push((key(&backup), ActionKind::BackedUp));
+                        }
+                        Err(e) => {
+                            failures.push((key(&backup), ActionKind::BackedUp, e.to_string()))
+                        }
+                    }
+                }
+            }
+        }
+
+        if !manual {
+            match extras::backup(&self.save_dir, &self.backup_dir, &self.config.extra_files) {
+                Ok(copied) => {
+                    actions.extend(copied.into_iter().map(|name| (name, ActionKind::BackedUp)))
+                }
+                Err(e) => warn!("[CONFIG] could not back up settings files: {}", e),
+            }
+        }
+
+        if !actions.is_empty() {
+            let message = commit_message(&actions);
+            if self.config.git_commit {
+                self.commit_history(&message);
+            }
+            notification::notify(&self.config, &message);
+        }
+
+        let changes = actions.len();
+        let now = Instant::now();
+        self.last_actions
+            .retain(|_, (_, at)| at.elapsed() < RECENT_ACTION_WINDOW);
+        for (key, kind) in actions {
+            *self.session_actions.entry(kind).or_default() += 1;
+            self.last_actions.insert(key, (kind, now));
+        }
+
+        // likewise warn about each oversized save once
+        let oversized: HashSet<_> = self
+            .state
+            .saves
+            .iter()
+            .filter(|s| self.is_oversized(s))
+            .map(|s| s.key())
+            .collect();
+        for key in &oversized {
+            if self.oversized.contains(key) {
+                continue;
+            }
+            warn!(
+                "[BACKUP] not backing up '{}'; it's bigger than max_save_bytes ({} bytes)",
+                key, self.config.max_save_bytes
+            );
+            self.set_status(format!("'{}' is too big to back up", key));
+        }
+        self.oversized = oversized;
+
+        let failed_archives: HashSet<String> = failures
+            .iter()
+            .filter(|(_, kind, _)| *kind == ActionKind::Archived)
+            .map(|(key, _, _)| key.clone())
+            .collect();
+
+        // report each failure once, rather than on every tick; they're tried again each tick
+        let mut failed_syncs = HashSet::new();
+        for (key, kind, error) in failures {
+            if !self.failed_syncs.contains(&key) {
+                let (tag, verb) = match kind {
+                    ActionKind::Restored => ("RESTORE", "restore"),
+                    ActionKind::Archived => ("ARCHIVE", "archive"),
+                    ActionKind::BackedUp => ("BACKUP", "back up"),
+                };
+                warn!("[{}] could not {} '{}': {}", tag, verb, key, error);
+                self.set_status(format!("could not {} '{}': {}", verb, key, error));
+            }
+            failed_syncs.insert(key);
+        }
+        self.failed_syncs = failed_syncs;
+
+        // a save we couldn't archive still counts as synced, so the next tick archives it
+        // rather than restoring it
+        let mut previous = self.state.clone();
+        for save in previous.saves.iter_mut() {
+            if !failed_archives.contains(&save.key()) {
+                continue;
+            }
+            if let Some(synced) = self.previous.saves.iter().find(|s| s.key() == save.key()) {
+                *save = synced.clone();
+            }
+        }
+        self.previous = previous;
+        Ok(changes)
+    }
+
+    // keeps a failed restore to try again later, backing off further each time, unless it
+    // failed in a way that trying again won't fix
+    fn restore_failed(&mut self, key: &str, error: &AppError) {
+        let attempts = self.failed_restores.get(key).map_or(0, |f| f.attempts) + 1;
+        let retry_at = is_transient(error).then(|| {
+            let delay = RESTORE_RETRY_MIN
+                .saturating_mul(1 << (attempts - 1).min(16))
+                .min(RESTORE_RETRY_MAX);
+            Instant::now() + delay
+        });
+        if retry_at.is_none() {
+            warn!(
+                "[RESTORE] not trying '{}' again until asked to; {}",
+                key, error
+            );
+        }
+        self.failed_restores.insert(
+            key.to_string(),
+            FailedRestore {
+                error: error.to_string(),
+                attempts,
+                retry_at,
+            },
+        );
+    }
+
+    /// The restores that have failed and not yet worked, by save.
+    pub fn failed_restores(&self) -> &BTreeMap<String, FailedRestore> {
+        &self.failed_restores
+    }
+
+    /// Tries every failed restore again on the next sync, whatever went wrong.
+    pub fn retry_restores(&mut self) {
+        if self.failed_restores.is_empty() {
+            self.set_status("no failed restores to retry");
+            return;
+        }
+        let now = Instant::now();
+        for failed in self.failed_restores.values_mut() {
+            failed.retry_at = Some(now);
+        }
+        self.set_status(format!(
+            "retrying {} failed restores",
+            self.failed_restores.len()
+        ));
+    }
+
+    /// Compares the saves we were tracking at the end of last session with what's there now
+    /// once the first scan is done; see `check_last_session`.
+    pub fn recall_last_session(&mut self, manifest: Manifest) {
+        self.last_session = Some(manifest);
+    }
+
+    /// Records the saves we're tracking, for the next run to check against. Does nothing if
+    /// we never got to scan the save dir, rather than forgetting last session's saves.
+    pub fn record_session(&self) -> Result<()> {
+        if self.waiting_for_save_dir {
+            return Ok(());
+        }
+        cache::save(&self.save_dir, &self.backup_dir, &self.state)?;
+        manifest::save(&self.backup_dir, &self.manifest())
+    }
+
+    /// Shows the saves as they were at the end of last session, if they were cached for these
+    /// dirs, until the first scan replaces them.
+    pub fn show_cached_state(&mut self) {
+        if !self.previous.saves.is_empty() {
+            self.state = self.previous.clone();
+            self.stale = true;
+        }
+    }
+
+    /// Whether the saves shown are last session's, not yet scanned.
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    /// The names of every save in either dir.
+    pub fn tracked_saves(&self) -> BTreeSet<String> {
+        self.state.saves.iter().map(|s| s.key()).collect()
+    }
+
+    // every save in either dir, with when the live one was written, or the backup if that's
+    // all there is
+    fn manifest(&self) -> Manifest {
+        self.state
+            .saves
+            .iter()
+            .map(|s| {
+                let written = s
+                    .path()
+                    .metadata()
+                    .and_then(|m| m.modified())
+                    .ok()
+                    .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs());
+                (s.key(), written)
+            })
+            .collect()
+    }
+
+    // sums up what's changed since last session for the popup. Saves that were there last
+    // session and have gone from both dirs since were removed by something other than us, so
+    // warn and offer to put back any copies we still have
+    fn check_last_session(&mut self, last_session: &Manifest) {
+        let changes = manifest::diff(last_session, &self.manifest());
+        let missing = changes.removed.clone();
+        if !changes.is_empty() {
+            info!("since last launch: {}", changes);
+            self.since_last_launch = Some(changes);
+        }
+        if missing.is_empty() {
+            return;
+        }
+
+        let mut found = vec![];
+        for key in &missing {
+            warn!(
+                "'{}' was there last session but has gone from both dirs",
+                key
+            );
+            let copy = [TRASH_DIR, ARCHIVE_DIR]
+                .iter()
+                .map(|dir| self.backup_dir.join(dir).join(key))
+                .flat_map(|path| [encrypted_path(&path), path])
+                .find_map(|path| newest_moved(&path));
+            found.extend(copy.map(|copy| (key.clone(), copy)));
+        }
+        self.prompt = Some(Prompt::Recover { missing, found });
+    }
+
+    // moves copies found in the trash or archive back into the backup dir under the names of
+    // the saves they're copies of, where the next reconcile picks them up
+    fn recover(&mut self, found: &[(String, PathBuf)]) {
+        for (key, path) in found {
+            let mut destination = backup_path(&self.backup_dir, key);
+            if is_encrypted(path) {
+                destination = encrypted_path(&destination);
+            }
+            let result = move_file(path, &destination).and_then(|_| {
+                let checksum = checksum_path(path);
+                if checksum.exists() {
+                    move_file(&checksum, &checksum_path(&destination))?;
+                }
+                Ok(())
+            });
+            match result {
+                Ok(()) => info!(
+                    "[RECOVER] moved {} => {}",
+                    path.display(),
+                    destination.display()
+                ),
+                Err(e) => warn!("[RECOVER] could not move {}: {}", path.display(), e),
+            }
+        }
+        self.set_status(format!("recovered {} saves", found.len()));
+    }
+
+    /// Starts in `AppMode::Preview`, showing what the first sync will do before doing it.
+    pub fn start_preview(&mut self) {
+        self.mode = AppMode::Preview(vec![]);
+    }
+
+    pub fn is_previewing(&self) -> bool {
+        matches!(self.mode, AppMode::Preview(_))
+    }
+
+    pub fn confirm_preview(&mut self) {
+        self.mode = AppMode::Active;
+    }
+
+    /// Stops syncing on its own; every action is queued for review instead. See `approve` and
+    /// `reject`.
+    pub fn start_manual(&mut self) {
+        self.mode = AppMode::Manual;
+    }
+
+    pub fn is_manual(&self) -> bool {
+        self.mode == AppMode::Manual
+    }
+
+    /// The actions waiting to be approved or rejected, oldest first.
+    pub fn review_queue(&self) -> &VecDeque<SyncAction> {
+        &self.review
+    }
+
+    /// Approves the oldest action waiting for review; it's done on the next sync.
+    pub fn approve(&mut self) {
+        if let Some(action) = self.review.pop_front() {
+            info!("[REVIEW] approved: {}", action);
+            self.approved.push(action);
+        }
+    }
+
+    /// Rejects the oldest action waiting for review; it isn't done, or asked about again, unless
+    /// it drops out of the plan and comes back.
+    pub fn reject(&mut self) {
+        if let Some(action) = self.review.pop_front() {
+            info!("[REVIEW] rejected: {}", action);
+            self.rejected.push(action);
+        }
+    }
+
+    // queues what's newly planned for review, forgets what no longer is, and hands back what's
+    // been approved and still applies
+    fn review(&mut self, plan: Vec<SyncAction>) -> Vec<SyncAction> {
+        self.review.retain(|action| plan.contains(action));
+        self.rejected.retain(|action| plan.contains(action));
+        let approved: Vec<SyncAction> = std::mem::take(&mut self.approved)
+            .into_iter()
+            .filter(|action| plan.contains(action))
+            .collect();
+        for action in plan {
+            let seen = self.review.contains(&action)
+                || self.rejected.contains(&action)
+                || approved.contains(&action);
+            if !seen {
+                self.review.push_back(action);
+            }
+        }
+        approved
+    }
+
+    /// In snapshot mode, keeps a version of every save brogue has rewritten since we last
+    /// looked, so each turn can be got back rather than just the latest. Saves we haven't seen
+    /// before are left to the normal backup.
+    fn snapshot(&mut self) -> Result<()> {
+        // snapshots are copies too, and there's no reviewing them
+        if !self.config.snapshot || self.waiting_for_save_dir || self.is_manual() {
+            return Ok(());
+        }
+
+        let mut seen = HashMap::new();
+        for save in files(&self.save_dir)? {
+            if !is_brogue_save(&save) || self.is_too_big(&save) {
+                continue;
+            }
+            // look again once brogue's finished, still comparing with the last version we kept
+            if self.is_being_written(&save) {
+                if let Some(previous) = self.snapshot_times.get(&save) {
+                    seen.insert(save, *previous);
+                }
+                continue;
+            }
+            let modified = save.metadata()?.modified()?;
+            let changed = self
+                .snapshot_times
+                .get(&save)
+                .is_some_and(|previous| *previous != modified);
+            if changed {
+                let mut backup = self.backup_dir.join(key(&save));
+                if self.cipher.is_some() {
+                    backup = encrypted_path(&backup);
+                }
+                let version = new_version_path(&backup, &self.backup_dir)?;
+                self.copy_atomic(&save, &version)?;
+                write_checksum(&version)?;
+                info!("[SNAPSHOT] {} => {}", save.display(), version.display());
+            }
+            seen.insert(save, modified);
+        }
+        self.snapshot_times = seen;
+        Ok(())
+    }
+
+    /// Copies every save and recording in the save dir into a new `snapshots/<time>/` folder,
+    /// then deletes the oldest snapshots beyond `keep_snapshots`. Returns the new folder.
+    pub fn take_snapshot(&mut self) -> Result<PathBuf> {
+        let dir = new_snapshot_dir(&self.backup_dir)?;
+        let mut count = 0;
+        for save in files(&self.save_dir)? {
+            if self.is_too_big(&save) {
+                continue;
+            }
+            // half a file is no use to anyone; the next snapshot will have it
+            if self.is_being_written(&save) {
+                info!(
+                    "[SNAPSHOT] skipping {} while it's being written",
+                    key(&save)
+                );
+                continue;
+            }
+            let mut copy = dir.join(key(&save));
+            if self.cipher.is_some() {
+                copy = encrypted_path(&copy);
+            }
+            self.copy_atomic(&save, &copy)?;
+            write_checksum(&copy)?;
+            count += 1;
+        }
+        self.last_full_snapshot = Some(SystemTime::now());
+        info!("[SNAPSHOT] copied {} files into {}", count, dir.display());
+
+        for pruned in snapshots::prune(&self.backup_dir, self.config.keep_snapshots)? {
+            info!("[PRUNE] deleted the snapshot {}", pruned.display());
+        }
+        Ok(dir)
+    }
+
+    // takes a snapshot of the whole save dir every `snapshot_interval_mins`
+    fn snapshot_if_due(&mut self) {
+        let interval = Duration::from_secs(self.config.snapshot_interval_mins * 60);
+        if interval.is_zero() || self.waiting_for_save_dir || self.is_manual() {
+            return;
+        }
+        let due = self.last_full_snapshot.is_none_or(|at| {
+            SystemTime::now()
+                .duration_since(at)
+                .is_ok_and(|since| since >= interval)
+        });
+        if !due {
+            return;
+        }
+        if let Err(e) = self.take_snapshot() {
+            warn!("[SNAPSHOT] could not snapshot the save dir: {}", e);
+            // rather than try again every tick
+            self.last_full_snapshot = Some(SystemTime::now());
+        }
+    }
+
+    pub fn on_tick(&mut self) {
+        if self.is_previewing() {
+            self.refresh_log();
+            return;
+        }
+        let _span = trace_span!("tick").entered();
+        self.check_backup_dir();
+        self.health = Health::classify(self.newest_unbacked(), &self.config);
+        if self.unwritable.is_some() {
+            self.refresh_log();
+            return;
+        }
+        if let Err(e) = self.snapshot() {
+            warn!("[SNAPSHOT] could not snapshot saves: {}", e);
+        }
+        self.snapshot_if_due();
+        // a save can vanish or a copy fail between the scan and acting on it; the next tick
+        // scans again, so that's worth reporting but not stopping for
+        match self.reconcile() {
+            Ok(changes) => {
+                self.last_sync = Some(Instant::now());
+                self.catch_up(changes);
+                // so a save backed up just now stops showing as unbacked straight away
+                if changes > 0 {
+                    self.health = Health::classify(self.newest_unbacked(), &self.config);
+                }
+            }
+            Err(e) => {
+                warn!("[BACKUP] could not sync: {}", e);
+                self.set_status(format!("could not sync: {}", e));
+            }
+        }
+        self.refresh_log();
+    }
+
+    /// Why the backup dir can't be written to, if it couldn't when we last checked. Nothing is
+    /// synced until it can be again.
+    pub fn unwritable_backup_dir(&self) -> Option<&str> {
+        self.unwritable.as_deref()
+    }
+
+    // every `writable_check_ms`, checks we can still write to the backup dir, e.g. that it
+    // hasn't been remounted read-only, so we stop syncing rather than fail every copy
+    fn check_backup_dir(&mut self) {
+        let interval = Duration::from_millis(self.config.writable_check_ms);
+        if matches!(self.last_write_check, Some(at) if at.elapsed() < interval) {
+            return;
+        }
+        self.last_write_check = Some(Instant::now());
+
+        let problem = match check_writable(&self.backup_dir) {
+            Ok(()) => self.inode_shortage(),
+            Err(e) => Some(e.to_string()),
+        };
+        match (problem, &self.unwritable) {
+            (None, Some(_)) => {
+                info!(
+                    "[BACKUP] {} is writable again; syncing",
+                    self.backup_dir.display()
+                );
+                self.unwritable = None;
+                self.set_status("backup dir is writable again");
+            }
+            (Some(problem), None) => {
+                warn!(
+                    "[BACKUP] can't write to {}: {}; only watching until it's writable again",
+                    self.backup_dir.display(),
+                    problem
+                );
+                self.unwritable = Some(problem);
+                self.catch_up = None;
+            }
+            _ => {}
+        }
+    }
+
+    // running out of inodes fails copies just like running out of space, with a less helpful
+    // error, so stop short of it
+    fn inode_shortage(&self) -> Option<String> {
+        let min = self.config.min_free_inodes;
+        if min == 0 {
+            return None;
+        }
+        let free = free_inodes(&self.backup_dir)?;
+        (free < min).then(|| {
+            format!(
+                "only {} inodes free, fewer than min_free_inodes ({})",
+                free, min
+            )
+        })
+    }
+
+    /// Whether the newest save is safely backed up, as of the last tick.
+    pub fn health(&self) -> Health {
+        self.health
+    }
+
+    // how long the newest save has been waiting for a backup, if it is; one brogue has
+    // rewritten without changing counts as backed up. Cheap enough to check every tick, so the
+    // status bar shows unbacked progress from the moment brogue writes it.
+    fn newest_unbacked(&self) -> Option<Duration> {
+        let newest = self
+            .state
+            .saves
+            .iter()
+            .find(|s| !s.is_recording() && !matches!(s, Save::BackupFileOnly(_)))?;
+        let (live, backup) = match newest {
+            Save::Both(live, backup) => (live, backup.clone()),
+            // it may have been backed up since the last scan
+            Save::OriginalFileOnly(live) => {
+                let backup = backup_path(&self.backup_dir, &key(live));
+                let encrypted = encrypted_path(&backup);
+                if encrypted.exists() {
+                    (live, encrypted)
+                } else if backup.exists() {
+                    (live, backup)
+                } else {
+                    return Some(age(live));
+                }
+            }
+            Save::BackupFileOnly(_) => return None,
+        };
+        let backed_up = (!is_newer(live, &backup).unwrap_or(true)
+            || matches!(
+                (self.hashes.hash(live), self.hashes.hash(&backup)),
+                (Ok(a), Ok(b)) if a == b
+            ))
+            && self.has_quorum(&Save::Both(live.clone(), backup));
+        (!backed_up).then(|| age(live))
+    }
+
+    /// How long ago we last synced the two dirs; `None` if we haven't yet.
+    pub fn since_last_sync(&self) -> Option<Duration> {
+        self.last_sync.map(|at| at.elapsed())
+    }
+
+    /// How many saves are waiting to be backed up or restored, including backups not yet in
+    /// enough of the backup dirs. Saves that are only backed up don't count in backup-only
+    /// mode, where they stay that way.
+    pub fn pending(&self) -> usize {
+        let counts = self.state.counts();
+        let short = if self.config.extra_backup_dirs.is_empty() {
+            0
+        } else {
+            self.state
+                .saves
+                .iter()
+                .filter(|s| !matches!(s, Save::OriginalFileOnly(_)) && !self.has_quorum(s))
+                .count()
+        };
+        if self.config.mode == SyncMode::BackupOnly {
+            counts.live_only + short
+        } else {
+            counts.live_only + counts.backup_only + short
+        }
+    }
+
+    /// Whether we're still working through the backlog from startup, and so should reconcile
+    /// again straight away rather than waiting for the next tick.
+    pub fn is_catching_up(&self) -> bool {
+        self.catch_up.is_some()
+    }
+
+    // keeps catching up until a pass changes nothing, or we've tried for long enough that
+    // something must be undoing our changes
+    fn catch_up(&mut self, changes: usize) {
+        let (rounds, total) = match self.catch_up {
+            Some((rounds, total)) => (rounds + 1, total + changes),
+            None => return,
+        };
+        if changes == 0 || rounds >= MAX_CATCH_UP_ROUNDS {
+            if total > 0 {
+                info!("caught up: {} changes in {} passes", total, rounds);
+                self.set_status(format!("caught up: {} changes", total));
+            }
+            self.catch_up = None;
+        } else {
+            self.catch_up = Some((rounds, total));
+            self.set_status(format!("catching up: {} changes so far", total));
+        }
+    }
+}
+/// e.g. "backup: Saved #1 at depth 3.broguesave", or a count with one line per change.
+fn commit_message(actions: &[(String, ActionKind)]) -> String {
+    let lines: Vec<_> = actions
+        .iter()
+        .map(|(key, kind)| {
+            let verb = match kind {
+                ActionKind::BackedUp => "backup",
+                ActionKind::Restored => "restore",
+                ActionKind::Archived => "archive",
+            };
+            format!("{}: {}", verb, key)
+        })
+        .collect();
+    match lines.as_slice() {
+        [line] => line.clone(),
+        _ => format!("{} changes\n\n{}", lines.len(), lines.join("\n")),
+    }
+}
+
+/// Runs a user-configured command with `path` as its last argument, in the background so a
+/// slow hook doesn't stall the UI. The command goes through the platform's shell, so a program
+/// whose path has spaces in it can be quoted. Failures are logged, never fatal.
+fn run_hook(cmd: &Option<String>, path: &Path) {
+    let cmd = match cmd {
+        Some(cmd) if !cmd.trim().is_empty() => cmd.clone(),
+        _ => return,
+    };
+    let path = path.to_path_buf();
+
+    std::thread::spawn(move || {
+        info!("[HOOK] running {} {}", cmd, path.display());
+        match hook_command(&cmd, &path).output() {
+            Ok(output) => {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                if !stdout.trim().is_empty() {
+                    info!("[HOOK] stdout: {}", stdout.trim());
+                }
+                if !stderr.trim().is_empty() {
+                    info!("[HOOK] stderr: {}", stderr.trim());
+                }
+                if !output.status.success() {
+                    warn!("[HOOK] '{}' exited with {}", cmd, output.status);
+                }
+            }
+            Err(e) => warn!("[HOOK] could not run '{}': {}", cmd, e),
+        }
+    });
+}
+
+// `sh -c '<cmd> "$1"' sh <path>`: the path is passed to the shell as an argument rather than
+// pasted into the script, so nothing in it needs escaping
+#[cfg(unix)]
+fn hook_command(cmd: &str, path: &Path) -> Command {
+    let mut command = Command::new("sh");
+    command
+        .arg("-c")
+        .arg(format!("{} \"$1\"", cmd))
+        .arg("sh")
+        .arg(path);
+    command
+}
+
+// cmd.exe takes the rest of its command line as is, so it's passed through unquoted
+#[cfg(windows)]
+fn hook_command(cmd: &str, path: &Path) -> Command {
+    use std::os::windows::process::CommandExt;
+    let mut command = Command::new("cmd");
+    command
+        .arg("/C")
+        .raw_arg(format!("{} \"{}\"", cmd, path.display()));
+    command
+}
+
+#[derive(Default, Clone)]
+pub struct State {
+    pub saves: Vec<Save>,
+    /// how many files were looked at to build this
+    pub scanned: usize,
+}
+
+/// A restore that failed; see `App::failed_restores`.
+#[derive(Clone, Debug)]
+pub struct FailedRestore {
+    pub error: String,
+    /// how many times in a row it's failed
+    pub attempts: u32,
+    /// when to try it again; `None` if the error won't go away by itself, so it waits to be
+    /// retried by hand
+    pub retry_at: Option<Instant>,
+}
+
+impl FailedRestore {
+    fn is_due(&self, now: Instant) -> bool {
+        self.retry_at.is_some_and(|at| at <= now)
+    }
+}
+
+// whether an error might go away if we try again: a file that's locked or busy, a full disk,
+// or a copy that came out wrong, rather than a backup that's gone, damaged or needs a key
+fn is_transient(error: &AppError) -> bool {
+    use std::io::ErrorKind;
+    match error {
+        AppError::IoError(e) => !matches!(
+            e.kind(),
+            ErrorKind::NotFound
+                | ErrorKind::InvalidInput
+                | ErrorKind::InvalidData
+                | ErrorKind::IsADirectory
+                | ErrorKind::Unsupported
+        ),
+        AppError::CopyMismatch(_) => true,
+        _ => false,
+    }
+}
+
+/// What the stats popup shows; see `App::stats`.
+#[derive(Debug, Default)]
+pub struct Stats {
+    pub saves: usize,
+    /// total size of the backups of every save, not counting older versions
+    pub backup_bytes: u64,
+    /// `None` counts saves whose difficulty we couldn't work out
+    pub by_difficulty: BTreeMap<Option<Difficulty>, usize>,
+    pub oldest_backup: Option<(String, SystemTime)>,
+    pub newest_backup: Option<(String, SystemTime)>,
+    /// what's been done this session
+    pub actions: BTreeMap<ActionKind, usize>,
+    /// how fast recent copies went, on average
+    pub copy_bytes_per_sec: Option<f64>,
+}
+
+/// Whether the newest save is backed up, for the indicator in the status bar; see
+/// `App::health`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Health {
+    /// backed up, or there's nothing to back up
+    Good,
+    /// written since its last backup, but not for long enough to warn about
+    Unbacked(Duration),
+    /// waiting longer than `health_warn_secs` for a backup
+    Warning(Duration),
+    /// waiting longer than `health_alarm_secs`
+    Alarm(Duration),
+}
+
+impl Health {
+    /// `unbacked` is how long the newest save has gone without a backup, if it has.
+    pub fn classify(unbacked: Option<Duration>, config: &Config) -> Health {
+        match unbacked {
+            Some(d) if d > Duration::from_secs(config.health_alarm_secs) => Health::Alarm(d),
+            Some(d) if d > Duration::from_secs(config.health_warn_secs) => Health::Warning(d),
+            Some(d) => Health::Unbacked(d),
+            None => Health::Good,
+        }
+    }
+}
+
+/// How many saves are where; see `State::counts`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Counts {
+    pub saves: usize,
+    pub synced: usize,
+    /// in the save dir but not backed up yet
+    pub live_only: usize,
+    /// backed up but gone from the save dir
+    pub backup_only: usize,
+}
+
+impl State {
+    pub fn counts(&self) -> Counts {
+        let mut counts = Counts {
+            saves: self.saves.len(),
+            ..Counts::default()
+        };
+        for save in &self.saves {
+            match save {
+                Save::Both(_, _) => counts.synced += 1,
+                Save::OriginalFileOnly(_) => counts.live_only += 1,
+                Save::BackupFileOnly(_) => counts.backup_only += 1,
+            }
+        }
+        counts
+    }
+
+    fn is_synced(&self, key: &str) -> bool {
+        self.saves
+            .iter()
+            .any(|s| matches!(s, Save::Both(_, _)) && s.key() == key)
+    }
+}
+
+/// The order `reconcile` handles saves in; earlier variants go first.
+#[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq)]
+enum Phase {
+    Restore,
+    Backup,
+}
+
+#[derive(Debug, Clone, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Save {
+    OriginalFileOnly(PathBuf),
+    BackupFileOnly(PathBuf),
+    Both(PathBuf, PathBuf),
+}
+
+impl Save {
+    pub fn key(&self) -> String {
+        match self {
+            Save::OriginalFileOnly(x) => key(x),
+            Save::BackupFileOnly(x) => key(x),
+            Save::Both(x, _) => key(x),
+        }
+    }
+
+    fn phase(&self) -> Phase {
+        match self {
+            Save::BackupFileOnly(_) => Phase::Restore,
+            Save::OriginalFileOnly(_) | Save::Both(_, _) => Phase::Backup,
+        }
+    }
+
+    pub fn metadata(&self) -> Option<SaveMetadata> {
+        parse_save_name(&self.key())
+    }
+
+    pub fn is_recording(&self) -> bool {
+        is_brogue_recording(self.path())
+    }
+
+    pub fn difficulty(&self) -> Option<Difficulty> {
+        self.metadata().and_then(|m| m.difficulty)
+    }
+
+    /// How the live save and its backup differ; `None` unless the save is in both places and
+    /// both can be read.
+    pub fn diff_summary(&self, cipher: Option<&Cipher>) -> Option<DiffSummary> {
+        match self {
+            Save::Both(save, backup) => diff_summary(save, backup, cipher).ok(),
+            _ => None,
+        }
+    }
+
+    /// The file to read the save from, preferring the live one.
+    pub fn path(&self) -> &Path {
+        match self {
+            Save::OriginalFileOnly(x) => x,
+            Save::BackupFileOnly(x) => x,
+            Save::Both(x, _) => x,
+        }
+    }
+
+    /// How long ago the save was last written.
+    pub fn age(&self) -> Duration {
+        match self {
+            Save::OriginalFileOnly(x) => age(x),
+            Save::BackupFileOnly(x) => age(x),
+            Save::Both(x, y) => age(x).max(age(y)),
+        }
+    }
+}
+
+fn expand_home(input: &str) -> PathBuf {
+    match (input.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(input),
+    }
+}
+
+fn partial_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".");
+    file_name.push(PARTIAL_EXTENSION);
+    path.with_file_name(file_name)
+}
+
+/// Where the backup of the save or recording called `name` goes; recordings are kept apart in
+/// `recordings/`.
+fn backup_path(backup_dir: &Path, name: &str) -> PathBuf {
+    if is_brogue_recording(Path::new(name)) {
+        backup_dir.join(RECORDINGS_DIR).join(name)
+    } else {
+        backup_dir.join(name)
+    }
+}
+
+/// The backups in `backup_dir`, including the recordings in `recordings/`.
+fn backup_files(backup_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut res = files(backup_dir)?;
+    let recordings_dir = backup_dir.join(RECORDINGS_DIR);
+    if recordings_dir.exists() {
+        res.extend(files(&recordings_dir)?);
+    }
+    Ok(res)
+}
+
+// saves are keyed by file name, ignoring whether the backup is encrypted, and shortened if
+// need be; the backup is stored, and a backup restored, under the key
+fn key(path: &Path) -> String {
+    short_name(plain_name(path))
+}
+
+// A name longer than this can't take what we add to a backup's name (`.age`, `.partial`,
+// `.sum`, a version's timestamp) and stay within the usual 255 byte limit.
+const MAX_KEY_BYTES: usize = 220;
+
+// Cuts a name too long to back up as-is down to `MAX_KEY_BYTES`, keeping the start (with the
+// seed and depth) and the extension, with a hash of the whole name in between so two long names
+// that start the same don't end up as one. Names that fit are left alone.
+fn short_name(name: String) -> String {
+    if name.len() <= MAX_KEY_BYTES {
+        return name;
+    }
+    let extension = Path::new(&name)
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    let hash = format!("~{:016x}", stable_hash(name.as_bytes()));
+    let mut end = MAX_KEY_BYTES.saturating_sub(hash.len() + extension.len());
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{}{}", &name[..end], hash, extension)
+}
+
+// FNV-1a; unlike `DefaultHasher`, it won't change with the compiler, so a shortened name comes
+// out the same every run and a checksum written to disk still matches after an upgrade
+const STABLE_HASH_START: u64 = 0xcbf2_9ce4_8422_2325;
+
+pub fn stable_hash(bytes: &[u8]) -> u64 {
+    continue_stable_hash(STABLE_HASH_START, bytes)
+}
+
+// the hash of whatever was hashed into `hash`, followed by `bytes`
+fn continue_stable_hash(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// a file deleted since the scan, or written "in the future" by a skewed clock, counts as
+// just written
+fn age(path: &Path) -> Duration {
+    path.metadata()
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .unwrap_or_default()
+}
+
+/// Renders as `<code> <glyph> <file name>`, e.g.
+///
+/// ```text
+/// SAVE S<-xB Saved #1 at depth 2.broguesave
+/// BACK Sx->B Saved #1 at depth 2.broguesave
+/// SYNC S<->B Saved #1 at depth 2.broguesave
+/// ```
+///
+/// The UI shows this as-is, so keep it stable.
+impl Display for Save {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let prefix = match self {
+            Save::OriginalFileOnly(_) => "S<-xB",
+            Save::BackupFileOnly(_) => "Sx->B",
+            Save::Both(_, _) => "S<->B",
+        };
+
+        let code = match self {
+            Save::OriginalFileOnly(_) => "SAVE",
+            Save::BackupFileOnly(_) => "BACK",
+            Save::Both(_, _) => "SYNC",
+        };
+        write!(f, "{} {} {}", code, prefix, self.key())
+    }
+}
+
+pub fn get_state(save_dir: &Path, backup_dir: &Path) -> Result<State> {
+    let _span = trace_span!(
+        "get_state",
+        save_dir = %save_dir.display(),
+        backup_dir = %backup_dir.display()
+    )
+    .entered();
+    let save_files = files(save_dir)?;
+    let backup_files = backup_files(backup_dir)?;
+    let scanned = save_files.len() + backup_files.len();
+    let mut map: HashMap<String, Save> = HashMap::new();
+
+    for save_file in save_files {
+        map.entry(key(&save_file))
+            .or_insert_with(|| Save::OriginalFileOnly(save_file.clone()));
+    }
+
+    for backup_file in backup_files {
+        map.entry(key(&backup_file))
+            .and_modify(|s| {
+                if let Save::OriginalFileOnly(p) = s {
+                    *s = Save::Both(p.clone(), backup_file.clone());
+                }
+            })
+            .or_insert(Save::BackupFileOnly(backup_file));
+    }
+
+    let mut saves: Vec<Save> = map.into_values().collect();
+    saves.sort_by_key(|x| x.age());
+
+    // pop in a couple of test values
+    // saves.push(Save::SaveOnly(PathBuf::from("save-only.broguesave")));
+    // saves.push(Save::BackupOnly(PathBuf::from("backup-only.broguesave")));
+
+    trace!(files = scanned, saves = saves.len(), "scanned");
+    Ok(State { saves, scanned })
+}
+
+/// Tidies the backup dir after a crash; removes stale `.partial` copies and reports anything
+/// else that doesn't look like a save. Valid saves are never touched, though recordings
+/// backed up before they had their own folder are moved into `recordings/`.
+pub fn repair(backup_dir: &Path) -> Result<()> {
+    move_recordings(backup_dir)?;
+    repair_dir(backup_dir)?;
+    let recordings_dir = backup_dir.join(RECORDINGS_DIR);
+    if recordings_dir.exists() {
+        repair_dir(&recordings_dir)?;
+    }
+    Ok(())
+}
+
+fn move_recordings(backup_dir: &Path) -> Result<()> {
+    for recording in files(backup_dir)? {
+        if !is_brogue_recording(&recording) {
+            continue;
+        }
+        let destination = backup_path(backup_dir, &key(&recording))
+            .with_file_name(recording.file_name().unwrap_or_default());
+        if destination.exists() {
+            warn!(
+                "[REPAIR] {} is also in {}; leaving it where it is",
+                recording.display(),
+                RECORDINGS_DIR
+            );
+            continue;
+        }
+
+        std::fs::create_dir_all(backup_dir.join(RECORDINGS_DIR))?;
+        info!(
+            "[REPAIR] moving recording {} => {}",
+            recording.display(),
+            destination.display()
+        );
+        move_file(&recording, &destination)?;
+        let checksum = checksum_path(&recording);
+        if checksum.exists() {
+            move_file(&checksum, &checksum_path(&destination))?;
+        }
+    }
+    Ok(())
+}
+
+fn repair_dir(backup_dir: &Path) -> Result<()> {
+    for entry in std::fs::read_dir(backup_dir)? {
+        let path = entry?.path();
+        if path.is_dir()
+            || is_tracked(&path)
+            || path.ends_with(CONFIG_FILE)
+            || path.ends_with(PINS_FILE)
+            || path.ends_with(NOTES_FILE)
+            || path.ends_with(LONG_NAMES_FILE)
+            || path.ends_with(MANIFEST_FILE)
+            || path.ends_with(STATE_CACHE_FILE)
+        {
+            continue;
+        }
+
+        if path.extension().unwrap_or_default() == OsStr::new(CHECKSUM_EXTENSION) {
+            if !path.with_extension("").exists() {
+                info!("[REPAIR] removing orphaned checksum {}", path.display());
+                std::fs::remove_file(&path)?;
+            }
+            continue;
+        }
+
+        if path.extension().unwrap_or_default() == OsStr::new(PARTIAL_EXTENSION) {
+            let modified = path.metadata()?.modified()?;
+            let age = SystemTime::now()
+                .duration_since(modified)
+                .unwrap_or_default();
+            if age > PARTIAL_MAX_AGE {
+                info!("[REPAIR] removing orphaned partial copy {}", path.display());
+                std::fs::remove_file(&path)?;
+            }
+        } else {
+            warn!("[REPAIR] unexpected file in backup dir: {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// Checks `dir` can be written to by writing a tiny file there and removing it again.
+pub fn check_writable(dir: &Path) -> std::io::Result<()> {
+    let probe = dir.join(WRITABLE_PROBE);
+    std::fs::write(&probe, b"ok")?;
+    std::fs::remove_file(&probe)
+}
+
+/// What happened when importing an old backup folder.
+#[derive(Default, Debug)]
+pub struct MigrateSummary {
+    pub copied: usize,
+    pub duplicates: usize,
+    pub conflicts: Vec<PathBuf>,
+}
+
+impl Display for MigrateSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "copied {}, skipped {} duplicates, {} name conflicts",
+            self.copied,
+            self.duplicates,
+            self.conflicts.len()
+        )?;
+        for conflict in &self.conflicts {
+            write!(f, "\n  conflict: {}", conflict.display())?;
+        }
+        Ok(())
+    }
+}
+
+/// Copies saves from an ad-hoc backup folder into `backup_dir`, encrypted with `cipher` if
+/// there is one. Files whose contents are already backed up are skipped, as are files that
+/// would overwrite a different backup of the same name. `old_dir` is left untouched.
+pub fn migrate(
+    old_dir: &Path,
+    backup_dir: &Path,
+    cipher: Option<&Cipher>,
+) -> Result<MigrateSummary> {
+    migrate_with(old_dir, backup_dir, cipher, |path| {
+        hash_contents(path, cipher)
+    })
+}
+
+// `migrate`, hashing files' contents with `hash`
+fn migrate_with(
+    old_dir: &Path,
+    backup_dir: &Path,
+    cipher: Option<&Cipher>,
+    hash: impl Fn(&Path) -> Result<u64>,
+) -> Result<MigrateSummary> {
+    // by hash, then compared in full, since two different files can share a hash
+    let mut known: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    for backup in backup_files(backup_dir)? {
+        known.entry(hash(&backup)?).or_default().push(backup);
+    }
+
+    let mut summary = MigrateSummary::default();
+    for old in files(old_dir)? {
+        let hash = hash(&old)?;
+        let mut duplicate = false;
+        for backup in known.get(&hash).into_iter().flatten() {
+            if same_contents(&old, backup, cipher)? {
+                duplicate = true;
+                break;
+            }
+        }
+        if duplicate {
+            summary.duplicates += 1;
+            continue;
+        }
+
+        let mut destination = backup_path(backup_dir, &key(&old));
+        if destination.exists() || encrypted_path(&destination).exists() {
+            summary.conflicts.push(old);
+            continue;
+        }
+        if cipher.is_some() {
+            destination = encrypted_path(&destination);
+        }
+
+        info!(
+            "[MIGRATE] copying {} => {}",
+            old.display(),
+            destination.display()
+        );
+        copy_new(&old, &destination, cipher, None)?;
+        write_checksum(&destination)?;
+        known.entry(hash).or_default().push(destination);
+        summary.copied += 1;
+    }
+    Ok(summary)
+}
+
+/// Copies `from` to `to` unless `to` already exists; returns whether anything was copied.
+fn copy_new(
+    from: &Path,
+    to: &Path,
+    cipher: Option<&Cipher>,
+    throttle: Option<&Throttle>,
+) -> Result<bool> {
+    if !from.exists() || to.exists() {
+        return Ok(false);
+    }
+
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    copy_atomic(from, to, cipher, throttle)?;
+    Ok(true)
+}
+
+// 'Saved #1 at depth 3.broguesave' restored alongside a different save of the same name is
+// 'Saved #1 at depth 3 [restored].broguesave', then '[restored 2]' and so on; the seed stays
+// readable and any comment is kept
+fn conflict_name(key: &str, n: usize) -> String {
+    let stem = Path::new(key)
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let mut comment = match split_comment(&stem) {
+        (_, Some(existing)) => format!("{} restored", existing),
+        (_, None) => "restored".to_string(),
+    };
+    if n > 1 {
+        comment.push_str(&format!(" {}", n));
+    }
+    with_comment(key, &comment)
+}
+
+// 'Saved #1 at depth 3.broguesave' moved into a folder that already has one is 'Saved #1 at
+// depth 3 [2].broguesave', then '[3]' and so on, keeping any comment and `.age` suffix, so it
+// still reads as the same game
+fn numbered_name(file_name: &str, n: usize) -> String {
+    let plain = plain_name(Path::new(file_name));
+    let stem = Path::new(&plain)
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let comment = match split_comment(&stem) {
+        (_, Some(existing)) => format!("{} {}", existing, n),
+        (_, None) => n.to_string(),
+    };
+    let numbered = with_comment(&plain, &comment);
+    match file_name.strip_prefix(plain.as_str()) {
+        Some(suffix) => format!("{}{}", numbered, suffix),
+        None => numbered,
+    }
+}
+
+// the last copy moved to `path`: the one with the highest number if there's more than one
+fn newest_moved(path: &Path) -> Option<PathBuf> {
+    let file_name = path.file_name()?.to_string_lossy().to_string();
+    let mut newest = None;
+    let mut candidate = path.to_path_buf();
+    let mut n = 2;
+    while candidate.exists() {
+        newest = Some(candidate);
+        candidate = path.with_file_name(numbered_name(&file_name, n));
+        n += 1;
+    }
+    newest
+}
+
+/// Copies `from` to `to`, decrypting `from` and encrypting `to` as their names call for, and
+/// no faster than `throttle` allows.
+pub fn copy_atomic(
+    from: &Path,
+    to: &Path,
+    cipher: Option<&Cipher>,
+    throttle: Option<&Throttle>,
+) -> Result<()> {
+    #[cfg(feature = "simulate-failure")]
+    crate::fault::maybe_fail(to)?;
+
+    // copy to a temp file first so a crash never leaves a half-written save behind
+    let partial = partial_path(to);
+    if is_encrypted(from) || is_encrypted(to) {
+        let mut contents = read_contents(from, cipher)?;
+        if is_encrypted(to) {
+            let cipher = cipher.ok_or_else(|| AppError::NoEncryptionKey(to.to_path_buf()))?;
+            contents = cipher.encrypt(&contents)?;
+        }
+        match throttle {
+            Some(throttle) => {
+                throttle.copy(
+                    &mut contents.as_slice(),
+                    &mut std::fs::File::create(&partial)?,
+                )?;
+            }
+            None => std::fs::write(&partial, contents)?,
+        }
+    } else {
+        match throttle {
+            Some(throttle) => {
+                let mut source = std::fs::File::open(from)?;
+                throttle.copy(&mut source, &mut std::fs::File::create(&partial)?)?;
+            }
+            None => {
+                std::fs::copy(from, &partial)?;
+            }
+        }
+    }
+    move_file(&partial, to)?;
+    Ok(())
+}
+
+/// Renames `from` to `to`. If they're on different volumes, where a rename can't work, copies
+/// `from` into a temp file next to `to`, checks the copy, then swaps it in and removes `from`.
+pub fn move_file(from: &Path, to: &Path) -> Result<()> {
+    match std::fs::rename(from, to) {
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            move_by_copy(from, to, |from, partial| std::fs::copy(from, partial))
+        }
+        result => Ok(result?),
+    }
+}
+
+// the cross-volume half of `move_file`, with the copy into the temp file done by `copy`. A copy
+// that doesn't match `from` is removed again, leaving `from` where it was.
+fn move_by_copy(
+    from: &Path,
+    to: &Path,
+    copy: impl FnOnce(&Path, &Path) -> std::io::Result<u64>,
+) -> Result<()> {
+    let partial = partial_path(to);
+    copy(from, &partial)?;
+    if hash_file(&partial)? != hash_file(from)? {
+        std::fs::remove_file(&partial)?;
+        return Err(AppError::CopyMismatch(to.to_path_buf()));
+    }
+    std::fs::rename(&partial, to)?;
+    std::fs::remove_file(from)?;
+    Ok(())
+}
+
+/// Whether `a` and `b` are on the same volume, so a file can be renamed from one to the other.
+/// Only unix tells us cheaply; elsewhere we assume they are.
+#[cfg(unix)]
+pub fn same_volume(a: &Path, b: &Path) -> Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+    Ok(a.metadata()?.dev() == b.metadata()?.dev())
+}
+
+#[cfg(not(unix))]
+pub fn same_volume(_a: &Path, _b: &Path) -> Result<bool> {
+    Ok(true)
+}
+
+/// Whether `a` and `b` are the same file, e.g. hard links to each other.
+#[cfg(unix)]
+pub fn same_file(a: &Path, b: &Path) -> Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+    let (a, b) = (a.metadata()?, b.metadata()?);
+    Ok(a.dev() == b.dev() && a.ino() == b.ino())
+}
+
+#[cfg(not(unix))]
+pub fn same_file(_a: &Path, _b: &Path) -> Result<bool> {
+    Ok(false)
+}
+
+// replaces `backup` with a hard link to `save`, via a temporary link so there's always a
+// backup in place; returns when the save was last modified
+fn link(save: &Path, backup: &Path) -> Result<SystemTime> {
+    let partial = partial_path(backup);
+    if partial.exists() {
+        std::fs::remove_file(&partial)?;
+    }
+    std::fs::hard_link(save, &partial)?;
+    std::fs::rename(&partial, backup)?;
+    Ok(save.metadata()?.modified()?)
+}
+
+/// A fast, non-cryptographic hash of a file's contents; `stable_hash`, so it's the same from
+/// one build to the next and can be written to disk.
+pub fn hash_file(path: &Path) -> Result<u64> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hash = STABLE_HASH_START;
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hash = continue_stable_hash(hash, &buf[..read]);
+    }
+    Ok(hash)
+}
+
+/// The saves and recordings in `dir`. Only failing to open `dir` itself is an error; entries
+/// that can't be read are logged and skipped so one bad file doesn't stop the whole scan.
+fn files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = std::fs::read_dir(dir)?.map(|entry| entry.map(|entry| entry.path()));
+    Ok(tracked_files(dir, entries))
+}
+
+// the saves and recordings among the entries of `dir`, skipping those that couldn't be read
+fn tracked_files(
+    dir: &Path,
+    entries: impl Iterator<Item = std::io::Result<PathBuf>>,
+) -> Vec<PathBuf> {
+    let mut res = vec![];
+    for entry in entries {
+        let path = match entry {
+            Ok(path) => path,
+            Err(e) => {
+                warn!("skipping unreadable entry in {}: {}", dir.display(), e);
+                continue;
+            }
+        };
+        if is_tracked(&path) {
+            res.push(path);
+        }
+    }
+    res
+}
+
+/// A quick comparison of a live save and its backup.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffSummary {
+    pub save_len: u64,
+    pub backup_len: u64,
+    pub save_modified: SystemTime,
+    pub backup_modified: SystemTime,
+    /// offset of the first byte that differs, if any; a shorter file differs where it ends
+    pub first_difference: Option<u64>,
+}
+
+fn diff_summary(save: &Path, backup: &Path, cipher: Option<&Cipher>) -> Result<DiffSummary> {
+    let save_metadata = save.metadata()?;
+    let backup_metadata = backup.metadata()?;
+    let save_file = std::fs::File::open(save)?;
+    // an encrypted backup is compared by what it decrypts to
+    let (backup_len, first_difference) = if is_encrypted(backup) {
+        let contents = read_contents(backup, cipher)?;
+        let first = first_difference(save_file, contents.as_slice())?;
+        (contents.len() as u64, first)
+    } else {
+        let first = first_difference(save_file, std::fs::File::open(backup)?)?;
+        (backup_metadata.len(), first)
+    };
+    Ok(DiffSummary {
+        save_len: save_metadata.len(),
+        backup_len,
+        save_modified: save_metadata.modified()?,
+        backup_modified: backup_metadata.modified()?,
+        first_difference,
+    })
+}
+
+/// Whether two files hold the same bytes, comparing what an encrypted one decrypts to.
+fn same_contents(a: &Path, b: &Path, cipher: Option<&Cipher>) -> Result<bool> {
+    let open = |path: &Path| -> Result<Box<dyn Read>> {
+        if is_encrypted(path) {
+            Ok(Box::new(std::io::Cursor::new(read_contents(path, cipher)?)))
+        } else {
+            Ok(Box::new(std::fs::File::open(path)?))
+        }
+    };
+    Ok(first_difference(open(a)?, open(b)?)?.is_none())
+}
+
+/// Compares two files chunk by chunk, stopping at the first difference.
+fn first_difference(a: impl Read, b: impl Read) -> Result<Option<u64>> {
+    let mut a = std::io::BufReader::new(a);
+    let mut b = std::io::BufReader::new(b);
+    let mut a_buf = [0u8; 64 * 1024];
+    let mut b_buf = [0u8; 64 * 1024];
+    let mut offset = 0u64;
+    loop {
+        let a_read = read_full(&mut a, &mut a_buf)?;
+        let b_read = read_full(&mut b, &mut b_buf)?;
+        let common = a_read.min(b_read);
+        if let Some(i) = (0..common).find(|&i| a_buf[i] != b_buf[i]) {
+            return Ok(Some(offset + i as u64));
+        }
+        if a_read != b_read {
+            return Ok(Some(offset + common as u64));
+        }
+        if a_read == 0 {
+            return Ok(None);
+        }
+        offset += a_read as u64;
+    }
+}
+
+// like `read`, but keeps going until the buffer is full or the file ends
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let read = reader.read(&mut buf[filled..])?;
+        if read == 0 {
+            break;
+        }
+        filled += read;
+    }
+    Ok(filled)
+}
+
+/// Whether `a` was modified after `b`.
+fn is_newer(a: &Path, b: &Path) -> Result<bool> {
+    Ok(a.metadata()?.modified()? > b.metadata()?.modified()?)
+}
+
+/// Whether `prefix`'s contents are the first bytes of `path`'s.
+fn is_prefix(prefix: &Path, path: &Path) -> Result<bool> {
+    let mut prefix = std::fs::File::open(prefix)?;
+    let mut file = std::fs::File::open(path)?;
+    let mut prefix_buf = [0u8; 64 * 1024];
+    let mut file_buf = [0u8; 64 * 1024];
+    loop {
+        let read = prefix.read(&mut prefix_buf)?;
+        if read == 0 {
+            return Ok(true);
+        }
+        match file.read_exact(&mut file_buf[..read]) {
+            Ok(()) => {}
+            // `path` ends first, so it's shorter than `prefix`
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(false),
+            Err(e) => return Err(e.into()),
+        }
+        if prefix_buf[..read] != file_buf[..read] {
+            return Ok(false);
+        }
+    }
+}
+
+/// Whether `path` is a brogue save or recording, encrypted or not.
+pub fn is_tracked(path: &Path) -> bool {
+    is_brogue_save(path) || is_brogue_recording(path)
+}
+
+// both look past any `.age` suffix, so encrypted backups count too
+fn is_brogue_recording(path: &Path) -> bool {
+    let name = key(path);
+    !path.is_dir()
+        && Path::new(&name).extension().unwrap_or_default() == OsStr::new(RECORDING_EXTENSION)
+}
+
+fn is_brogue_save(path: &Path) -> bool {
+    let name = key(path);
+    !path.is_dir()
+        && Path::new(&name).extension().unwrap_or_default() == OsStr::new(SAVE_EXTENSION)
+        && name.starts_with("Saved")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PrunePolicy;
+    use crate::testing::{names, save_name, set_age, write, Dirs};
+
+    #[test]
+    fn displays_each_kind_of_save() {
+        let live = PathBuf::from("/saves/Saved #1 at depth 2.broguesave");
+        let backup = PathBuf::from("/backups/Saved #1 at depth 2.broguesave");
+        assert_eq!(
+            Save::OriginalFileOnly(live.clone()).to_string(),
+            "SAVE S<-xB Saved #1 at depth 2.broguesave"
+        );
+        assert_eq!(
+            Save::BackupFileOnly(backup.clone()).to_string(),
+            "BACK Sx->B Saved #1 at depth 2.broguesave"
+        );
+        assert_eq!(
+            Save::Both(live, backup).to_string(),
+            "SYNC S<->B Saved #1 at depth 2.broguesave"
+        );
+    }
+
+    #[test]
+    fn displays_an_encrypted_backup_by_its_save_name() {
+        let backup = PathBuf::from("/backups/Saved #1 at depth 2.broguesave.age");
+        assert_eq!(
+            Save::BackupFileOnly(backup).to_string(),
+            "BACK Sx->B Saved #1 at depth 2.broguesave"
+        );
+    }
+
+    fn mode(mode: SyncMode) -> Config {
+        Config {
+            mode,
+            ..Config::default()
+        }
+    }
+
+    // backs up a save, then deletes it from the save dir as brogue does when a game ends
+    fn back_up_then_delete(dirs: &Dirs, app: &mut App, name: &str) {
+        let live = dirs.save(name, "game");
+        app.scan();
+        app.reconcile().unwrap();
+        app.scan();
+        assert!(matches!(app.save_by_key(name), Some(Save::Both(..))));
+        // the next tick sees it synced
+        app.reconcile().unwrap();
+        std::fs::remove_file(live).unwrap();
+        app.scan();
+    }
+
+    #[test]
+    fn archive_mode_archives_a_save_brogue_deleted() {
+        let dirs = Dirs::new();
+        let mut app = dirs.app(mode(SyncMode::Archive));
+        back_up_then_delete(&dirs, &mut app, &save_name(1, 1));
+
+        assert!(matches!(app.plan()[..], [SyncAction::Archive(_)]));
+        app.reconcile().unwrap();
+        assert!(!dirs.save_dir.join(save_name(1, 1)).exists());
+        assert!(dirs
+            .backup_dir
+            .join(ARCHIVE_DIR)
+            .join(save_name(1, 1))
+            .exists());
+    }
+
+    #[test]
+    fn mirror_mode_restores_a_save_brogue_deleted() {
+        let dirs = Dirs::new();
+        let mut app = dirs.app(mode(SyncMode::Mirror));
+        back_up_then_delete(&dirs, &mut app, &save_name(1, 1));
+
+        app.reconcile().unwrap();
+        assert!(dirs.save_dir.join(save_name(1, 1)).exists());
+        assert!(names(&dirs.backup_dir.join(ARCHIVE_DIR)).is_empty());
+    }
+
+    #[test]
+    fn archive_mode_archives_a_save_deleted_while_we_were_closed() {
+        let dirs = Dirs::new();
+        let mut app = dirs.app(mode(SyncMode::Archive));
+        let live = dirs.save(&save_name(1, 1), "game");
+        app.scan();
+        app.reconcile().unwrap();
+        app.scan();
+        app.record_session().unwrap();
+        drop(app);
+        std::fs::remove_file(live).unwrap();
+
+        let mut app = dirs.app(mode(SyncMode::Archive));
+        app.reconcile().unwrap();
+        assert!(!dirs.save_dir.join(save_name(1, 1)).exists());
+        assert!(dirs
+            .backup_dir
+            .join(ARCHIVE_DIR)
+            .join(save_name(1, 1))
+            .exists());
+    }
+
+    #[test]
+    fn archive_mode_restores_a_backup_that_was_never_synced() {
+        let dirs = Dirs::new();
+        dirs.backup(&save_name(1, 1), "game");
+        let mut app = dirs.app(mode(SyncMode::Archive));
+
+        app.reconcile().unwrap();
+        assert!(dirs.save_dir.join(save_name(1, 1)).exists());
+    }
+
+    #[test]
+    fn a_failed_archive_does_not_stop_the_rest_of_the_sync() {
+        let dirs = Dirs::new();
+        let mut app = dirs.app(mode(SyncMode::Archive));
+        back_up_then_delete(&dirs, &mut app, &save_name(1, 1));
+        std::fs::write(dirs.backup_dir.join(ARCHIVE_DIR), "not a dir").unwrap();
+        dirs.save(&save_name(2, 1), "new game");
+        app.scan();
+
+        app.reconcile().unwrap();
+        assert!(dirs.backup_dir.join(save_name(2, 1)).exists());
+        assert!(dirs.backup_dir.join(save_name(1, 1)).exists());
+        assert!(app
+            .status()
+            .is_some_and(|s| s.starts_with("could not archive")));
+        // and the next tick tries again rather than restoring it
+        assert!(matches!(app.plan()[..], [SyncAction::Archive(_)]));
+    }
+
+    #[test]
+    fn shows_last_sessions_saves_as_stale_until_the_background_scan_is_done() {
+        let dirs = Dirs::new();
+        dirs.save(&save_name(1, 1), "game");
+        dirs.app(Config::default()).record_session().unwrap();
+        dirs.save(&save_name(2, 1), "new game");
+
+        let mut app = dirs.unscanned_app(Config::default());
+        app.show_cached_state();
+        assert!(app.is_stale());
+        assert_eq!(app.tracked_saves(), BTreeSet::from([save_name(1, 1)]));
+
+        app.scan_in_background();
+        let mut tries = 0;
+        while !app.scan() {
+            assert!(app.is_stale() && app.is_scanning());
+            assert!(tries < 500, "the background scan never finished");
+            tries += 1;
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!app.is_stale() && !app.is_scanning());
+        assert_eq!(
+            app.tracked_saves(),
+            BTreeSet::from([save_name(1, 1), save_name(2, 1)])
+        );
+    }
+
+    #[test]
+    fn nothing_is_stale_without_a_cache() {
+        let dirs = Dirs::new();
+        dirs.save(&save_name(1, 1), "game");
+        let mut app = dirs.unscanned_app(Config::default());
+        app.show_cached_state();
+        assert!(!app.is_stale());
+        assert!(app.tracked_saves().is_empty());
+    }
+
+    // too long to back up as-is, but with room for a comment
+    fn long_name() -> String {
+        format!("Saved #12345 at depth 3 {}.broguesave", "x".repeat(195))
+    }
+
+    #[test]
+    fn backs_up_a_long_name_under_a_short_one_and_restores_it_under_its_own() {
+        let dirs = Dirs::new();
+        let full = long_name();
+        let short = short_name(full.clone());
+        assert!(short.len() <= MAX_KEY_BYTES && short != full);
+
+        let live = dirs.save(&full, "game");
+        let mut app = dirs.app(Config::default());
+        app.reconcile().unwrap();
+        assert_eq!(
+            dirs.backups(),
+            [
+                short.clone(),
+                format!("{}.sum", short),
+                LONG_NAMES_FILE.to_string()
+            ]
+        );
+        std::fs::remove_file(live).unwrap();
+
+        // a new run, which only has the shortened name to go on
+        let mut app = dirs.app(Config::default());
+        app.reconcile().unwrap();
+        assert_eq!(names(&dirs.save_dir), [full]);
+    }
+
+    #[test]
+    fn renames_a_save_with_a_long_name() {
+        let dirs = Dirs::new();
+        let full = long_name();
+        dirs.save(&full, "game");
+        let mut app = dirs.app(Config::default());
+        app.reconcile().unwrap();
+        app.scan();
+
+        let renamed = app.rename(&short_name(full.clone()), "stairs").unwrap();
+        let renamed_full = with_comment(&full, "stairs");
+        assert_eq!(renamed, short_name(renamed_full.clone()));
+        assert_eq!(names(&dirs.save_dir), [renamed_full.as_str()]);
+        assert_eq!(
+            dirs.backups(),
+            [
+                renamed.clone(),
+                format!("{}.sum", renamed),
+                LONG_NAMES_FILE.to_string()
+            ]
+        );
+
+        app.scan();
+        assert!(matches!(app.save_by_key(&renamed), Some(Save::Both(..))));
+        // and it comes back under its new name
+        std::fs::remove_file(dirs.save_dir.join(&renamed_full)).unwrap();
+        let mut app = dirs.app(Config::default());
+        app.reconcile().unwrap();
+        assert_eq!(names(&dirs.save_dir), [renamed_full]);
+    }
+
+    #[test]
+    fn deleting_with_no_saves_says_so() {
+        let dirs = Dirs::new();
+        let mut app = dirs.app(Config::default());
+        app.start_delete();
+        assert_eq!(app.delete_state, DeleteState::NotDeleting);
+        assert_eq!(app.status(), Some("nothing to delete"));
+    }
+
+    #[test]
+    fn a_letter_past_the_end_of_the_list_is_rejected() {
+        let dirs = Dirs::new();
+        dirs.save(&save_name(1, 1), "game");
+        let mut app = dirs.app(Config::default());
+        app.start_delete();
+        app.choose_delete(3);
+        assert_eq!(app.status(), Some("no save 'd'"));
+        assert_eq!(app.delete_state, DeleteState::AwaitingIndex);
+        app.reconcile().unwrap();
+        assert_eq!(names(&dirs.save_dir), [save_name(1, 1)]);
+    }
+
+    #[test]
+    fn a_save_past_z_is_deleted_through_the_selection() {
+        let dirs = Dirs::new();
+        for n in 0..30 {
+            let save = dirs.save(&save_name(n, 1), "game");
+            set_age(&save, Duration::from_secs(60 + u64::from(n)));
+        }
+        let mut app = dirs.app(Config::default());
+        app.selected = 27;
+        let chosen = app.selected_save().unwrap().key();
+
+        app.start_delete();
+        app.choose_selected_delete();
+        assert_eq!(app.delete_state, DeleteState::Delete(27));
+        assert_eq!(app.delete_label(27), chosen);
+        app.reconcile().unwrap();
+        assert!(!dirs.save_dir.join(&chosen).exists());
+        assert_eq!(names(&dirs.save_dir).len(), 29);
+    }
+
+    fn dedup_config(dedup: DedupMode) -> Config {
+        Config {
+            dedup,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn dedup_trashes_the_older_of_two_identical_backups() {
+        let dirs = Dirs::new();
+        let older = dirs.backup(&save_name(1, 1), "same");
+        set_age(&older, Duration::from_secs(120));
+        dirs.backup(&save_name(2, 1), "same");
+        dirs.backup(&save_name(3, 1), "different");
+        let mut app = dirs.app(dedup_config(DedupMode::Trash));
+
+        assert_eq!(app.reconcile().unwrap(), 1);
+        let saves: Vec<_> = dirs
+            .backups()
+            .into_iter()
+            .filter(|n| is_brogue_save(Path::new(n)))
+            .collect();
+        assert_eq!(saves, vec![save_name(2, 1), save_name(3, 1)]);
+        assert_eq!(
+            names(&dirs.backup_dir.join(TRASH_DIR)),
+            vec![save_name(1, 1)]
+        );
+    }
+
+    #[test]
+    fn dedup_keeps_backups_whose_hashes_collide() {
+        let dirs = Dirs::new();
+        let one = dirs.backup(&save_name(1, 1), "one");
+        let two = dirs.backup(&save_name(2, 1), "two");
+        let app = dirs.app(dedup_config(DedupMode::Remove));
+        app.hashes.insert(&one, 7);
+        app.hashes.insert(&two, 7);
+
+        assert!(!app.dedup());
+        assert!(one.exists() && two.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dedup_skips_a_backup_it_cannot_read() {
+        let dirs = Dirs::new();
+        let older = dirs.backup(&save_name(1, 1), "same");
+        set_age(&older, Duration::from_secs(120));
+        dirs.backup(&save_name(2, 1), "same");
+        let dangling = dirs.backup_dir.join(save_name(3, 1));
+        std::os::unix::fs::symlink(dirs.backup_dir.join("nowhere"), &dangling).unwrap();
+        let mut app = dirs.app(dedup_config(DedupMode::Trash));
+
+        assert_eq!(app.reconcile().unwrap(), 1);
+        assert!(!older.exists());
+        assert!(dangling.symlink_metadata().is_ok());
+    }
+
+    #[test]
+    fn on_tick_survives_saves_vanishing_after_the_scan() {
+        let dirs = Dirs::new();
+        let saves: Vec<_> = (1..=5)
+            .map(|n| dirs.save(&save_name(n, 1), "save"))
+            .collect();
+        let mut app = dirs.app(Config::default());
+        for save in &saves[..3] {
+            std::fs::remove_file(save).unwrap();
+        }
+
+        // sorting by age reads the metadata of files that are gone
+        let ages: Vec<_> = app.visible_saves().iter().map(|s| s.age()).collect();
+        assert_eq!(ages.len(), 5);
+        app.on_tick();
+        assert!(app.scan());
+        app.on_tick();
+
+        let backed_up: Vec<_> = dirs
+            .backups()
+            .into_iter()
+            .filter(|name| name.ends_with(".broguesave"))
+            .collect();
+        assert_eq!(backed_up, vec![save_name(4, 1), save_name(5, 1)]);
+    }
+
+    #[test]
+    fn on_tick_survives_an_unwritable_backup_dir() {
+        let dirs = Dirs::new();
+        dirs.save(&save_name(1, 1), "save");
+        let mut app = dirs.app(Config {
+            writable_check_ms: 60 * 60 * 1000,
+            ..Config::default()
+        });
+        // the writable check passes, then the dir goes
+        app.on_tick();
+        std::fs::remove_dir_all(&dirs.backup_dir).unwrap();
+        std::fs::write(&dirs.backup_dir, "not a dir").unwrap();
+        dirs.save(&save_name(2, 1), "save");
+
+        app.on_tick();
+        app.scan();
+        app.on_tick();
+        assert!(app.status().is_some_and(|s| s.starts_with("could not")));
+    }
+
+    #[test]
+    fn a_failed_scan_is_shown_not_returned() {
+        let dirs = Dirs::new();
+        dirs.save(&save_name(1, 1), "save");
+        let mut app = dirs.app(Config::default());
+        std::fs::remove_dir_all(&dirs.save_dir).unwrap();
+        std::fs::write(&dirs.save_dir, "not a dir").unwrap();
+
+        assert!(!app.scan());
+        assert!(app
+            .status()
+            .is_some_and(|s| s.starts_with("could not scan")));
+        // the last good scan is kept
+        assert_eq!(app.visible_saves().len(), 1);
+    }
+
+    #[test]
+    fn repair_removes_stale_partial_copies_and_orphaned_checksums() {
+        let dirs = Dirs::new();
+        dirs.backup(&save_name(1, 1), "game");
+        dirs.backup(&format!("{}.sum", save_name(1, 1)), "1234");
+        dirs.backup(&format!("{}.sum", save_name(2, 1)), "5678");
+        let stale = dirs.backup(&format!("{}.partial", save_name(3, 1)), "half");
+        set_age(&stale, PARTIAL_MAX_AGE * 2);
+        let fresh = dirs.backup(&format!("{}.partial", save_name(4, 1)), "half");
+        set_age(&fresh, Duration::ZERO);
+        dirs.backup("notes to self.txt", "mine");
+
+        repair(&dirs.backup_dir).unwrap();
+        assert_eq!(
+            dirs.backups(),
+            [
+                "Saved #1 at depth 1.broguesave",
+                "Saved #1 at depth 1.broguesave.sum",
+                // still being written, perhaps
+                "Saved #4 at depth 1.broguesave.partial",
+                "notes to self.txt",
+            ]
+        );
+    }
+
+    #[test]
+    fn repair_moves_recordings_into_their_own_folder() {
+        let dirs = Dirs::new();
+        dirs.backup("Saved #1 at depth 1.broguerec", "replay");
+        dirs.backup("Saved #1 at depth 1.broguerec.sum", "1234");
+
+        repair(&dirs.backup_dir).unwrap();
+        assert!(dirs.backups().is_empty());
+        assert_eq!(
+            names(&dirs.backup_dir.join(RECORDINGS_DIR)),
+            [
+                "Saved #1 at depth 1.broguerec",
+                "Saved #1 at depth 1.broguerec.sum"
+            ]
+        );
+        // and a second run has nothing to do
+        repair(&dirs.backup_dir).unwrap();
+        assert_eq!(names(&dirs.backup_dir.join(RECORDINGS_DIR)).len(), 2);
+    }
+
+    #[test]
+    fn repair_leaves_a_recording_that_is_in_both_places() {
+        let dirs = Dirs::new();
+        dirs.backup("Saved #1 at depth 1.broguerec", "old");
+        std::fs::create_dir_all(dirs.backup_dir.join(RECORDINGS_DIR)).unwrap();
+        std::fs::write(
+            dirs.backup_dir
+                .join(RECORDINGS_DIR)
+                .join("Saved #1 at depth 1.broguerec"),
+            "new",
+        )
+        .unwrap();
+
+        repair(&dirs.backup_dir).unwrap();
+        assert_eq!(dirs.backups(), ["Saved #1 at depth 1.broguerec"]);
+    }
+
+    // waits a few seconds for a hook, which runs in the background, to make `path`
+    #[cfg(unix)]
+    fn wait_for(path: &Path) -> bool {
+        (0..500).any(|_| {
+            std::thread::sleep(Duration::from_millis(10));
+            path.exists()
+        })
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn runs_the_hooks_with_the_backup_and_restored_save() {
+        let dirs = Dirs::new();
+        let hooked = tempfile::tempdir().unwrap();
+        let (backed_up, restored) = (
+            hooked.path().join("backed-up"),
+            hooked.path().join("restored"),
+        );
+        std::fs::create_dir_all(&backed_up).unwrap();
+        std::fs::create_dir_all(&restored).unwrap();
+        dirs.save(&save_name(1, 1), "game");
+        dirs.backup(&save_name(2, 1), "older game");
+        let mut app = dirs.app(Config {
+            // GNU cp's -t takes the folder first, so the path can go last
+            post_backup_cmd: Some(format!("cp -t {}", backed_up.display())),
+            post_restore_cmd: Some(format!("cp -t {}", restored.display())),
+            ..Config::default()
+        });
+
+        app.reconcile().unwrap();
+        assert!(wait_for(&backed_up.join(save_name(1, 1))));
+        assert!(wait_for(&restored.join(save_name(2, 1))));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn runs_the_backup_hook_after_appending_to_a_recording() {
+        use std::io::Write;
+        let dirs = Dirs::new();
+        let hooked = tempfile::tempdir().unwrap();
+        let recording = dirs.save(&recording_name(1), "turn 1;");
+        let mut app = dirs.app(Config {
+            post_backup_cmd: Some(format!("cp -t {}", hooked.path().display())),
+            ..Config::default()
+        });
+        app.reconcile().unwrap();
+        let copied = hooked.path().join(recording_name(1));
+        assert!(wait_for(&copied));
+        std::fs::remove_file(&copied).unwrap();
+
+        let mut file = std::fs::File::options()
+            .append(true)
+            .open(&recording)
+            .unwrap();
+        file.write_all(b" turn 2;").unwrap();
+        drop(file);
+        set_age(&recording, Duration::from_secs(30));
+        app.scan();
+        assert!(matches!(
+            app.plan().as_slice(),
+            [SyncAction::AppendRecording { .. }]
+        ));
+        app.reconcile().unwrap();
+        assert!(wait_for(&copied));
+        assert_eq!(std::fs::read_to_string(&copied).unwrap(), "turn 1; turn 2;");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn runs_a_hook_whose_program_has_spaces_in_its_path() {
+        use std::os::unix::fs::PermissionsExt;
+        let dirs = Dirs::new();
+        let hooked = tempfile::tempdir().unwrap();
+        let scripts = hooked.path().join("my hooks");
+        std::fs::create_dir_all(&scripts).unwrap();
+        let script = scripts.join("copy it.sh");
+        std::fs::write(&script, "#!/bin/sh\ncp \"$2\" \"$1\"\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let copied = hooked.path().join("it's copied");
+        dirs.save(&save_name(1, 1), "game");
+        let mut app = dirs.app(Config {
+            post_backup_cmd: Some(format!("'{}' \"{}\"", script.display(), copied.display())),
+            ..Config::default()
+        });
+
+        app.reconcile().unwrap();
+        assert!(wait_for(&copied));
+        assert_eq!(std::fs::read_to_string(&copied).unwrap(), "game");
+    }
+
+    #[test]
+    fn a_failing_hook_does_not_stop_the_backup() {
+        let dirs = Dirs::new();
+        dirs.save(&save_name(1, 1), "game");
+        for cmd in ["false", "no-such-program --flag", ""] {
+            let mut app = dirs.app(Config {
+                post_backup_cmd: Some(cmd.to_string()),
+                ..Config::default()
+            });
+            app.reconcile().unwrap();
+            assert!(dirs.backup_dir.join(save_name(1, 1)).exists());
+            std::fs::remove_file(dirs.backup_dir.join(save_name(1, 1))).unwrap();
+        }
+    }
+
+    #[test]
+    fn deleting_can_be_turned_off() {
+        let dirs = Dirs::new();
+        dirs.save(&save_name(1, 1), "game");
+        let mut app = dirs.app(Config {
+            allow_delete: false,
+            ..Config::default()
+        });
+        app.start_delete();
+        assert_eq!(app.delete_state, DeleteState::NotDeleting);
+        app.reconcile().unwrap();
+        assert_eq!(names(&dirs.save_dir), [save_name(1, 1)]);
+    }
+
+    #[test]
+    fn restores_from_an_extra_copy_then_an_older_version_when_the_backup_is_damaged() {
+        let dirs = Dirs::new();
+        let extra = tempfile::tempdir().unwrap();
+        let name = save_name(1, 1);
+        let backup = dirs.backup(&name, "truncat");
+        std::fs::write(checksum_path(&backup), "fnv1a:0000000000000000\n").unwrap();
+        let copy = write(extra.path(), &name, "from the extra dir");
+        write_checksum(&copy).unwrap();
+        let versions_dir = dirs.backup_dir.join(VERSIONS_DIR).join(&name);
+        std::fs::create_dir_all(&versions_dir).unwrap();
+        let older = write(&versions_dir, &format!("1000 {}", name), "older");
+        // empty, so no use either
+        write(&versions_dir, &format!("2000 {}", name), "");
+        let mut app = dirs.app(Config {
+            extra_backup_dirs: vec![extra.path().to_path_buf()],
+            ..Config::default()
+        });
+        let save = app.save_by_key(&name).unwrap().clone();
+
+        assert_eq!(app.best_backup(&save).unwrap(), copy);
+        std::fs::write(&copy, "damaged too").unwrap();
+        assert_eq!(app.best_backup(&save).unwrap(), older);
+        app.reconcile().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dirs.save_dir.join(&name)).unwrap(),
+            "older"
+        );
+
+        std::fs::write(&older, "").unwrap();
+        assert!(matches!(
+            app.best_backup(&save),
+            Err(AppError::NoIntactBackup(_))
+        ));
+    }
+
+    #[test]
+    fn plans_every_restore_before_any_backup() {
+        let dirs = Dirs::new();
+        // newest first: live, backup, live, backup
+        for (n, save) in [(1, true), (2, false), (3, true), (4, false)] {
+            let name = save_name(n, 1);
+            let path = if save {
+                dirs.save(&name, "game")
+            } else {
+                dirs.backup(&name, "game")
+            };
+            set_age(&path, Duration::from_secs(60 * u64::from(n)));
+        }
+        let app = dirs.app(Config::default());
+
+        let plan: Vec<_> = app
+            .plan()
+            .iter()
+            .map(|action| (action.kind(), action.key()))
+            .collect();
+        assert_eq!(
+            plan,
+            [
+                ("restore", save_name(2, 1)),
+                ("restore", save_name(4, 1)),
+                ("backup", save_name(1, 1)),
+                ("backup", save_name(3, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_an_unreadable_entry_and_keeps_scanning() {
+        let dirs = Dirs::new();
+        let saves = [
+            dirs.save(&save_name(1, 1), "game"),
+            dirs.save(&save_name(2, 1), "game"),
+        ];
+        let entries = vec![
+            Ok(saves[0].clone()),
+            Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                "unreadable",
+            )),
+            Ok(dirs.save_dir.join("notes.txt")),
+            Ok(saves[1].clone()),
+        ];
+
+        assert_eq!(
+            tracked_files(&dirs.save_dir, entries.into_iter()),
+            saves.to_vec()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn plans_around_a_save_it_cannot_read() {
+        let dirs = Dirs::new();
+        let unreadable = save_name(1, 1);
+        std::os::unix::fs::symlink(
+            dirs.save_dir.join("nowhere"),
+            dirs.save_dir.join(&unreadable),
+        )
+        .unwrap();
+        dirs.backup(&unreadable, "game");
+        dirs.save(&save_name(2, 1), "game");
+        let mut app = dirs.app(Config::default());
+        assert!(matches!(app.save_by_key(&unreadable), Some(Save::Both(..))));
+
+        let (plan, skipped) = app.plan_and_skipped();
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].key(), save_name(2, 1));
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].0, unreadable);
+
+        assert_eq!(app.reconcile().unwrap(), 1);
+        assert!(dirs.backup_dir.join(save_name(2, 1)).exists());
+        assert!(app
+            .status()
+            .is_some_and(|s| s.starts_with(&format!("could not back up '{}'", unreadable))));
+    }
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    // a backup in `subdir` of the backup dir, written `age` ago
+    fn old_backup(dirs: &Dirs, subdir: &str, name: &str, age: Duration) -> PathBuf {
+        let dir = dirs.backup_dir.join(subdir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = write(&dir, name, "game");
+        set_age(&path, age);
+        path
+    }
+
+    #[test]
+    fn pruning_leaves_pinned_saves_alone_even_when_they_are_the_oldest() {
+        let dirs = Dirs::new();
+        let (old_game, older_game) = (save_name(1, 1), save_name(2, 1));
+        old_backup(&dirs, "", &old_game, 10 * DAY);
+        old_backup(&dirs, "", &older_game, 20 * DAY);
+        let archived = save_name(3, 1);
+        old_backup(&dirs, ARCHIVE_DIR, &archived, 30 * DAY);
+        let (recording, old_recording) = (
+            "Saved #4 at depth 1.broguerec",
+            "Saved #5 at depth 1.broguerec",
+        );
+        old_backup(&dirs, RECORDINGS_DIR, recording, DAY);
+        old_backup(&dirs, RECORDINGS_DIR, old_recording, 2 * DAY);
+        let mut app = dirs.app(Config {
+            mode: SyncMode::BackupOnly,
+            keep_recordings: Some(0),
+            prune_default: PrunePolicy {
+                max_age_days: Some(7),
+            },
+            ..Config::default()
+        });
+        for pinned in [&older_game, &archived, old_recording] {
+            app.pins.toggle(pinned).unwrap();
+        }
+
+        while app.reconcile().unwrap() > 0 {
+            app.scan();
+        }
+        assert_eq!(
+            names(&dirs.backup_dir.join(TRASH_DIR)),
+            [old_game.as_str(), recording]
+        );
+        assert!(dirs.backup_dir.join(&older_game).exists());
+        assert_eq!(names(&dirs.backup_dir.join(ARCHIVE_DIR)), [archived]);
+        assert_eq!(
+            names(&dirs.backup_dir.join(RECORDINGS_DIR)),
+            [old_recording]
+        );
+    }
+
+    fn recording_name(n: u32) -> String {
+        format!("Saved #{} at depth 1.broguerec", n)
+    }
+
+    #[test]
+    fn backs_up_recordings_into_their_own_folder() {
+        let dirs = Dirs::new();
+        dirs.save(&recording_name(1), "replay");
+        dirs.save(&save_name(2, 1), "game");
+        let mut app = dirs.app(Config::default());
+
+        app.reconcile().unwrap();
+        assert_eq!(
+            names(&dirs.backup_dir.join(RECORDINGS_DIR)),
+            [recording_name(1), format!("{}.sum", recording_name(1))]
+        );
+        assert!(!dirs.backup_dir.join(recording_name(1)).exists());
+        assert!(dirs.backup_dir.join(save_name(2, 1)).exists());
+    }
+
+    #[test]
+    fn keeps_only_the_newest_recordings_once_they_are_gone_from_the_save_dir() {
+        let dirs = Dirs::new();
+        for n in 1..=4 {
+            old_backup(&dirs, RECORDINGS_DIR, &recording_name(n), n * DAY);
+        }
+        // a live recording doesn't count towards the limit, however old
+        let live = dirs.save(&recording_name(5), "game");
+        set_age(&live, 10 * DAY);
+        old_backup(&dirs, RECORDINGS_DIR, &recording_name(5), 10 * DAY);
+        let config = |keep_recordings| Config {
+            mode: SyncMode::BackupOnly,
+            keep_recordings,
+            ..Config::default()
+        };
+
+        // with no limit, they're all kept
+        let mut app = dirs.app(config(None));
+        assert_eq!(app.reconcile().unwrap(), 0);
+        assert!(names(&dirs.backup_dir.join(TRASH_DIR)).is_empty());
+
+        let mut app = dirs.app(config(Some(2)));
+        assert_eq!(app.reconcile().unwrap(), 1);
+        assert_eq!(
+            names(&dirs.backup_dir.join(TRASH_DIR)),
+            [recording_name(3), recording_name(4)]
+        );
+        assert_eq!(
+            names(&dirs.backup_dir.join(RECORDINGS_DIR)),
+            [recording_name(1), recording_name(2), recording_name(5)]
+        );
+    }
+
+    #[test]
+    fn prunes_each_difficulty_on_its_own_schedule() {
+        let dirs = Dirs::new();
+        let easy = "Saved #1 at depth 1 (easy).broguesave";
+        let normal = "Saved #2 at depth 1.broguesave";
+        let wizard = "Saved #3 at depth 1 (wizard).broguesave";
+        // difficulties we can't read go by the default
+        let unknown = "Saved #4 at depth 1 (nightmare).broguesave";
+        let recent_easy = "Saved #5 at depth 1 (easy).broguesave";
+        for name in [easy, normal, wizard, unknown] {
+            old_backup(&dirs, "", name, 10 * DAY);
+        }
+        old_backup(&dirs, ARCHIVE_DIR, recent_easy, 3 * DAY);
+        let policy = |days| PrunePolicy {
+            max_age_days: Some(days),
+        };
+        let mut app = dirs.app(Config {
+            mode: SyncMode::BackupOnly,
+            prune: BTreeMap::from([
+                ("easy".to_string(), policy(7)),
+                ("wizard".to_string(), policy(30)),
+            ]),
+            prune_default: policy(5),
+            ..Config::default()
+        });
+
+        assert_eq!(app.reconcile().unwrap(), 1);
+        assert_eq!(
+            names(&dirs.backup_dir.join(TRASH_DIR)),
+            [easy, normal, unknown]
+        );
+        assert_eq!(dirs.backups(), [wizard]);
+        assert_eq!(names(&dirs.backup_dir.join(ARCHIVE_DIR)), [recent_easy]);
+    }
+
+    #[test]
+    fn mirror_mode_restores_an_old_backup_only_save_rather_than_pruning_it() {
+        let dirs = Dirs::new();
+        let (resumed, archived) = (save_name(1, 1), save_name(2, 1));
+        old_backup(&dirs, "", &resumed, 10 * DAY);
+        old_backup(&dirs, ARCHIVE_DIR, &archived, 10 * DAY);
+        let mut app = dirs.app(Config {
+            prune_default: PrunePolicy {
+                max_age_days: Some(5),
+            },
+            ..Config::default()
+        });
+
+        // the first pass prunes, the second restores
+        for _ in 0..2 {
+            app.reconcile().unwrap();
+            app.update_state().unwrap();
+        }
+        assert_eq!(names(&dirs.save_dir), [resumed.as_str()]);
+        assert_eq!(dirs.backups(), [resumed.as_str()]);
+        assert_eq!(names(&dirs.backup_dir.join(TRASH_DIR)), [archived]);
+    }
+
+    #[test]
+    fn a_game_that_cannot_be_pruned_does_not_stop_the_sync() {
+        let dirs = Dirs::new();
+        old_backup(&dirs, ARCHIVE_DIR, &save_name(1, 1), 10 * DAY);
+        // nothing can be moved into the trash
+        std::fs::write(dirs.backup_dir.join(TRASH_DIR), "").unwrap();
+        dirs.save(&save_name(2, 1), "game");
+        let mut app = dirs.app(Config {
+            prune_default: PrunePolicy {
+                max_age_days: Some(5),
+            },
+            ..Config::default()
+        });
+
+        app.reconcile().unwrap();
+        assert!(dirs
+            .backup_dir
+            .join(ARCHIVE_DIR)
+            .join(save_name(1, 1))
+            .exists());
+        assert!(dirs.backup_dir.join(save_name(2, 1)).exists());
+    }
+
+    #[test]
+    fn a_file_shorter_than_the_prefix_does_not_start_with_it() {
+        let dirs = Dirs::new();
+        let long = dirs.save("long", "turn 1; turn 2;");
+        let short = dirs.save("short", "turn 1;");
+        assert!(is_prefix(&short, &long).unwrap());
+        assert!(!is_prefix(&long, &short).unwrap());
+    }
+
+    #[test]
+    fn moves_across_volumes_by_copying_then_swapping_the_copy_in() {
+        let dirs = Dirs::new();
+        let from = dirs.save(&save_name(1, 1), "game");
+        let to = dirs.backup_dir.join(save_name(1, 1));
+
+        move_by_copy(&from, &to, |from, partial| std::fs::copy(from, partial)).unwrap();
+        assert!(!from.exists());
+        assert_eq!(std::fs::read_to_string(&to).unwrap(), "game");
+        assert_eq!(dirs.backups(), [save_name(1, 1)]);
+    }
+
+    #[test]
+    fn a_cross_volume_copy_that_does_not_match_is_removed() {
+        let dirs = Dirs::new();
+        let from = dirs.save(&save_name(1, 1), "game");
+        let to = dirs.backup_dir.join(save_name(1, 1));
+
+        let moved = move_by_copy(&from, &to, |_, partial| {
+            std::fs::write(partial, "ga").map(|_| 2)
+        });
+        assert!(matches!(moved, Err(AppError::CopyMismatch(path)) if path == to));
+        assert_eq!(std::fs::read_to_string(&from).unwrap(), "game");
+        assert!(dirs.backups().is_empty());
+    }
+
+    #[test]
+    fn leaves_a_save_bigger_than_max_save_bytes_alone() {
+        let dirs = Dirs::new();
+        dirs.save(&save_name(1, 1), "a very big game");
+        dirs.save(&save_name(2, 1), "game");
+        dirs.backup(&save_name(3, 1), "a very big backup");
+        let mut app = dirs.app(Config {
+            mode: SyncMode::BackupOnly,
+            max_save_bytes: 10,
+            ..Config::default()
+        });
+        let oversized = |app: &App, n| app.is_oversized(app.save_by_key(&save_name(n, 1)).unwrap());
+        assert!(oversized(&app, 1));
+        assert!(!oversized(&app, 2));
+        // a backup's already as big as it's going to get
+        assert!(!oversized(&app, 3));
+
+        app.reconcile().unwrap();
+        assert!(!dirs.backup_dir.join(save_name(1, 1)).exists());
+        assert!(dirs.backup_dir.join(save_name(2, 1)).exists());
+        assert_eq!(
+            app.status(),
+            Some(format!("'{}' is too big to back up", save_name(1, 1)).as_str())
+        );
+    }
+
+    #[test]
+    fn catches_up_until_a_pass_changes_nothing() {
+        let dirs = Dirs::new();
+        for n in 1..=3 {
+            dirs.save(&save_name(n, 1), "game");
+        }
+        dirs.backup(&save_name(4, 1), "game");
+        let mut app = dirs.app(Config::default());
+        assert!(app.is_catching_up());
+
+        let mut ticks = 0;
+        while app.is_catching_up() {
+            assert!(ticks < 5, "never caught up");
+            app.scan();
+            app.on_tick();
+            ticks += 1;
+        }
+        assert_eq!(ticks, 2);
+        assert_eq!(app.status(), Some("caught up: 4 changes"));
+        assert_eq!(app.state.counts().synced, 4);
+    }
+
+    #[test]
+    fn gives_up_catching_up_after_too_many_passes() {
+        let dirs = Dirs::new();
+        let mut app = dirs.app(Config::default());
+        // as if something kept undoing every change
+        for _ in 1..MAX_CATCH_UP_ROUNDS {
+            app.catch_up(1);
+            assert!(app.is_catching_up());
+        }
+        assert_eq!(
+            app.status(),
+            Some(format!("catching up: {} changes so far", MAX_CATCH_UP_ROUNDS - 1).as_str())
+        );
+        app.catch_up(1);
+        assert!(!app.is_catching_up());
+        assert_eq!(
+            app.status(),
+            Some(format!("caught up: {} changes", MAX_CATCH_UP_ROUNDS).as_str())
+        );
+    }
+
+    #[test]
+    fn backup_only_mode_never_restores() {
+        let dirs = Dirs::new();
+        let mut app = dirs.app(mode(SyncMode::BackupOnly));
+        back_up_then_delete(&dirs, &mut app, &save_name(1, 1));
+        dirs.backup(&save_name(2, 1), "never synced");
+        dirs.save(&save_name(3, 1), "new game");
+        app.scan();
+
+        let plan = app.plan();
+        assert_eq!(plan.len(), 1);
+        assert!(
+            matches!(&plan[0], SyncAction::Backup { save, .. } if key(save) == save_name(3, 1))
+        );
+        app.reconcile().unwrap();
+        assert_eq!(names(&dirs.save_dir), [save_name(3, 1)]);
+        assert!(names(&dirs.backup_dir.join(ARCHIVE_DIR)).is_empty());
+        // and they aren't waiting on anything
+        app.scan();
+        assert_eq!(app.pending(), 0);
+    }
+
+    #[test]
+    fn switching_to_archive_mode_at_runtime_archives_rather_than_restores() {
+        let dirs = Dirs::new();
+        let mut app = dirs.app(mode(SyncMode::Mirror));
+        back_up_then_delete(&dirs, &mut app, &save_name(1, 1));
+        assert!(matches!(app.plan()[..], [SyncAction::Restore(_)]));
+
+        app.cycle_mode();
+        assert_eq!(app.config().mode, SyncMode::Archive);
+        assert_eq!(app.status(), Some("sync mode: archive"));
+        assert!(matches!(app.plan()[..], [SyncAction::Archive(_)]));
+        app.reconcile().unwrap();
+        assert!(names(&dirs.save_dir).is_empty());
+        assert_eq!(
+            names(&dirs.backup_dir.join(ARCHIVE_DIR)),
+            [save_name(1, 1), format!("{}.sum", save_name(1, 1))]
+        );
+
+        app.cycle_mode();
+        assert_eq!(app.config().mode, SyncMode::BackupOnly);
+        app.cycle_mode();
+        assert_eq!(app.config().mode, SyncMode::Mirror);
+    }
+
+    // a save in both dirs, with a checksum, an older version, a pin and a note
+    fn synced_save(dirs: &Dirs, name: &str) -> App {
+        dirs.save(name, "game");
+        let mut app = dirs.app(Config::default());
+        app.reconcile().unwrap();
+        let versions_dir = dirs.backup_dir.join(VERSIONS_DIR).join(name);
+        std::fs::create_dir_all(&versions_dir).unwrap();
+        write(&versions_dir, &format!("1000 {}", name), "older game");
+        app.pins.toggle(name).unwrap();
+        app.notes.set(name, "nearly there").unwrap();
+        app.scan();
+        app
+    }
+
+    #[test]
+    fn renames_a_save_everywhere_it_is_kept() {
+        let dirs = Dirs::new();
+        let mut app = synced_save(&dirs, &save_name(1, 3));
+
+        let renamed = app.rename(&save_name(1, 3), "almost won").unwrap();
+        assert_eq!(renamed, "Saved #1 at depth 3 [almost won].broguesave");
+        assert_eq!(names(&dirs.save_dir), [renamed.as_str()]);
+        assert_eq!(
+            dirs.backups()
+                .into_iter()
+                .filter(|n| n.starts_with("Saved"))
+                .collect::<Vec<_>>(),
+            [renamed.clone(), format!("{}.sum", renamed)]
+        );
+        assert_eq!(
+            names(&dirs.backup_dir.join(VERSIONS_DIR).join(&renamed)),
+            [format!("1000 {}", save_name(1, 3))]
+        );
+        assert!(app.pins.contains(&renamed) && !app.pins.contains(&save_name(1, 3)));
+        assert_eq!(app.notes.get(&renamed), Some("nearly there"));
+
+        // renaming again replaces the comment; an empty one takes it off
+        app.scan();
+        let again = app.rename(&renamed, "won").unwrap();
+        assert_eq!(again, "Saved #1 at depth 3 [won].broguesave");
+        app.scan();
+        assert_eq!(app.rename(&again, "").unwrap(), save_name(1, 3));
+    }
+
+    #[test]
+    fn renames_a_save_that_is_only_backed_up() {
+        let dirs = Dirs::new();
+        dirs.backup(&save_name(1, 3), "game");
+        let mut app = dirs.app(mode(SyncMode::BackupOnly));
+
+        let renamed = app.rename(&save_name(1, 3), "for later").unwrap();
+        assert!(names(&dirs.save_dir).is_empty());
+        assert!(dirs.backups().contains(&renamed));
+        assert!(!dirs.backup_dir.join(save_name(1, 3)).exists());
+        app.scan();
+        assert!(matches!(
+            app.save_by_key(&renamed),
+            Some(Save::BackupFileOnly(_))
+        ));
+    }
+
+    #[test]
+    fn will_not_rename_over_another_save_or_with_brackets() {
+        let dirs = Dirs::new();
+        dirs.save(&save_name(1, 3), "game");
+        dirs.backup("Saved #1 at depth 3 [taken].broguesave", "other game");
+        let mut app = dirs.app(mode(SyncMode::BackupOnly));
+
+        assert!(matches!(
+            app.rename(&save_name(1, 3), "taken"),
+            Err(AppError::NameTaken(name)) if name == "Saved #1 at depth 3 [taken].broguesave"
+        ));
+        assert!(matches!(
+            app.rename(&save_name(1, 3), "a [b]"),
+            Err(AppError::InvalidComment('['))
+        ));
+        assert_eq!(names(&dirs.save_dir), [save_name(1, 3)]);
+    }
+
+    #[test]
+    fn offers_to_recover_saves_that_vanished_since_last_session() {
+        let dirs = Dirs::new();
+        for n in 1..=3 {
+            dirs.save(&save_name(n, 1), "game");
+        }
+        let mut app = dirs.app(Config::default());
+        app.reconcile().unwrap();
+        app.scan();
+        app.record_session().unwrap();
+        drop(app);
+        // something else deletes two saves and their backups, though one's still in the trash
+        for n in 1..=2 {
+            std::fs::remove_file(dirs.save_dir.join(save_name(n, 1))).unwrap();
+        }
+        let trashed = App::move_backup(
+            &dirs.backup_dir.join(save_name(1, 1)),
+            &dirs.backup_dir,
+            TRASH_DIR,
+        )
+        .unwrap();
+        App::rm(&dirs.backup_dir.join(save_name(2, 1))).unwrap();
+        dirs.save(&save_name(4, 1), "new game");
+
+        let mut app = dirs.unscanned_app(mode(SyncMode::BackupOnly));
+        app.recall_last_session(manifest::load(&dirs.backup_dir).unwrap().unwrap());
+        app.update_state().unwrap();
+        assert_eq!(
+            app.prompt,
+            Some(Prompt::Recover {
+                missing: vec![save_name(1, 1), save_name(2, 1)],
+                found: vec![(save_name(1, 1), trashed)],
+            })
+        );
+        let changes = app.since_last_launch.as_ref().unwrap();
+        assert_eq!(changes.added, [save_name(4, 1)]);
+        assert_eq!(changes.removed, [save_name(1, 1), save_name(2, 1)]);
+
+        app.prompt_char('y');
+        assert_eq!(app.prompt, None);
+        assert_eq!(app.status(), Some("recovered 1 saves"));
+        assert!(dirs.backup_dir.join(save_name(1, 1)).exists());
+        assert!(dirs
+            .backup_dir
+            .join(format!("{}.sum", save_name(1, 1)))
+            .exists());
+        assert!(names(&dirs.backup_dir.join(TRASH_DIR)).is_empty());
+    }
+
+    #[test]
+    fn numbers_a_name_that_is_already_taken() {
+        assert_eq!(
+            numbered_name(&save_name(1, 3), 2),
+            "Saved #1 at depth 3 [2].broguesave"
+        );
+        assert_eq!(
+            numbered_name("Saved #1 at depth 3 (easy) [won].broguesave.age", 3),
+            "Saved #1 at depth 3 (easy) [won 3].broguesave.age"
+        );
+        assert_eq!(
+            parse_save_name(&numbered_name(&save_name(7, 3), 2)).map(|m| m.seed),
+            Some(7)
+        );
+    }
+
+    #[test]
+    fn trashing_a_save_twice_keeps_both_and_recovers_the_newer() {
+        let dirs = Dirs::new();
+        let name = save_name(1, 1);
+        let first = dirs.backup(&name, "first game");
+        write_checksum(&first).unwrap();
+        let trashed = App::move_backup(&first, &dirs.backup_dir, TRASH_DIR).unwrap();
+        let second = dirs.backup(&name, "second game");
+        let trashed_again = App::move_backup(&second, &dirs.backup_dir, TRASH_DIR).unwrap();
+
+        assert_eq!(trashed, dirs.backup_dir.join(TRASH_DIR).join(&name));
+        assert_eq!(
+            trashed_again,
+            dirs.backup_dir
+                .join(TRASH_DIR)
+                .join("Saved #1 at depth 1 [2].broguesave")
+        );
+        assert_eq!(std::fs::read_to_string(&trashed).unwrap(), "first game");
+        assert!(is_intact(&trashed));
+        assert_eq!(
+            std::fs::read_to_string(&trashed_again).unwrap(),
+            "second game"
+        );
+
+        let mut app = dirs.app(Config::default());
+        assert_eq!(newest_moved(&trashed), Some(trashed_again.clone()));
+        app.recover(&[(name.clone(), trashed_again)]);
+        assert_eq!(
+            std::fs::read_to_string(dirs.backup_dir.join(&name)).unwrap(),
+            "second game"
+        );
+    }
+
+    #[test]
+    fn asks_nothing_when_no_save_has_vanished() {
+        let dirs = Dirs::new();
+        dirs.save(&save_name(1, 1), "game");
+        dirs.app(Config::default()).record_session().unwrap();
+
+        let mut app = dirs.unscanned_app(Config::default());
+        app.recall_last_session(manifest::load(&dirs.backup_dir).unwrap().unwrap());
+        app.update_state().unwrap();
+        assert_eq!(app.prompt, None);
+        assert_eq!(app.since_last_launch, None);
+    }
+
+    #[test]
+    fn names_a_conflicting_restore_after_the_save_it_restores() {
+        assert_eq!(
+            conflict_name(&save_name(1, 3), 1),
+            "Saved #1 at depth 3 [restored].broguesave"
+        );
+        assert_eq!(
+            conflict_name(&save_name(1, 3), 2),
+            "Saved #1 at depth 3 [restored 2].broguesave"
+        );
+        assert_eq!(
+            conflict_name("Saved #1 at depth 3 (easy) [won].broguesave", 1),
+            "Saved #1 at depth 3 (easy) [won restored].broguesave"
+        );
+        assert_eq!(
+            parse_save_name(&conflict_name(&save_name(7, 3), 3)).map(|m| m.seed),
+            Some(7)
+        );
+    }
+
+    #[test]
+    fn restores_under_a_new_name_when_an_older_different_save_has_the_name() {
+        let dirs = Dirs::new();
+        let backup = dirs.backup(&save_name(1, 3), "backed up");
+        set_age(&backup, Duration::from_secs(30));
+        let app = dirs.app(Config::default());
+        let save = app.save_by_key(&save_name(1, 3)).unwrap().clone();
+        // turned up since the scan, and older than the backup
+        let older = dirs.save(&save_name(1, 3), "older game");
+        dirs.save("Saved #1 at depth 3 [restored].broguesave", "another game");
+
+        let (_, restored) = app.restore_best(&save).unwrap();
+        assert_eq!(
+            restored,
+            dirs.save_dir
+                .join("Saved #1 at depth 3 [restored 2].broguesave")
+        );
+        assert_eq!(std::fs::read_to_string(&restored).unwrap(), "backed up");
+        assert_eq!(std::fs::read_to_string(&older).unwrap(), "older game");
+        // restoring again finds it's already there
+        assert_eq!(app.restore_best(&save).unwrap().1, restored);
+    }
+
+    #[test]
+    fn restoring_into_a_folder_never_replaces_whats_there() {
+        let dirs = Dirs::new();
+        let live = dirs.save(&save_name(1, 3), "live game");
+        dirs.backup(&save_name(1, 3), "backed up");
+        let mut app = dirs.app(Config::default());
+        let restore_into_save_dir = |app: &mut App| {
+            app.prompt = Some(Prompt::RestorePath {
+                key: save_name(1, 3),
+                version: None,
+                input: dirs.save_dir.display().to_string(),
+            });
+            app.submit_prompt();
+        };
+
+        restore_into_save_dir(&mut app);
+        let restored = dirs
+            .save_dir
+            .join("Saved #1 at depth 3 [restored].broguesave");
+        assert_eq!(std::fs::read_to_string(&live).unwrap(), "live game");
+        assert_eq!(std::fs::read_to_string(&restored).unwrap(), "backed up");
+
+        // already there, so there's no copy for undo to take back
+        restore_into_save_dir(&mut app);
+        assert_eq!(names(&dirs.save_dir).len(), 2);
+        app.undo();
+        app.undo();
+        assert!(!restored.exists());
+        assert_eq!(std::fs::read_to_string(&live).unwrap(), "live game");
+    }
+
+    #[test]
+    fn undoes_a_delete() {
+        let dirs = Dirs::new();
+        dirs.save(&save_name(1, 1), "game");
+        let mut app = dirs.app(Config::default());
+        app.reconcile().unwrap();
+        app.scan();
+        app.start_delete();
+        app.choose_delete(0);
+        app.reconcile().unwrap();
+        assert!(!dirs.backup_dir.join(save_name(1, 1)).exists());
+
+        app.undo();
+        assert_eq!(
+            app.status(),
+            Some(format!("undid deleting {}", save_name(1, 1)).as_str())
+        );
+        assert!(dirs.backup_dir.join(save_name(1, 1)).exists());
+        assert!(names(&dirs.backup_dir.join(TRASH_DIR)).is_empty());
+        // and the sync puts the live save back
+        app.scan();
+        app.reconcile().unwrap();
+        assert_eq!(names(&dirs.save_dir), [save_name(1, 1)]);
+    }
+
+    #[test]
+    fn undoes_archiving_the_oldest_saves() {
+        let dirs = Dirs::new();
+        for n in 1..=3 {
+            let save = dirs.save(&save_name(n, 1), "game");
+            set_age(&save, Duration::from_secs(60 * u64::from(n)));
+        }
+        let mut app = dirs.app(Config {
+            max_live_saves: 1,
+            ..Config::default()
+        });
+        app.reconcile().unwrap();
+        app.scan();
+        assert_eq!(app.excess_live_saves(), 2);
+        app.archive_oldest();
+        assert_eq!(names(&dirs.save_dir), [save_name(1, 1)]);
+
+        app.undo();
+        assert_eq!(app.status(), Some("undid archiving 2 saves"));
+        assert!(names(&dirs.backup_dir.join(ARCHIVE_DIR)).is_empty());
+        assert!(dirs.backup_dir.join(save_name(2, 1)).exists());
+        assert!(dirs.backup_dir.join(save_name(3, 1)).exists());
+    }
+
+    #[test]
+    fn undoes_a_rename() {
+        let dirs = Dirs::new();
+        let mut app = synced_save(&dirs, &save_name(1, 3));
+        app.prompt = Some(Prompt::Rename {
+            key: save_name(1, 3),
+            input: "won".to_string(),
+        });
+        app.submit_prompt();
+        assert_eq!(
+            names(&dirs.save_dir),
+            ["Saved #1 at depth 3 [won].broguesave"]
+        );
+
+        app.scan();
+        app.undo();
+        assert_eq!(names(&dirs.save_dir), [save_name(1, 3)]);
+        assert!(app.pins.contains(&save_name(1, 3)));
+        assert_eq!(app.notes.get(&save_name(1, 3)), Some("nearly there"));
+    }
+
+    #[test]
+    fn undoes_an_export_but_not_an_overwrite() {
+        let dirs = Dirs::new();
+        let save = dirs.save(&save_name(1, 1), "game");
+        let mut app = dirs.app(Config::default());
+        let exported = dirs.backup_dir.join("exported.broguesave");
+        app.export(&save, &exported, false);
+        app.export(&save, &exported, true);
+
+        app.undo();
+        assert_eq!(
+            app.status(),
+            Some(format!("can't undo overwriting {}", exported.display()).as_str())
+        );
+        assert!(exported.exists());
+        // the next undo gets past it
+        app.undo();
+        assert!(!exported.exists());
+        app.undo();
+        assert_eq!(app.status(), Some("nothing to undo"));
+    }
+
+    #[test]
+    fn only_remembers_the_most_recent_changes() {
+        let dirs = Dirs::new();
+        let mut app = dirs.app(Config::default());
+        let copies: Vec<_> = (0..UNDO_LIMIT + 5)
+            .map(|n| dirs.backup(&format!("copy {}", n), "game"))
+            .collect();
+        for copy in &copies {
+            app.push_undo(ReversibleAction::Copied(copy.clone()));
+        }
+        assert_eq!(app.undo.len(), UNDO_LIMIT);
+
+        for _ in 0..UNDO_LIMIT {
+            app.undo();
+        }
+        app.undo();
+        assert_eq!(app.status(), Some("nothing to undo"));
+        assert!(copies[..5].iter().all(|copy| copy.exists()));
+        assert!(copies[5..].iter().all(|copy| !copy.exists()));
+    }
+
+    #[test]
+    fn sorting_flips_the_list_and_keeps_the_selection() {
+        let dirs = Dirs::new();
+        for n in 1..=3 {
+            let save = dirs.save(&save_name(n, 1), "game");
+            set_age(&save, Duration::from_secs(60 * u64::from(n)));
+        }
+        let mut app = dirs.app(Config::default());
+        let keys =
+            |app: &App| -> Vec<String> { app.visible_saves().iter().map(|s| s.key()).collect() };
+        assert_eq!(
+            keys(&app),
+            [save_name(1, 1), save_name(2, 1), save_name(3, 1)]
+        );
+        app.selected = 0;
+
+        app.toggle_sort_direction();
+        assert!(!app.sort_descending);
+        assert_eq!(
+            keys(&app),
+            [save_name(3, 1), save_name(2, 1), save_name(1, 1)]
+        );
+        assert_eq!(app.selected_save().unwrap().key(), save_name(1, 1));
+
+        app.toggle_sort_direction();
+        assert_eq!(app.selected, 0);
+        assert_eq!(keys(&app)[0], save_name(1, 1));
+    }
+
+    #[test]
+    fn a_recently_modified_save_is_being_written() {
+        let dirs = Dirs::new();
+        let save = dirs.save(&save_name(1, 1), "game");
+        let app = dirs.app(Config {
+            write_settle_ms: 10_000,
+            ..Config::default()
+        });
+
+        set_age(&save, Duration::from_secs(1));
+        assert!(app.is_being_written(&save));
+        set_age(&save, Duration::from_secs(11));
+        assert!(!app.is_being_written(&save));
+        // a clock problem, not a write
+        std::fs::File::options()
+            .write(true)
+            .open(&save)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+        assert!(!app.is_being_written(&save));
+        assert!(!app.is_being_written(&dirs.save_dir.join("missing.broguesave")));
+
+        let app = dirs.app(Config {
+            write_settle_ms: 10_000,
+            auto_defer_active_writes: false,
+            ..Config::default()
+        });
+        set_age(&save, Duration::from_secs(1));
+        assert!(!app.is_being_written(&save));
+    }
+
+    #[test]
+    fn stops_syncing_while_the_backup_dir_cannot_be_written() {
+        let dirs = Dirs::new();
+        let mut app = dirs.app(Config {
+            writable_check_ms: 0,
+            ..Config::default()
+        });
+        app.check_backup_dir();
+        assert_eq!(app.unwritable_backup_dir(), None);
+
+        // running as root ignores permissions, so put a file where the dir should be
+        std::fs::remove_dir_all(&dirs.backup_dir).unwrap();
+        std::fs::write(&dirs.backup_dir, "not a dir").unwrap();
+        assert!(check_writable(&dirs.backup_dir).is_err());
+        app.check_backup_dir();
+        assert!(app.unwritable_backup_dir().is_some());
+
+        std::fs::remove_file(&dirs.backup_dir).unwrap();
+        std::fs::create_dir(&dirs.backup_dir).unwrap();
+        app.check_backup_dir();
+        assert_eq!(app.unwritable_backup_dir(), None);
+        assert_eq!(app.status(), Some("backup dir is writable again"));
+    }
+
+    #[test]
+    fn running_low_on_inodes_counts_as_unwritable() {
+        let dirs = Dirs::new();
+        let mut app = dirs.app(Config {
+            writable_check_ms: 0,
+            min_free_inodes: u64::MAX,
+            ..Config::default()
+        });
+        // some filesystems don't count inodes, and those never run short
+        let Some(free) = free_inodes(&dirs.backup_dir) else {
+            assert_eq!(app.inode_shortage(), None);
+            return;
+        };
+        app.check_backup_dir();
+        assert_eq!(
+            app.unwritable_backup_dir(),
+            Some(
+                format!(
+                    "only {} inodes free, fewer than min_free_inodes ({})",
+                    free,
+                    u64::MAX
+                )
+                .as_str()
+            )
+        );
+
+        app.config.min_free_inodes = 0;
+        assert_eq!(app.inode_shortage(), None);
+    }
+
+    #[test]
+    fn counts_live_saves_over_the_limit() {
+        let dirs = Dirs::new();
+        for n in 1..=3 {
+            dirs.save(&save_name(n, 1), "game");
+            dirs.save(&recording_name(n), "recording");
+        }
+        dirs.backup(&save_name(4, 1), "game");
+        let mut app = dirs.app(Config {
+            max_live_saves: 2,
+            ..Config::default()
+        });
+        // neither recordings nor backups without a live save count
+        assert_eq!(app.excess_live_saves(), 1);
+
+        app.config.max_live_saves = 3;
+        assert_eq!(app.excess_live_saves(), 0);
+        app.config.max_live_saves = 0;
+        assert_eq!(app.excess_live_saves(), 0);
+    }
+
+    #[test]
+    fn classifies_how_long_the_newest_save_has_waited() {
+        let config = Config {
+            health_warn_secs: 60,
+            health_alarm_secs: 300,
+            ..Config::default()
+        };
+        let secs = Duration::from_secs;
+        let cases = [
+            (None, Health::Good),
+            (Some(secs(0)), Health::Unbacked(secs(0))),
+            (Some(secs(60)), Health::Unbacked(secs(60))),
+            (Some(secs(61)), Health::Warning(secs(61))),
+            (Some(secs(300)), Health::Warning(secs(300))),
+            (Some(secs(301)), Health::Alarm(secs(301))),
+        ];
+        for (unbacked, health) in cases {
+            assert_eq!(
+                Health::classify(unbacked, &config),
+                health,
+                "{:?}",
+                unbacked
+            );
+        }
+    }
+
+    #[test]
+    fn priming_restores_every_backup_into_a_new_save_dir() {
+        let dirs = Dirs::new();
+        dirs.backup(&save_name(1, 1), "one");
+        dirs.backup(&save_name(2, 1), "two");
+        let mut app = dirs.unscanned_app(mode(SyncMode::BackupOnly));
+        std::fs::remove_dir(&dirs.save_dir).unwrap();
+
+        // even in a mode that never restores
+        assert_eq!(app.prime_saves().unwrap(), 2);
+        assert_eq!(names(&dirs.save_dir), [save_name(1, 1), save_name(2, 1)]);
+        assert_eq!(
+            std::fs::read_to_string(dirs.save_dir.join(save_name(2, 1))).unwrap(),
+            "two"
+        );
+        assert_eq!(app.status(), Some("primed the save dir with 2 saves"));
+
+        // saves already in the save dir are left alone
+        std::fs::write(dirs.save_dir.join(save_name(1, 1)), "played on").unwrap();
+        assert_eq!(app.prime_saves().unwrap(), 0);
+        assert_eq!(
+            std::fs::read_to_string(dirs.save_dir.join(save_name(1, 1))).unwrap(),
+            "played on"
+        );
+    }
+
+    #[test]
+    fn never_restores_over_a_newer_live_save() {
+        let dirs = Dirs::new();
+        let backup = dirs.backup(&save_name(1, 3), "backed up");
+        set_age(&backup, Duration::from_secs(120));
+        let app = dirs.app(Config::default());
+        let save = app.save_by_key(&save_name(1, 3)).unwrap().clone();
+        // brogue saved the game again since the scan
+        let newer = dirs.save(&save_name(1, 3), "played on");
+
+        match app.restore_best(&save) {
+            Err(AppError::NewerLiveSave(key)) => assert_eq!(key, save_name(1, 3)),
+            other => panic!("expected NewerLiveSave, got {:?}", other),
+        }
+        assert_eq!(std::fs::read_to_string(&newer).unwrap(), "played on");
+        assert_eq!(names(&dirs.save_dir), [save_name(1, 3)]);
+    }
+
+    #[test]
+    fn times_how_long_the_newest_save_has_gone_unbacked() {
+        let dirs = Dirs::new();
+        let mut app = dirs.app(Config {
+            auto_defer_active_writes: false,
+            ..mode(SyncMode::BackupOnly)
+        });
+        assert_eq!(app.newest_unbacked(), None);
+
+        dirs.backup(&save_name(9, 1), "gone");
+        let save = dirs.save(&save_name(1, 1), "game");
+        set_age(&save, Duration::from_secs(120));
+        app.scan();
+        let unbacked = app.newest_unbacked().unwrap();
+        assert!(unbacked >= Duration::from_secs(120), "{:?}", unbacked);
+
+        // backed up since the last scan
+        app.reconcile().unwrap();
+        assert_eq!(app.newest_unbacked(), None);
+
+        // rewritten by brogue without changing
+        app.scan();
+        set_age(
+            &dirs.backup_dir.join(save_name(1, 1)),
+            Duration::from_secs(60),
+        );
+        std::fs::write(&save, "game").unwrap();
+        assert_eq!(app.newest_unbacked(), None);
+
+        std::fs::write(&save, "played on").unwrap();
+        assert!(app.newest_unbacked().is_some());
+
+        // an unbacked recording doesn't count
+        app.reconcile().unwrap();
+        app.scan();
+        dirs.save(&recording_name(2), "recording");
+        app.scan();
+        assert_eq!(app.newest_unbacked(), None);
+    }
+
+    #[cfg(unix)]
+    fn sync_twice(app: &mut App) {
+        for _ in 0..2 {
+            app.scan();
+            app.reconcile().unwrap();
+        }
+        app.scan();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn links_a_matching_backup_and_unlinks_it_when_hardlink_is_turned_off() {
+        let dirs = Dirs::new();
+        let save = dirs.save(&save_name(1, 1), "game");
+        let backup = dirs.backup_dir.join(save_name(1, 1));
+        let mut app = dirs.app(Config {
+            hardlink: true,
+            ..Config::default()
+        });
+        sync_twice(&mut app);
+        assert!(same_file(&save, &backup).unwrap());
+        assert!(app.plan().is_empty());
+
+        app.config.hardlink = false;
+        assert!(matches!(app.plan().as_slice(), [SyncAction::Unlink { .. }]));
+        app.reconcile().unwrap();
+        assert!(!same_file(&save, &backup).unwrap());
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), "game");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stops_linking_a_save_brogue_rewrites_in_place() {
+        let dirs = Dirs::new();
+        let save = dirs.save(&save_name(1, 1), "game");
+        let backup = dirs.backup_dir.join(save_name(1, 1));
+        let mut app = dirs.app(Config {
+            hardlink: true,
+            auto_defer_active_writes: false,
+            ..Config::default()
+        });
+        sync_twice(&mut app);
+        assert!(same_file(&save, &backup).unwrap());
+
+        // writing through the link changes the backup too; the mtime is set so it differs
+        // even within the filesystem's timestamp granularity
+        std::fs::write(&save, "played on").unwrap();
+        set_age(&save, Duration::ZERO);
+        app.scan();
+        assert!(matches!(app.plan().as_slice(), [SyncAction::Unlink { .. }]));
+        sync_twice(&mut app);
+        assert!(!same_file(&save, &backup).unwrap());
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), "played on");
+        assert!(app.rewritten_in_place.contains(&save_name(1, 1)));
+        assert!(app.plan().is_empty());
+    }
+
+    #[test]
+    fn manual_mode_only_does_what_is_approved() {
+        let dirs = Dirs::new();
+        dirs.save(&save_name(1, 1), "one");
+        dirs.save(&save_name(2, 1), "two");
+        let mut app = dirs.app(Config::default());
+        app.start_manual();
+        app.reconcile().unwrap();
+        assert!(dirs.backups().is_empty());
+        assert_eq!(app.review_queue().len(), 2);
+        let approved = app.review_queue()[0].key();
+        let rejected = app.review_queue()[1].key();
+
+        app.approve();
+        app.scan();
+        app.reconcile().unwrap();
+        assert!(dirs.backup_dir.join(&approved).exists());
+        assert_eq!(app.review_queue().len(), 1);
+
+        app.reject();
+        app.scan();
+        app.reconcile().unwrap();
+        assert!(app.review_queue().is_empty());
+        assert!(!dirs.backup_dir.join(&rejected).exists());
+
+        // once it drops out of the plan, it's asked about again when it comes back
+        let live = dirs.save_dir.join(&rejected);
+        std::fs::remove_file(&live).unwrap();
+        app.scan();
+        app.reconcile().unwrap();
+        dirs.save(&rejected, "two");
+        app.scan();
+        app.reconcile().unwrap();
+        assert_eq!(app.review_queue().len(), 1);
+        assert_eq!(app.review_queue()[0].key(), rejected);
+    }
+
+    #[test]
+    fn copies_backups_into_the_extra_dirs_until_there_are_enough() {
+        let dirs = Dirs::new();
+        let extras = tempfile::tempdir().unwrap();
+        let (first, second) = (extras.path().join("first"), extras.path().join("second"));
+        std::fs::create_dir(&first).unwrap();
+        dirs.save(&save_name(1, 1), "game");
+        let mut app = dirs.app(Config {
+            extra_backup_dirs: vec![first.clone(), second.clone()],
+            backup_quorum: 2,
+            ..Config::default()
+        });
+        for _ in 0..2 {
+            app.reconcile().unwrap();
+            app.scan();
+        }
+        let save = app.save_by_key(&save_name(1, 1)).unwrap().clone();
+        assert_eq!(
+            std::fs::read_to_string(first.join(save_name(1, 1))).unwrap(),
+            "game"
+        );
+        // the second dir isn't there, so only two of the three have it
+        assert_eq!(app.copies(&save), (2, 2));
+        assert!(app.has_quorum(&save));
+        app.config.backup_quorum = 0;
+        assert_eq!(app.copies(&save), (2, 3));
+        assert!(!app.has_quorum(&save));
+
+        std::fs::create_dir(&second).unwrap();
+        assert_eq!(
+            app.replicate(&save).unwrap(),
+            [SyncAction::Replicate {
+                backup: dirs.backup_dir.join(save_name(1, 1)),
+                copy: second.join(save_name(1, 1)),
+            }]
+        );
+        app.reconcile().unwrap();
+        assert_eq!(app.copies(&save), (3, 3));
+        assert!(app.replicate(&save).unwrap().is_empty());
+
+        // a newer backup makes the copies out of date
+        for dir in [&first, &second] {
+            set_age(&dir.join(save_name(1, 1)), Duration::from_secs(60));
+        }
+        let backup = dirs.backup_dir.join(save_name(1, 1));
+        std::fs::write(&backup, "played on").unwrap();
+        assert_eq!(app.copies(&save), (1, 3));
+        assert_eq!(app.replicate(&save).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn backs_off_retrying_a_failed_restore() {
+        let dirs = Dirs::new();
+        let mut app = dirs.app(Config::default());
+        let busy = || AppError::from(std::io::Error::other("device busy"));
+
+        let mut delays = vec![];
+        for _ in 0..12 {
+            let before = Instant::now();
+            app.restore_failed("busy", &busy());
+            let failed = &app.failed_restores()["busy"];
+            assert!(!failed.is_due(before));
+            delays.push(failed.retry_at.unwrap() - before);
+        }
+        assert_eq!(app.failed_restores()["busy"].attempts, 12);
+        let secs: Vec<_> = delays.iter().map(|delay| delay.as_secs()).collect();
+        // doubling each time, up to five minutes
+        assert_eq!(secs, [1, 2, 4, 8, 16, 32, 64, 128, 256, 300, 300, 300]);
+        let failed = &app.failed_restores()["busy"];
+        assert!(failed.is_due(failed.retry_at.unwrap()));
+
+        // a copy that came out wrong is worth another go too; a missing backup isn't
+        app.restore_failed("mismatch", &AppError::CopyMismatch(PathBuf::from("a")));
+        assert!(app.failed_restores()["mismatch"].retry_at.is_some());
+        let missing = std::io::Error::from(std::io::ErrorKind::NotFound);
+        app.restore_failed("missing", &AppError::from(missing));
+        let failed = &app.failed_restores()["missing"];
+        assert_eq!(failed.retry_at, None);
+        assert!(!failed.is_due(Instant::now() + RESTORE_RETRY_MAX));
+
+        // until asked to
+        app.retry_restores();
+        assert_eq!(app.status(), Some("retrying 3 failed restores"));
+        let now = Instant::now();
+        assert!(app.failed_restores().values().all(|f| f.is_due(now)));
+    }
+
+    // save 1's backup was written after its live save, and save 2's live save after its backup
+    fn diverged_saves(dirs: &Dirs, keep_diverged: KeepDiverged) -> App {
+        for (n, live_age, backup_age) in [(1, 120, 60), (2, 60, 120)] {
+            let live = dirs.save(&save_name(n, 1), &format!("live {}", n));
+            set_age(&live, Duration::from_secs(live_age));
+            let backup = dirs.backup(&save_name(n, 1), &format!("backup {}", n));
+            set_age(&backup, Duration::from_secs(backup_age));
+        }
+        dirs.app(Config {
+            keep_diverged,
+            ..Config::default()
+        })
+    }
+
+    fn sorted(mut diverged: Vec<(String, Side)>) -> Vec<(String, Side)> {
+        diverged.sort_by(|a, b| a.0.cmp(&b.0));
+        diverged
+    }
+
+    #[test]
+    fn picks_a_side_of_each_diverged_save() {
+        let cases = [
+            (KeepDiverged::Newest, [Side::Backup, Side::Live]),
+            (KeepDiverged::Live, [Side::Live, Side::Live]),
+            (KeepDiverged::Backup, [Side::Backup, Side::Backup]),
+        ];
+        for (keep, sides) in cases {
+            let dirs = Dirs::new();
+            let app = diverged_saves(&dirs, keep);
+            assert_eq!(
+                sorted(app.diverged()),
+                [(save_name(1, 1), sides[0]), (save_name(2, 1), sides[1])],
+                "{:?}",
+                keep
+            );
+        }
+
+        // saves that match aren't diverged
+        let dirs = Dirs::new();
+        dirs.save(&save_name(1, 1), "game");
+        dirs.backup(&save_name(1, 1), "game");
+        assert!(dirs.app(Config::default()).diverged().is_empty());
+    }
+
+    #[test]
+    fn resolving_keeps_the_losing_copy_as_a_version() {
+        for keep in [
+            KeepDiverged::Newest,
+            KeepDiverged::Live,
+            KeepDiverged::Backup,
+        ] {
+            let dirs = Dirs::new();
+            let mut app = diverged_saves(&dirs, keep);
+            let resolutions = app.diverged();
+            app.resolve_diverged(&resolutions);
+            assert_eq!(app.status(), Some("resolved 2 of 2 diverged saves"));
+
+            for (n, (key, side)) in (1..).zip(sorted(resolutions)) {
+                let (kept, lost) = match side {
+                    Side::Live => (format!("live {}", n), format!("backup {}", n)),
+                    Side::Backup => (format!("backup {}", n), format!("live {}", n)),
+                };
+                let read = |path: PathBuf| std::fs::read_to_string(path).unwrap();
+                assert_eq!(read(dirs.save_dir.join(&key)), kept, "{:?}", keep);
+                assert_eq!(read(dirs.backup_dir.join(&key)), kept, "{:?}", keep);
+                let versions = versions(&dirs.backup_dir, &key);
+                assert_eq!(versions.len(), 1, "{:?}", keep);
+                assert_eq!(read(versions[0].clone()), lost, "{:?}", keep);
+            }
+            app.scan();
+            assert!(app.diverged().is_empty());
+        }
+    }
+
+    #[test]
+    fn protecting_the_last_copy_archives_the_backup_of_a_deleted_save() {
+        let dirs = Dirs::new();
+        dirs.save(&save_name(1, 1), "game");
+        let mut app = dirs.app(Config {
+            protect_last_copy: true,
+            ..Config::default()
+        });
+        app.reconcile().unwrap();
+        app.scan();
+        app.start_delete();
+        app.choose_delete(0);
+        assert_eq!(app.delete_state, DeleteState::Delete(0));
+        app.reconcile().unwrap();
+
+        assert!(names(&dirs.save_dir).is_empty());
+        assert!(!dirs.backup_dir.join(save_name(1, 1)).exists());
+        assert_eq!(
+            names(&dirs.backup_dir.join(ARCHIVE_DIR)),
+            [save_name(1, 1), format!("{}.sum", save_name(1, 1))]
+        );
+        assert!(names(&dirs.backup_dir.join(TRASH_DIR)).is_empty());
+        assert!(app.status().unwrap().contains("its backup is in archive/"));
+    }
+
+    #[test]
+    fn protecting_the_last_copy_keeps_an_earlier_archived_save_of_the_same_name() {
+        let dirs = Dirs::new();
+        let mut app = dirs.app(Config {
+            protect_last_copy: true,
+            ..Config::default()
+        });
+        for game in ["first game", "second game"] {
+            dirs.save(&save_name(1, 1), game);
+            app.scan();
+            app.reconcile().unwrap();
+            app.scan();
+            app.start_delete();
+            app.choose_delete(0);
+            app.reconcile().unwrap();
+        }
+
+        let archive = dirs.backup_dir.join(ARCHIVE_DIR);
+        let read = |name: &str| std::fs::read_to_string(archive.join(name)).unwrap();
+        assert_eq!(read(&save_name(1, 1)), "first game");
+        assert_eq!(read("Saved #1 at depth 1 [2].broguesave"), "second game");
+        assert!(is_intact(&archive.join(save_name(1, 1))));
+        assert!(is_intact(
+            &archive.join("Saved #1 at depth 1 [2].broguesave")
+        ));
+    }
+
+    #[test]
+    fn protecting_the_last_copy_asks_before_deleting_a_backup_only_save() {
+        let dirs = Dirs::new();
+        dirs.backup(&save_name(1, 1), "game");
+        let mut app = dirs.app(Config {
+            protect_last_copy: true,
+            ..mode(SyncMode::BackupOnly)
+        });
+        app.start_delete();
+        app.choose_delete(0);
+        assert_eq!(app.delete_state, DeleteState::ConfirmLastCopy(0));
+        app.confirm_delete(false);
+        assert_eq!(app.delete_state, DeleteState::NotDeleting);
+        assert_eq!(app.status(), Some("kept the last copy"));
+        app.reconcile().unwrap();
+        assert_eq!(dirs.backups(), [save_name(1, 1)]);
+
+        app.start_delete();
+        app.choose_delete(0);
+        app.confirm_delete(true);
+        app.reconcile().unwrap();
+        assert!(dirs.backups().is_empty());
+    }
+
+    #[test]
+    fn snapshots_the_save_dir_and_prunes_old_snapshots() {
+        let dirs = Dirs::new();
+        dirs.save(&save_name(1, 1), "one");
+        dirs.save(&save_name(2, 1), "two");
+        // too soon to copy
+        std::fs::write(dirs.save_dir.join(save_name(3, 1)), "three").unwrap();
+        let old = dirs
+            .backup_dir
+            .join(snapshots::SNAPSHOTS_DIR)
+            .join("2020-01-01 09.00.00");
+        std::fs::create_dir_all(&old).unwrap();
+        let mut app = dirs.app(Config {
+            keep_snapshots: 1,
+            write_settle_ms: 30_000,
+            ..Config::default()
+        });
+
+        let snapshot = app.take_snapshot().unwrap();
+        assert_eq!(
+            names(&snapshot),
+            [
+                save_name(1, 1),
+                format!("{}.sum", save_name(1, 1)),
+                save_name(2, 1),
+                format!("{}.sum", save_name(2, 1)),
+            ]
+        );
+        assert!(is_intact(&snapshot.join(save_name(2, 1))));
+        assert!(!old.exists());
+        assert!(app.last_full_snapshot.is_some());
+    }
+
+    #[test]
+    fn migrating_compares_contents_as_well_as_hashes() {
+        let dirs = Dirs::new();
+        let old = tempfile::tempdir().unwrap();
+        dirs.backup(&save_name(1, 1), "one");
+        write(old.path(), &save_name(3, 1), "one");
+        write(old.path(), &save_name(2, 1), "two");
+        write(old.path(), &save_name(1, 1), "another one");
+
+        // every file hashes the same, so only a full comparison tells them apart
+        let summary = migrate_with(old.path(), &dirs.backup_dir, None, |_| Ok(0)).unwrap();
+        assert_eq!(summary.duplicates, 1);
+        assert_eq!(summary.copied, 1);
+        assert_eq!(summary.conflicts, [old.path().join(save_name(1, 1))]);
+        assert_eq!(
+            std::fs::read_to_string(dirs.backup_dir.join(save_name(2, 1))).unwrap(),
+            "two"
+        );
+    }
+
+    #[test]
+    fn migrating_into_an_encrypted_backup_dir_encrypts() {
+        let dirs = Dirs::new();
+        let old = tempfile::tempdir().unwrap();
+        let key_file = old.path().join("key");
+        std::fs::write(&key_file, "passphrase").unwrap();
+        let cipher = Cipher::load(&Config {
+            key_file: Some(key_file),
+            ..Config::default()
+        })
+        .unwrap()
+        .unwrap();
+        write(old.path(), &save_name(1, 1), "game");
+
+        let summary = migrate(old.path(), &dirs.backup_dir, Some(&cipher)).unwrap();
+        assert_eq!(summary.copied, 1);
+        let backup = encrypted_path(&dirs.backup_dir.join(save_name(1, 1)));
+        assert!(!dirs.backup_dir.join(save_name(1, 1)).exists());
+        assert_eq!(read_contents(&backup, Some(&cipher)).unwrap(), b"game");
+        assert!(is_intact(&backup));
+
+        // and knows the encrypted backup holds the same save
+        let summary = migrate(old.path(), &dirs.backup_dir, Some(&cipher)).unwrap();
+        assert_eq!((summary.copied, summary.duplicates), (0, 1));
+    }
 }
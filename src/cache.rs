@@ -0,0 +1,104 @@
+use crate::backup::{Save, State};
+use crate::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub const STATE_CACHE_FILE: &str = "state-cache.bin";
+
+// bumped whenever what's cached changes shape, so an old cache is ignored, not misread
+const VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Cache {
+    version: u32,
+    save_dir: PathBuf,
+    backup_dir: PathBuf,
+    saves: Vec<Save>,
+}
+
+/// Reads the saves as they were when we last shut down, from `state-cache.bin` in the backup
+/// dir, to show straight away while the first scan runs. `None` if there isn't a cache, it
+/// can't be read, or it was written for a different save dir or backup dir.
+pub fn load(save_dir: &Path, backup_dir: &Path) -> Option<State> {
+    let bytes = std::fs::read(backup_dir.join(STATE_CACHE_FILE)).ok()?;
+    let cache: Cache = bincode::deserialize(&bytes).ok()?;
+    if cache.version != VERSION || cache.save_dir != save_dir || cache.backup_dir != backup_dir {
+        return None;
+    }
+    Some(State {
+        saves: cache.saves,
+        scanned: 0,
+    })
+}
+
+/// Records `state` for the next run to show before it's scanned, along with the dirs it was
+/// scanned from.
+pub fn save(save_dir: &Path, backup_dir: &Path, state: &State) -> Result<()> {
+    let cache = Cache {
+        version: VERSION,
+        save_dir: save_dir.to_path_buf(),
+        backup_dir: backup_dir.to_path_buf(),
+        saves: state.saves.clone(),
+    };
+    let bytes = bincode::serialize(&cache)?;
+    std::fs::write(backup_dir.join(STATE_CACHE_FILE), bytes)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{save_name, Dirs};
+
+    fn state(dirs: &Dirs) -> State {
+        let live = |n| dirs.save_dir.join(save_name(n, 1));
+        let backup = |n| dirs.backup_dir.join(save_name(n, 1));
+        State {
+            saves: vec![
+                Save::OriginalFileOnly(live(1)),
+                Save::BackupFileOnly(backup(2)),
+                Save::Both(live(3), backup(3)),
+                // nothing stops a name having a tab or a newline in it
+                Save::Both(
+                    dirs.save_dir.join("tab\there.broguesave"),
+                    dirs.backup_dir.join("new\nline.broguesave"),
+                ),
+            ],
+            scanned: 7,
+        }
+    }
+
+    #[test]
+    fn loads_what_it_saved() {
+        let dirs = Dirs::new();
+        save(&dirs.save_dir, &dirs.backup_dir, &state(&dirs)).unwrap();
+
+        let loaded = load(&dirs.save_dir, &dirs.backup_dir).unwrap();
+        assert_eq!(loaded.saves, state(&dirs).saves);
+        assert_eq!(loaded.scanned, 0);
+    }
+
+    #[test]
+    fn ignores_a_cache_for_other_dirs() {
+        let dirs = Dirs::new();
+        save(&dirs.save_dir, &dirs.backup_dir, &state(&dirs)).unwrap();
+
+        let elsewhere = dirs.save_dir.join("elsewhere");
+        assert!(load(&elsewhere, &dirs.backup_dir).is_none());
+        std::fs::rename(
+            dirs.backup_dir.join(STATE_CACHE_FILE),
+            dirs.save_dir.join(STATE_CACHE_FILE),
+        )
+        .unwrap();
+        assert!(load(&dirs.save_dir, &dirs.save_dir).is_none());
+    }
+
+    #[test]
+    fn ignores_a_missing_or_damaged_cache() {
+        let dirs = Dirs::new();
+        assert!(load(&dirs.save_dir, &dirs.backup_dir).is_none());
+
+        std::fs::write(dirs.backup_dir.join(STATE_CACHE_FILE), b"\x01\x02\x03").unwrap();
+        assert!(load(&dirs.save_dir, &dirs.backup_dir).is_none());
+    }
+}
@@ -0,0 +1,380 @@
+use crate::activity::Activity;
+use crate::backup::{DedupMode, KeepDiverged, SyncMode};
+use crate::metadata::Difficulty;
+use crate::notification::QuietHours;
+use crate::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use strum_macros::Display;
+
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Settings read from `config.toml` in the backup dir. Everything is optional; a missing
+/// file is the same as an empty one.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct Config {
+    /// where brogue keeps its saves, if not the usual place; set by the first-run setup
+    pub save_dir: Option<PathBuf>,
+    /// where Steam is installed, if not the usual place; without a `save_dir`, and with no
+    /// standalone brogue saves, we look there for the Steam version's
+    pub steam_dir: Option<PathBuf>,
+    pub mode: SyncMode,
+    /// run by the shell after a save is backed up, with the backup's path as the last
+    /// argument; quote a program whose path has spaces in it
+    pub post_backup_cmd: Option<String>,
+    /// run by the shell after a save is restored, with the restored save's path as the last
+    /// argument
+    pub post_restore_cmd: Option<String>,
+    /// what 'x' runs on the selected save or recording to play or replay it, e.g.
+    /// "brogue --view {path}"; without `{path}`, the path goes on the end
+    pub play_cmd: Option<String>,
+    /// flag backup-only saves that haven't been restored after this many seconds
+    pub stale_backup_secs: u64,
+    /// show the newest save's protection as yellow once it's gone this long without a backup
+    pub health_warn_secs: u64,
+    /// and as red once it's gone this long
+    pub health_alarm_secs: u64,
+    /// set to false (or pass `--no-delete`) to stop saves being deleted from the UI
+    pub allow_delete: bool,
+    /// the key that starts the delete prompt; `delete` in `keybindings` takes its place
+    pub delete_key: char,
+    /// never delete the last copy of a save in one keystroke: deleting a backed-up save only
+    /// removes the live copy and moves the backup into `archive/`, and deleting one that's
+    /// only in the backup dir asks again first
+    pub protect_last_copy: bool,
+    /// keep the most recently modified save selected
+    pub follow: bool,
+    /// list the newest saves first; set to false for the oldest first
+    pub sort_descending: bool,
+    /// what to do with backups that duplicate another backup's contents under a different name
+    pub dedup: DedupMode,
+    /// where to write the log
+    pub log_file: PathBuf,
+    /// how often to redraw the screen
+    pub render_interval_ms: u64,
+    /// how often to scan the save and backup dirs and sync them
+    pub reconcile_interval_ms: u64,
+    /// keep a version of a save every time brogue rewrites it, not just when we back it up;
+    /// uses a lot more space
+    pub snapshot: bool,
+    /// every this many minutes, copy the whole save dir into a new `snapshots/<time>/`
+    /// folder in the backup dir, to go back to if the sync itself ever goes wrong; 0 turns it
+    /// off
+    pub snapshot_interval_mins: u64,
+    /// how many of those snapshots to keep; the oldest are deleted
+    pub keep_snapshots: usize,
+    /// make a backup that matches its save a hard link to it rather than a second copy, when
+    /// they're on the same volume. Brogue deletes a save it loads and writes a new file when it
+    /// saves, which leaves the backup alone; a save rewritten in place changes its backup too,
+    /// and gets a copy of its own again from then on
+    pub hardlink: bool,
+    /// more dirs to keep a copy of every backup in, like one on an external drive; the backup
+    /// dir still holds everything else. One that's missing, like an unplugged drive, is
+    /// skipped until it's back, and is never created
+    pub extra_backup_dirs: Vec<PathBuf>,
+    /// how many of the backup dirs, the main one included, a save has to be in to count as
+    /// backed up; 0 means all of them
+    pub backup_quorum: usize,
+    /// sync nothing without asking: each copy, restore or archive waits for '+' to approve it
+    /// or '-' to reject it, and nothing is deduplicated, pruned or snapshotted
+    pub manual: bool,
+    /// which copy of each save whose live copy and backup differ 'K' keeps: "newest", "live"
+    /// or "backup"
+    pub keep_diverged: KeepDiverged,
+    /// if the backup dir is a git repo, commit to it after every change
+    pub git_commit: bool,
+    /// watch the save dir by polling even if it doesn't look like a network mount
+    pub force_poll: bool,
+    /// how to mark the selected save: "reverse", a colour name like "yellow", or "#rrggbb"
+    pub highlight: String,
+    /// a file holding the passphrase to encrypt backups with; `BACKUP_BROGUE_KEY` overrides it
+    pub key_file: Option<PathBuf>,
+    /// how to format times and sizes, e.g. "de" or "fr_FR"; unset follows `LANG`
+    pub locale: Option<String>,
+    /// brogue's own settings files to back up into `config/`, by name; `*` and `?` match
+    /// like they do in a shell, e.g. "*.txt"
+    pub extra_files: Vec<String>,
+    /// saves and recordings bigger than this aren't backed up; nothing brogue writes comes
+    /// close, so a file this big is probably something else
+    pub max_save_bytes: u64,
+    /// presses of 'j'/'k' closer together than this count as holding the key down
+    pub key_repeat_ms: u64,
+    /// while 'j'/'k' is held, scroll one more save per press for every this many ms
+    pub scroll_accel_ms: u64,
+    /// the most saves a held 'j'/'k' scrolls per press
+    pub scroll_max_step: usize,
+    /// how many backed-up recordings to keep once they're gone from the save dir; older ones go
+    /// to `trash/`. Unset keeps them all
+    pub keep_recordings: Option<usize>,
+    /// warn when the save dir holds more saves than this, and offer to archive the oldest;
+    /// 0 turns the warning off
+    pub max_live_saves: usize,
+    /// copy saves no faster than this, to spare a slow disk while brogue is running; the
+    /// screen doesn't update while a copy is held back
+    pub max_copy_bytes_per_sec: Option<u64>,
+    /// leave a save alone while brogue is still writing it, judged by it having changed in the
+    /// last `write_settle_ms`, rather than risk copying half a file
+    pub auto_defer_active_writes: bool,
+    pub write_settle_ms: u64,
+    /// how often to check the backup dir is still writable; while it isn't, we only watch
+    pub writable_check_ms: u64,
+    /// treat the backup dir as unwritable while its volume has fewer free inodes than this,
+    /// rather than fail copies part way; every backup, version and snapshot uses some. 0
+    /// turns the check off
+    pub min_free_inodes: u64,
+    /// pop up a desktop notification when saves are backed up, restored or archived
+    pub notifications: bool,
+    /// a time of day when no notifications pop up, like "22:00-07:00"; it can run past
+    /// midnight. What they'd have said is still logged
+    pub quiet_hours: Option<QuietHours>,
+    /// mark log lines with plain ASCII icons like '^' for a backup, for a terminal whose font
+    /// doesn't have the arrows
+    pub ascii_icons: bool,
+    // the tables go last so the config can be written back out as toml
+    /// keys to use in place of the usual ones, by action, e.g. `quit = "Q"`; several keys go
+    /// space-separated, like `down = "j down"`
+    pub keybindings: BTreeMap<String, String>,
+    /// icons to mark log lines with in place of the usual ones, by kind: backup, restore,
+    /// delete or skip, e.g. `backup = "B"`
+    pub log_icons: BTreeMap<String, String>,
+    /// colours for log lines in place of the usual ones, by kind as for `log_icons`; a colour
+    /// name like "green", or "#rrggbb"
+    pub log_colors: BTreeMap<String, String>,
+    /// how long to keep finished games of each difficulty, e.g. `[prune.easy]`
+    pub prune: BTreeMap<String, PrunePolicy>,
+    /// how long to keep finished games of a difficulty `prune` doesn't mention, or can't be
+    /// read from the name
+    pub prune_default: PrunePolicy,
+}
+
+/// How long to keep a finished game: one that's only in the backup dir or `archive/`. Pruned
+/// games go to `trash/`.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(default)]
+pub struct PrunePolicy {
+    /// unset keeps them forever
+    pub max_age_days: Option<u64>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            save_dir: None,
+            steam_dir: None,
+            mode: SyncMode::Mirror,
+            post_backup_cmd: None,
+            post_restore_cmd: None,
+            play_cmd: None,
+            stale_backup_secs: 10 * 60,
+            health_warn_secs: 60,
+            health_alarm_secs: 5 * 60,
+            allow_delete: true,
+            delete_key: 'd',
+            protect_last_copy: false,
+            follow: false,
+            sort_descending: true,
+            dedup: DedupMode::Off,
+            log_file: PathBuf::from("output.log"),
+            render_interval_ms: 100,
+            reconcile_interval_ms: 250,
+            force_poll: false,
+            git_commit: false,
+            snapshot: false,
+            snapshot_interval_mins: 0,
+            keep_snapshots: 24,
+            hardlink: false,
+            extra_backup_dirs: vec![],
+            backup_quorum: 0,
+            manual: false,
+            keep_diverged: KeepDiverged::Newest,
+            highlight: "reverse".to_string(),
+            key_file: None,
+            locale: None,
+            extra_files: vec![],
+            max_save_bytes: 100 * 1024 * 1024,
+            key_repeat_ms: 150,
+            scroll_accel_ms: 500,
+            scroll_max_step: 5,
+            keep_recordings: None,
+            max_live_saves: 200,
+            max_copy_bytes_per_sec: None,
+            auto_defer_active_writes: true,
+            write_settle_ms: 1000,
+            writable_check_ms: 5000,
+            min_free_inodes: 0,
+            notifications: false,
+            quiet_hours: None,
+            ascii_icons: false,
+            keybindings: BTreeMap::new(),
+            log_icons: BTreeMap::new(),
+            log_colors: BTreeMap::new(),
+            prune: BTreeMap::new(),
+            prune_default: PrunePolicy::default(),
+        }
+    }
+}
+
+/// Where a setting's value came from, for `--print-config`. Later sources win.
+#[derive(Clone, Copy, Debug, PartialEq, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum Source {
+    Default,
+    Config,
+    /// an environment variable, like `BROGUE_SAVE_DIR`
+    Env,
+    Flag,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Config> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+
+        let text = std::fs::read_to_string(path)?;
+        Ok(toml::from_str(&text)?)
+    }
+
+    /// The icon to mark log lines about `activity` with.
+    pub fn log_icon(&self, activity: Activity) -> &str {
+        self.log_icons
+            .get(&activity.to_string())
+            .map(String::as_str)
+            .unwrap_or_else(|| activity.default_icon(self.ascii_icons))
+    }
+
+    /// The colour, by name, of log lines about `activity`.
+    pub fn log_color(&self, activity: Activity) -> &str {
+        self.log_colors
+            .get(&activity.to_string())
+            .map(String::as_str)
+            .unwrap_or_else(|| activity.default_color())
+    }
+
+    /// The prune policy for games of `difficulty`.
+    pub fn prune_policy(&self, difficulty: Option<Difficulty>) -> PrunePolicy {
+        difficulty
+            .and_then(|d| self.prune.get(&d.to_string()))
+            .copied()
+            .unwrap_or(self.prune_default)
+    }
+
+    /// The names of the settings the config file at `path` sets.
+    pub fn keys_in_file(path: &Path) -> Result<BTreeSet<String>> {
+        if !path.exists() {
+            return Ok(BTreeSet::new());
+        }
+
+        let text = std::fs::read_to_string(path)?;
+        let table: toml::value::Table = toml::from_str(&text)?;
+        Ok(table.keys().cloned().collect())
+    }
+
+    /// Every setting with its value and where that came from. `file_keys` are the settings
+    /// the config file set and `flags` the ones overridden on the command line. Settings that
+    /// are unset, like hooks nobody configured, are left out.
+    pub fn describe(
+        &self,
+        file_keys: &BTreeSet<String>,
+        flags: &[&str],
+    ) -> Result<Vec<(String, String, Source)>> {
+        let table = match toml::Value::try_from(self)? {
+            toml::Value::Table(table) => table,
+            _ => return Ok(vec![]),
+        };
+        Ok(table
+            .into_iter()
+            .map(|(key, value)| {
+                let source = if flags.contains(&key.as_str()) {
+                    Source::Flag
+                } else if file_keys.contains(&key) {
+                    Source::Config
+                } else {
+                    Source::Default
+                };
+                let value = match value {
+                    toml::Value::String(s) => s,
+                    other => inline(&other),
+                };
+                (key, value, source)
+            })
+            .collect())
+    }
+}
+
+// a value on one line, with tables written inline like `{ easy = { max_age_days = 7 } }`
+fn inline(value: &toml::Value) -> String {
+    match value {
+        toml::Value::Table(table) if table.is_empty() => "{}".to_string(),
+        toml::Value::Table(table) => {
+            let entries: Vec<_> = table
+                .iter()
+                .map(|(key, value)| format!("{} = {}", key, inline(value)))
+                .collect();
+            format!("{{ {} }}", entries.join(", "))
+        }
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_each_setting_with_where_it_came_from() {
+        let config: Config = toml::from_str(
+            r#"
+            mode = "archive"
+            post_backup_cmd = "notify-send backed up"
+            allow_delete = false
+
+            [prune.easy]
+            max_age_days = 7
+            "#,
+        )
+        .unwrap();
+        let file_keys: BTreeSet<String> = ["mode", "post_backup_cmd", "allow_delete", "prune"]
+            .iter()
+            .map(|key| key.to_string())
+            .collect();
+        // as if --no-delete was passed too
+        let rows = config.describe(&file_keys, &["allow_delete"]).unwrap();
+        let row = |key: &str| {
+            rows.iter()
+                .find(|(k, _, _)| k == key)
+                .map(|(_, value, source)| (value.as_str(), *source))
+        };
+
+        assert_eq!(row("mode"), Some(("archive", Source::Config)));
+        assert_eq!(
+            row("post_backup_cmd"),
+            Some(("notify-send backed up", Source::Config))
+        );
+        assert_eq!(row("allow_delete"), Some(("false", Source::Flag)));
+        assert_eq!(row("reconcile_interval_ms"), Some(("250", Source::Default)));
+        assert_eq!(
+            row("prune"),
+            Some(("{ easy = { max_age_days = 7 } }", Source::Config))
+        );
+        assert_eq!(row("keybindings"), Some(("{}", Source::Default)));
+        // unset, so left out
+        assert_eq!(row("post_restore_cmd"), None);
+        assert_eq!(row("key_file"), None);
+    }
+
+    #[test]
+    fn lists_the_keys_a_config_file_sets() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        assert!(Config::keys_in_file(&path).unwrap().is_empty());
+
+        std::fs::write(&path, "follow = true\n[keybindings]\nquit = \"Q\"\n").unwrap();
+        assert_eq!(
+            Config::keys_in_file(&path).unwrap(),
+            BTreeSet::from(["follow".to_string(), "keybindings".to_string()])
+        );
+    }
+}
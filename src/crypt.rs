@@ -0,0 +1,140 @@
+use crate::backup::{hash_file, stable_hash};
+use crate::config::Config;
+use crate::{AppError, Result};
+use argon2::Argon2;
+use chacha20poly1305::aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+pub const ENCRYPTED_EXTENSION: &str = "age";
+pub const KEY_ENV_VAR: &str = "BACKUP_BROGUE_KEY";
+
+// An encrypted backup is MAGIC, then the salt the key was derived with, then the nonce, then
+// the ChaCha20-Poly1305 ciphertext of the save.
+const MAGIC: &[u8] = b"BBENC1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Encrypts and decrypts backups with a key derived from the passphrase in
+/// `BACKUP_BROGUE_KEY` or the config's `key_file`. Keys are derived with argon2, which is
+/// deliberately slow, so each salt's key is only worked out once.
+pub struct Cipher {
+    secret: Vec<u8>,
+    salt: [u8; SALT_LEN],
+    keys: Mutex<HashMap<[u8; SALT_LEN], Key>>,
+}
+
+impl Cipher {
+    /// The cipher to use, or `None` if no passphrase is configured.
+    pub fn load(config: &Config) -> Result<Option<Cipher>> {
+        let secret = match std::env::var(KEY_ENV_VAR) {
+            Ok(passphrase) => passphrase,
+            Err(_) => match &config.key_file {
+                Some(path) => std::fs::read_to_string(path)?,
+                None => return Ok(None),
+            },
+        };
+        let secret = secret.trim_end_matches(['\r', '\n']);
+        if secret.is_empty() {
+            return Ok(None);
+        }
+
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Ok(Some(Cipher {
+            secret: secret.as_bytes().to_vec(),
+            salt,
+            keys: Mutex::new(HashMap::new()),
+        }))
+    }
+
+    fn key(&self, salt: &[u8; SALT_LEN]) -> Result<Key> {
+        let mut keys = self.keys.lock().expect("cipher keys lock");
+        if let Some(key) = keys.get(salt) {
+            return Ok(*key);
+        }
+
+        let mut key = Key::default();
+        Argon2::default()
+            .hash_password_into(&self.secret, salt, &mut key)
+            .map_err(|e| AppError::EncryptionError(e.to_string()))?;
+        keys.insert(*salt, key);
+        Ok(key)
+    }
+
+    pub fn encrypt(&self, plain: &[u8]) -> Result<Vec<u8>> {
+        let cipher = ChaCha20Poly1305::new(&self.key(&self.salt)?);
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, plain)
+            .map_err(|e| AppError::EncryptionError(e.to_string()))?;
+
+        let mut out = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&self.salt);
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&ciphertext);
+        Ok(out)
+    }
+
+    /// Decrypts the contents of the backup at `path`, failing if the key is wrong or the file
+    /// has been tampered with.
+    pub fn decrypt(&self, path: &Path, data: &[u8]) -> Result<Vec<u8>> {
+        let failed = || AppError::DecryptionFailed(path.to_path_buf());
+        let rest = data.strip_prefix(MAGIC).ok_or_else(failed)?;
+        if rest.len() < SALT_LEN + NONCE_LEN {
+            return Err(failed());
+        }
+        let (salt, rest) = rest.split_at(SALT_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+        let salt: [u8; SALT_LEN] = salt.try_into().map_err(|_| failed())?;
+        let cipher = ChaCha20Poly1305::new(&self.key(&salt)?);
+        cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| failed())
+    }
+}
+
+pub fn is_encrypted(path: &Path) -> bool {
+    path.extension().unwrap_or_default() == ENCRYPTED_EXTENSION
+}
+
+/// `path` with `.age` on the end, e.g. 'x.broguesave' -> 'x.broguesave.age'.
+pub fn encrypted_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".");
+    file_name.push(ENCRYPTED_EXTENSION);
+    path.with_file_name(file_name)
+}
+
+/// The name of the save a file holds, without any `.age` suffix.
+pub fn plain_name(path: &Path) -> String {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let suffix = format!(".{}", ENCRYPTED_EXTENSION);
+    name.strip_suffix(&suffix).unwrap_or(&name).to_string()
+}
+
+/// Reads a save or backup, decrypting it if it's encrypted.
+pub fn read_contents(path: &Path, cipher: Option<&Cipher>) -> Result<Vec<u8>> {
+    let data = std::fs::read(path)?;
+    if !is_encrypted(path) {
+        return Ok(data);
+    }
+    match cipher {
+        Some(cipher) => cipher.decrypt(path, &data),
+        None => Err(AppError::NoEncryptionKey(path.to_path_buf())),
+    }
+}
+
+/// The same hash as `hash_file`, but of the decrypted contents for an encrypted backup, so a
+/// save and its encrypted backup can still be compared.
+pub fn hash_contents(path: &Path, cipher: Option<&Cipher>) -> Result<u64> {
+    if !is_encrypted(path) {
+        return hash_file(path);
+    }
+    Ok(stable_hash(&read_contents(path, cipher)?))
+}
@@ -0,0 +1,110 @@
+use std::path::Path;
+
+/// What `statvfs` says about a volume: its free space and its inodes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VolumeStat {
+    /// blocks free for unprivileged users, and how big each one is
+    pub blocks_available: u64,
+    pub block_size: u64,
+    /// inodes in total, and free for unprivileged users
+    pub files: u64,
+    pub files_available: u64,
+}
+
+impl VolumeStat {
+    pub fn free_kb(&self) -> u64 {
+        self.blocks_available.saturating_mul(self.block_size) / 1024
+    }
+
+    /// `None` for a volume with no fixed number of inodes, like btrfs, which reports none
+    /// at all.
+    pub fn free_inodes(&self) -> Option<u64> {
+        (self.files > 0).then_some(self.files_available)
+    }
+}
+
+/// Asks `statvfs` about the volume holding `dir`; `None` where there isn't one, as on
+/// windows, or it fails.
+#[cfg(unix)]
+pub fn stat(dir: &Path) -> Option<VolumeStat> {
+    let stat = nix::sys::statvfs::statvfs(dir).ok()?;
+    // the field types differ between platforms; none are wider than 64 bits
+    #[allow(clippy::unnecessary_cast, clippy::useless_conversion)]
+    Some(VolumeStat {
+        blocks_available: u64::from(stat.blocks_available()),
+        block_size: stat.fragment_size() as u64,
+        files: u64::from(stat.files()),
+        files_available: u64::from(stat.files_available()),
+    })
+}
+
+#[cfg(not(unix))]
+pub fn stat(_dir: &Path) -> Option<VolumeStat> {
+    None
+}
+
+/// How much space is free on the volume holding `dir`, in KB.
+pub fn free_kb(dir: &Path) -> Option<u64> {
+    stat(dir).map(|stat| stat.free_kb())
+}
+
+/// How many more files can be made on the volume holding `dir`; `None` if we can't tell, or
+/// it has no fixed number of inodes, like btrfs.
+pub fn free_inodes(dir: &Path) -> Option<u64> {
+    stat(dir)?.free_inodes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_free_inodes_on_a_volume_with_a_fixed_number() {
+        let ext4 = VolumeStat {
+            files: 655_360,
+            files_available: 412_000,
+            ..VolumeStat::default()
+        };
+        assert_eq!(ext4.free_inodes(), Some(412_000));
+
+        let full = VolumeStat {
+            files: 655_360,
+            files_available: 0,
+            ..VolumeStat::default()
+        };
+        assert_eq!(full.free_inodes(), Some(0));
+    }
+
+    #[test]
+    fn a_volume_with_no_inodes_has_no_limit() {
+        // btrfs reports every inode count as zero
+        assert_eq!(VolumeStat::default().free_inodes(), None);
+    }
+
+    #[test]
+    fn free_space_is_in_kb() {
+        let stat = VolumeStat {
+            blocks_available: 3,
+            block_size: 4096,
+            ..VolumeStat::default()
+        };
+        assert_eq!(stat.free_kb(), 12);
+
+        let huge = VolumeStat {
+            blocks_available: u64::MAX,
+            block_size: 4096,
+            ..VolumeStat::default()
+        };
+        assert_eq!(huge.free_kb(), u64::MAX / 1024);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stats_a_real_volume() {
+        let dir = tempfile::tempdir().unwrap();
+        let volume = stat(dir.path()).unwrap();
+        assert!(volume.block_size > 0);
+        assert!(volume.files_available <= volume.files);
+        assert!(stat(&dir.path().join("missing")).is_none());
+    }
+}
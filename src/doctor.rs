@@ -0,0 +1,176 @@
+use crate::backup::check_writable;
+use crate::config::Config;
+use crate::disk::{free_inodes, free_kb};
+use crate::paths;
+use crate::steam;
+use std::path::Path;
+
+// warn when the backup dir's volume has less than this free
+const LOW_DISK_SPACE_KB: u64 = 100 * 1024;
+// and when it has fewer than this many files left to make
+const LOW_FREE_INODES: u64 = 10_000;
+
+enum Outcome {
+    Pass(String),
+    /// worth knowing about, but the tool will still work
+    Warn(String),
+    /// the tool won't work until this is fixed
+    Fail(String),
+}
+
+/// Checks everything the tool needs and prints a line for each, for `--doctor`. Returns
+/// whether every critical check passed.
+pub fn run(
+    save_dir_in_home: &str,
+    save_dir_flag: Option<&Path>,
+    backup_dir_flag: Option<&Path>,
+) -> bool {
+    let mut checks = vec![];
+
+    let home = match dirs::home_dir() {
+        Some(home) => {
+            checks.push(("home dir", Outcome::Pass(home.display().to_string())));
+            home
+        }
+        None => {
+            checks.push((
+                "home dir",
+                Outcome::Fail("couldn't work it out".to_string()),
+            ));
+            return report(checks);
+        }
+    };
+
+    let mut paths = paths::resolve(&home);
+    if let Some((dir, _)) = paths::choose_dir(
+        &home,
+        backup_dir_flag,
+        std::env::var_os(paths::BACKUP_DIR_VAR),
+        None,
+    ) {
+        paths.backup_dir = dir;
+    }
+
+    // a broken config is reported below; until then assume the usual place
+    let config = Config::load(&paths.config_file).unwrap_or_default();
+    let save_dir = match paths::choose_dir(
+        &home,
+        save_dir_flag,
+        std::env::var_os(paths::SAVE_DIR_VAR),
+        config.save_dir.as_deref(),
+    ) {
+        Some((dir, _)) => dir,
+        None => {
+            let standalone = home.join(save_dir_in_home);
+            steam::default_save_dir(&home, standalone, config.steam_dir.as_deref())
+        }
+    };
+    checks.push(("save dir", check_save_dir(&save_dir)));
+
+    let backup_dir = check_backup_dir(&paths.backup_dir);
+    let writable = matches!(backup_dir, Outcome::Pass(_));
+    checks.push(("backup dir", backup_dir));
+    if writable {
+        checks.push(("disk space", check_disk_space(&paths.backup_dir)));
+        checks.push((
+            "inodes",
+            check_inodes(&paths.backup_dir, config.min_free_inodes),
+        ));
+    }
+
+    checks.push(("config", check_config(&paths.config_file)));
+    checks.push(("notifications", check_notifications(&config)));
+
+    report(checks)
+}
+
+fn report(checks: Vec<(&str, Outcome)>) -> bool {
+    let mut failures = 0;
+    let mut warnings = 0;
+    for (name, outcome) in &checks {
+        let (label, detail) = match outcome {
+            Outcome::Pass(detail) => ("  ok", detail),
+            Outcome::Warn(detail) => {
+                warnings += 1;
+                ("warn", detail)
+            }
+            Outcome::Fail(detail) => {
+                failures += 1;
+                ("FAIL", detail)
+            }
+        };
+        println!("[{}] {}: {}", label, name, detail);
+    }
+
+    match (failures, warnings) {
+        (0, 0) => println!("all good"),
+        (0, w) => println!("ready, with {} warning(s)", w),
+        (f, _) => println!("not ready: {} problem(s) to fix", f),
+    }
+    failures == 0
+}
+
+fn check_save_dir(save_dir: &Path) -> Outcome {
+    if !save_dir.exists() {
+        return Outcome::Warn(format!(
+            "{} doesn't exist yet; brogue creates it the first time it runs",
+            save_dir.display()
+        ));
+    }
+    match std::fs::read_dir(save_dir) {
+        Ok(_) => Outcome::Pass(save_dir.display().to_string()),
+        Err(e) => Outcome::Fail(format!("can't read {}: {}", save_dir.display(), e)),
+    }
+}
+
+fn check_backup_dir(backup_dir: &Path) -> Outcome {
+    if let Err(e) = std::fs::create_dir_all(backup_dir) {
+        return Outcome::Fail(format!("can't create {}: {}", backup_dir.display(), e));
+    }
+
+    match check_writable(backup_dir) {
+        Ok(()) => Outcome::Pass(format!("{} is writable", backup_dir.display())),
+        Err(e) => Outcome::Fail(format!("can't write to {}: {}", backup_dir.display(), e)),
+    }
+}
+
+fn check_disk_space(dir: &Path) -> Outcome {
+    match free_kb(dir) {
+        Some(kb) if kb < LOW_DISK_SPACE_KB => Outcome::Warn(format!("only {} MB free", kb / 1024)),
+        Some(kb) => Outcome::Pass(format!("{} MB free", kb / 1024)),
+        None => Outcome::Warn("couldn't find out how much space is free".to_string()),
+    }
+}
+
+// below `min_free_inodes` nothing gets backed up, so that's a failure, not a warning
+fn check_inodes(dir: &Path, min_free_inodes: u64) -> Outcome {
+    match free_inodes(dir) {
+        Some(free) if free < min_free_inodes => Outcome::Fail(format!(
+            "only {} free; backups stop below min_free_inodes ({})",
+            free, min_free_inodes
+        )),
+        Some(free) if free < LOW_FREE_INODES => Outcome::Warn(format!("only {} free", free)),
+        Some(free) => Outcome::Pass(format!("{} free", free)),
+        None => Outcome::Pass("no fixed limit on this volume".to_string()),
+    }
+}
+
+fn check_config(config_file: &Path) -> Outcome {
+    if !config_file.exists() {
+        return Outcome::Pass(format!("no {}; using defaults", config_file.display()));
+    }
+    match Config::load(config_file) {
+        Ok(_) => Outcome::Pass(format!("{} is valid", config_file.display())),
+        Err(e) => Outcome::Fail(format!("{}: {}", config_file.display(), e)),
+    }
+}
+
+fn check_notifications(config: &Config) -> Outcome {
+    if !config.notifications {
+        return Outcome::Pass("off".to_string());
+    }
+    if cfg!(target_os = "linux") && std::env::var_os("DBUS_SESSION_BUS_ADDRESS").is_none() {
+        return Outcome::Warn("no D-Bus session, so desktop notifications won't show".to_string());
+    }
+    Outcome::Pass("supported".to_string())
+}
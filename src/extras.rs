@@ -0,0 +1,200 @@
+use crate::backup::copy_atomic;
+use crate::Result;
+use log::info;
+use std::path::{Path, PathBuf};
+
+/// Where copies of brogue's own settings files go, inside the backup dir.
+pub const EXTRAS_DIR: &str = "config";
+
+/// Copies the files in `save_dir` that match any of `patterns` into the `config/` folder of
+/// `backup_dir`, if they're new or have changed since the last copy. These are brogue's
+/// settings, like keybindings, so they're kept apart from the saves and never restored
+/// automatically. Returns the names of the files copied.
+pub fn backup(save_dir: &Path, backup_dir: &Path, patterns: &[String]) -> Result<Vec<String>> {
+    if patterns.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let extras_dir = backup_dir.join(EXTRAS_DIR);
+    let mut copied = vec![];
+    for path in matching(save_dir, patterns)? {
+        let name = path.file_name().unwrap_or_default();
+        let destination = extras_dir.join(name);
+        if !changed(&path, &destination)? {
+            continue;
+        }
+
+        std::fs::create_dir_all(&extras_dir)?;
+        info!(
+            "[CONFIG] copying {} => {}",
+            path.display(),
+            destination.display()
+        );
+        copy_atomic(&path, &destination, None, None)?;
+        copied.push(name.to_string_lossy().to_string());
+    }
+    Ok(copied)
+}
+
+/// Copies everything in the `config/` folder of `backup_dir` back into `save_dir`, for
+/// `--restore-config`. Returns the files restored.
+pub fn restore(backup_dir: &Path, save_dir: &Path) -> Result<Vec<PathBuf>> {
+    let extras_dir = backup_dir.join(EXTRAS_DIR);
+    if !extras_dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut restored = vec![];
+    for entry in std::fs::read_dir(&extras_dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            continue;
+        }
+        let destination = save_dir.join(path.file_name().unwrap_or_default());
+        info!(
+            "[CONFIG] restoring {} => {}",
+            path.display(),
+            destination.display()
+        );
+        copy_atomic(&path, &destination, None, None)?;
+        restored.push(destination);
+    }
+    restored.sort();
+    Ok(restored)
+}
+
+fn matching(dir: &Path, patterns: &[String]) -> Result<Vec<PathBuf>> {
+    let mut res = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            continue;
+        }
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if patterns.iter().any(|pattern| glob_match(pattern, &name)) {
+            res.push(path);
+        }
+    }
+    Ok(res)
+}
+
+// a copy is always written after the original, so it's out of date if the original is newer
+fn changed(original: &Path, copy: &Path) -> Result<bool> {
+    if !copy.exists() {
+        return Ok(true);
+    }
+    let original = original.metadata()?;
+    let copy = copy.metadata()?;
+    Ok(original.len() != copy.len() || original.modified()? > copy.modified()?)
+}
+
+/// Matches a file name against a pattern where `*` is any run of characters and `?` is any
+/// one character; enough for names like "*.txt" without pulling in a glob crate.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // where the last `*` was, and how much of the name it had taken when we got there
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                // let the last `*` take one more character and try again
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{names, set_age, write, Dirs};
+    use std::time::Duration;
+
+    #[test]
+    fn matches_names_like_a_shell_glob() {
+        assert!(glob_match("*.txt", "keybindings.txt"));
+        assert!(glob_match("brogue?.cfg", "brogue1.cfg"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "axxbyyc"));
+        assert!(!glob_match("*.txt", "keybindings.txt.bak"));
+        assert!(!glob_match("brogue?.cfg", "brogue.cfg"));
+        assert!(!glob_match("", "x"));
+    }
+
+    #[test]
+    fn backs_up_matching_files_until_they_change() {
+        let dirs = Dirs::new();
+        write(&dirs.save_dir, "keybindings.txt", "q = quit");
+        write(&dirs.save_dir, "Saved #1 at depth 1.broguesave", "game");
+        let patterns = vec!["*.txt".to_string()];
+
+        let copied = backup(&dirs.save_dir, &dirs.backup_dir, &patterns).unwrap();
+        assert_eq!(copied, ["keybindings.txt"]);
+        assert_eq!(
+            names(&dirs.backup_dir.join(EXTRAS_DIR)),
+            ["keybindings.txt"]
+        );
+        assert!(backup(&dirs.save_dir, &dirs.backup_dir, &patterns)
+            .unwrap()
+            .is_empty());
+
+        let changed = dirs.save_dir.join("keybindings.txt");
+        std::fs::write(&changed, "q = quit\nd = drop").unwrap();
+        set_age(&changed, Duration::ZERO);
+        assert_eq!(
+            backup(&dirs.save_dir, &dirs.backup_dir, &patterns).unwrap(),
+            ["keybindings.txt"]
+        );
+    }
+
+    #[test]
+    fn backs_up_nothing_without_patterns() {
+        let dirs = Dirs::new();
+        write(&dirs.save_dir, "keybindings.txt", "q = quit");
+        assert!(backup(&dirs.save_dir, &dirs.backup_dir, &[])
+            .unwrap()
+            .is_empty());
+        assert!(!dirs.backup_dir.join(EXTRAS_DIR).exists());
+    }
+
+    #[test]
+    fn restores_the_backed_up_files_into_the_save_dir() {
+        let dirs = Dirs::new();
+        assert!(restore(&dirs.backup_dir, &dirs.save_dir)
+            .unwrap()
+            .is_empty());
+
+        let extras_dir = dirs.backup_dir.join(EXTRAS_DIR);
+        std::fs::create_dir_all(&extras_dir).unwrap();
+        write(&extras_dir, "keybindings.txt", "q = quit");
+        write(&extras_dir, "brogue.cfg", "fast");
+
+        assert_eq!(
+            restore(&dirs.backup_dir, &dirs.save_dir).unwrap(),
+            [
+                dirs.save_dir.join("brogue.cfg"),
+                dirs.save_dir.join("keybindings.txt")
+            ]
+        );
+        assert_eq!(
+            std::fs::read_to_string(dirs.save_dir.join("keybindings.txt")).unwrap(),
+            "q = quit"
+        );
+    }
+}
@@ -0,0 +1,41 @@
+use crate::Result;
+use log::warn;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// the chance of failing, as the bits of an f64, and the state of a xorshift generator; a real
+// random number crate isn't worth it for a debugging aid
+static RATE: AtomicU64 = AtomicU64::new(0);
+static STATE: AtomicU64 = AtomicU64::new(0);
+
+/// Makes roughly `rate` (0 to 1) of copies fail from now on, for `--simulate-failure`.
+pub fn set_rate(rate: f64) {
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default();
+    // xorshift never leaves zero
+    STATE.store(seed | 1, Ordering::Relaxed);
+    RATE.store(rate.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+}
+
+/// Fails as if writing `to` had gone wrong, as often as `set_rate` asked for.
+pub fn maybe_fail(to: &Path) -> Result<()> {
+    let rate = f64::from_bits(RATE.load(Ordering::Relaxed));
+    if rate <= 0.0 || next() >= rate {
+        return Ok(());
+    }
+    warn!("[FAULT] simulating a failed copy to {}", to.display());
+    Err(std::io::Error::other("simulated failure").into())
+}
+
+// a number in [0, 1)
+fn next() -> f64 {
+    let mut x = STATE.load(Ordering::Relaxed);
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    STATE.store(x, Ordering::Relaxed);
+    (x >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -0,0 +1,123 @@
+use crate::crypt::{hash_contents, Cipher};
+use crate::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Identifies a particular version of a file without reading it.
+pub type Fingerprint = (PathBuf, SystemTime, u64);
+
+pub fn fingerprint(path: &Path) -> Option<Fingerprint> {
+    let metadata = path.metadata().ok()?;
+    Some((
+        path.to_path_buf(),
+        metadata.modified().ok()?,
+        metadata.len(),
+    ))
+}
+
+/// Hashes of file contents, remembered by path, mtime and size, so a file that hasn't changed
+/// is only ever read once. Shared by the sync loop and the verifier's thread.
+pub struct HashCache {
+    cipher: Option<Arc<Cipher>>,
+    hashes: Mutex<HashMap<Fingerprint, u64>>,
+}
+
+impl HashCache {
+    pub fn new(cipher: Option<Arc<Cipher>>) -> HashCache {
+        HashCache {
+            cipher,
+            hashes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The same as `hash_contents`, but only reads `path` if it's changed since we last did.
+    pub fn hash(&self, path: &Path) -> Result<u64> {
+        let fingerprint = fingerprint(path);
+        if let Some(fingerprint) = &fingerprint {
+            if let Some(hash) = self.lock().get(fingerprint) {
+                return Ok(*hash);
+            }
+        }
+
+        // don't hold the lock while reading, or the other thread waits on a big file
+        let hash = hash_contents(path, self.cipher.as_deref())?;
+        if let Some(fingerprint) = fingerprint {
+            self.lock().insert(fingerprint, hash);
+        }
+        Ok(hash)
+    }
+
+    /// Remembers `hash` for `path` as it is now, as if it had been read; for tests that need
+    /// two files to collide, or to tell a remembered hash from a fresh one.
+    #[cfg(test)]
+    pub fn insert(&self, path: &Path, hash: u64) {
+        if let Some(fingerprint) = fingerprint(path) {
+            self.lock().insert(fingerprint, hash);
+        }
+    }
+
+    /// Forgets files that have changed or gone since they were hashed.
+    pub fn prune(&self) {
+        self.lock()
+            .retain(|cached, _| fingerprint(&cached.0).as_ref() == Some(cached));
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Fingerprint, u64>> {
+        self.hashes.lock().expect("hash cache lock")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{set_age, write};
+    use std::time::Duration;
+
+    #[test]
+    fn rereads_a_file_once_its_size_or_mtime_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(dir.path(), "a.broguesave", "game");
+        let cache = HashCache::new(None);
+        let real = hash_contents(&path, None).unwrap();
+        assert_eq!(cache.hash(&path).unwrap(), real);
+
+        // a remembered hash is used for as long as the file looks the same
+        cache.insert(&path, 7);
+        assert_eq!(cache.hash(&path).unwrap(), 7);
+
+        set_age(&path, Duration::from_secs(30));
+        assert_eq!(cache.hash(&path).unwrap(), real);
+
+        cache.insert(&path, 7);
+        std::fs::write(&path, "longer game").unwrap();
+        set_age(&path, Duration::from_secs(30));
+        assert_eq!(
+            cache.hash(&path).unwrap(),
+            hash_contents(&path, None).unwrap()
+        );
+    }
+
+    #[test]
+    fn prune_forgets_files_that_have_changed_or_gone() {
+        let dir = tempfile::tempdir().unwrap();
+        let kept = write(dir.path(), "a.broguesave", "game");
+        let changed = write(dir.path(), "b.broguesave", "game");
+        let gone = write(dir.path(), "c.broguesave", "game");
+        let cache = HashCache::new(None);
+        for path in [&kept, &changed, &gone] {
+            cache.hash(path).unwrap();
+        }
+        set_age(&changed, Duration::from_secs(30));
+        std::fs::remove_file(&gone).unwrap();
+
+        cache.prune();
+        let cached: Vec<_> = cache
+            .lock()
+            .keys()
+            .map(|(path, _, _)| path.clone())
+            .collect();
+        assert_eq!(cached, [kept]);
+    }
+}
@@ -0,0 +1,42 @@
+use git2::{IndexAddOption, Repository, Signature};
+use std::path::Path;
+
+/// Commits everything in `backup_dir` if it's a git repo and anything has changed; returns
+/// whether a commit was made. Errors, including `backup_dir` not being a repo, are for the
+/// caller to log; none of them should stop syncing.
+pub fn commit(backup_dir: &Path, message: &str) -> Result<bool, git2::Error> {
+    let repo = Repository::open(backup_dir)?;
+
+    let mut index = repo.index()?;
+    index.add_all(["*"], IndexAddOption::DEFAULT, None)?;
+    // pick up deletions and archived saves too
+    index.update_all(["*"], None)?;
+    index.write()?;
+    let tree_id = index.write_tree()?;
+
+    let parent = match repo.head() {
+        Ok(head) => Some(head.peel_to_commit()?),
+        // a fresh repo with no commits yet
+        Err(_) => None,
+    };
+    if let Some(parent) = &parent {
+        if parent.tree_id() == tree_id {
+            return Ok(false);
+        }
+    }
+
+    let tree = repo.find_tree(tree_id)?;
+    let signature = repo
+        .signature()
+        .or_else(|_| Signature::now("backup-brogue", "backup-brogue@localhost"))?;
+    let parents: Vec<_> = parent.iter().collect();
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parents,
+    )?;
+    Ok(true)
+}
@@ -0,0 +1,303 @@
+use crate::config::Config;
+use crate::{AppError, Result};
+use crossterm::event::KeyCode;
+use std::collections::HashMap;
+use std::str::FromStr;
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter, EnumString};
+
+/// Something a key does in the save list. Named as in the `[keybindings]` section of the
+/// config, e.g. `quit = "q"`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Display, EnumString, EnumIter)]
+#[strum(serialize_all = "snake_case")]
+pub enum Action {
+    Quit,
+    Delete,
+    Down,
+    Up,
+    Expand,
+    Collapse,
+    CopySeed,
+    Export,
+    Play,
+    RestoreTo,
+    Rename,
+    Note,
+    Pin,
+    Diff,
+    Compare,
+    Follow,
+    Log,
+    LogBack,
+    LogForward,
+    Stats,
+    Compact,
+    FullPaths,
+    GoToSeed,
+    Search,
+    FlipSort,
+    CycleKind,
+    FilterEasy,
+    FilterNormal,
+    FilterWizard,
+    CycleMode,
+    ArchiveOldest,
+    Undo,
+    RetryRestores,
+    ResolveDiverged,
+    Approve,
+    Reject,
+}
+
+impl Action {
+    // today's keys; the delete key has always had its own setting
+    fn default_keys(self, config: &Config) -> Vec<KeyCode> {
+        let char = |c| vec![KeyCode::Char(c)];
+        match self {
+            Action::Quit => char('q'),
+            Action::Delete => char(config.delete_key),
+            Action::Down => vec![KeyCode::Char('j'), KeyCode::Down],
+            Action::Up => vec![KeyCode::Char('k'), KeyCode::Up],
+            Action::Expand => vec![KeyCode::Right],
+            Action::Collapse => vec![KeyCode::Left],
+            Action::CopySeed => char('s'),
+            Action::Export => char('e'),
+            Action::Play => char('x'),
+            Action::RestoreTo => char('r'),
+            Action::Rename => char('n'),
+            Action::Note => char('a'),
+            Action::Pin => char('*'),
+            Action::Diff => char('v'),
+            Action::Compare => char('m'),
+            Action::Follow => char('f'),
+            Action::Log => char('l'),
+            Action::LogBack => vec![KeyCode::PageUp],
+            Action::LogForward => vec![KeyCode::PageDown],
+            Action::Stats => char('t'),
+            Action::Compact => char('c'),
+            Action::FullPaths => char('P'),
+            Action::GoToSeed => char('#'),
+            Action::Search => char('/'),
+            Action::FlipSort => char('o'),
+            Action::CycleKind => vec![KeyCode::Tab],
+            Action::FilterEasy => char('1'),
+            Action::FilterNormal => char('2'),
+            Action::FilterWizard => char('3'),
+            Action::CycleMode => char('M'),
+            Action::ArchiveOldest => char('A'),
+            Action::Undo => char('u'),
+            Action::RetryRestores => char('R'),
+            Action::ResolveDiverged => char('K'),
+            Action::Approve => char('+'),
+            Action::Reject => char('-'),
+        }
+    }
+}
+
+/// Which key does what in the save list: the defaults, with any the config's `keybindings`
+/// sets in their place. ESC, ENTER and ctrl-c always keep their usual meanings.
+pub struct KeyMap {
+    actions: HashMap<KeyCode, Action>,
+    keys: HashMap<Action, Vec<KeyCode>>,
+}
+
+impl KeyMap {
+    /// Fails on a binding for an action we don't have, a key we can't read, or two actions
+    /// on the same key.
+    pub fn new(config: &Config) -> Result<KeyMap> {
+        let mut keys: HashMap<Action, Vec<KeyCode>> = Action::iter()
+            .map(|action| (action, action.default_keys(config)))
+            .collect();
+        for (name, bound) in &config.keybindings {
+            let action = Action::from_str(name)
+                .map_err(|_| AppError::InvalidKeyBinding(format!("no such action '{}'", name)))?;
+            let codes = bound
+                .split_whitespace()
+                .map(parse_key)
+                .collect::<Result<Vec<_>>>()?;
+            if codes.is_empty() {
+                return Err(AppError::InvalidKeyBinding(format!("no key for {}", name)));
+            }
+            keys.insert(action, codes);
+        }
+
+        let mut actions = HashMap::new();
+        // in a fixed order, so the same conflict is always reported the same way
+        for action in Action::iter() {
+            for code in &keys[&action] {
+                if let Some(other) = actions.insert(*code, action) {
+                    return Err(AppError::InvalidKeyBinding(format!(
+                        "{} is bound to both {} and {}",
+                        label(*code),
+                        other,
+                        action
+                    )));
+                }
+            }
+        }
+        Ok(KeyMap { actions, keys })
+    }
+
+    pub fn action(&self, code: KeyCode) -> Option<Action> {
+        self.actions.get(&code).copied()
+    }
+
+    /// The first key for `action`, as the help text shows it, e.g. `'q'` or `TAB`.
+    pub fn key(&self, action: Action) -> String {
+        self.keys
+            .get(&action)
+            .and_then(|codes| codes.first())
+            .map(|code| label(*code))
+            .unwrap_or_default()
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        KeyMap::new(&Config::default()).expect("the default keys don't conflict")
+    }
+}
+
+// a single character, or the name of a key like "tab" or "down"
+fn parse_key(s: &str) -> Result<KeyCode> {
+    let mut chars = s.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(KeyCode::Char(c));
+    }
+    let code = match s.to_lowercase().as_str() {
+        "space" => KeyCode::Char(' '),
+        "tab" => KeyCode::Tab,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "delete" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+            Some(n @ 1..=12) => KeyCode::F(n),
+            _ => return Err(AppError::InvalidKeyBinding(format!("unknown key '{}'", s))),
+        },
+    };
+    Ok(code)
+}
+
+fn label(code: KeyCode) -> String {
+    match code {
+        KeyCode::Char(' ') => "SPACE".to_string(),
+        KeyCode::Char(c) => format!("'{}'", c),
+        KeyCode::F(n) => format!("F{}", n),
+        KeyCode::Tab => "TAB".to_string(),
+        KeyCode::PageUp => "PAGEUP".to_string(),
+        KeyCode::PageDown => "PAGEDOWN".to_string(),
+        other => format!("{:?}", other).to_uppercase(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_delete_key_setting_moves_delete() {
+        let keymap = KeyMap::new(&Config {
+            delete_key: 'D',
+            ..Config::default()
+        })
+        .unwrap();
+        assert_eq!(keymap.action(KeyCode::Char('D')), Some(Action::Delete));
+        assert_eq!(keymap.action(KeyCode::Char('d')), None);
+        assert_eq!(keymap.key(Action::Delete), "'D'");
+    }
+
+    #[test]
+    fn a_delete_key_taken_by_another_action_is_an_error() {
+        let config = Config {
+            delete_key: 'q',
+            ..Config::default()
+        };
+        assert!(KeyMap::new(&config).is_err());
+    }
+
+    fn bindings(pairs: &[(&str, &str)]) -> Result<KeyMap> {
+        KeyMap::new(&Config {
+            keybindings: pairs
+                .iter()
+                .map(|(action, keys)| (action.to_string(), keys.to_string()))
+                .collect(),
+            ..Config::default()
+        })
+    }
+
+    fn error(pairs: &[(&str, &str)]) -> String {
+        match bindings(pairs) {
+            Err(AppError::InvalidKeyBinding(problem)) => problem,
+            Err(e) => panic!("unexpected error {}", e),
+            Ok(_) => panic!("{:?} should not be accepted", pairs),
+        }
+    }
+
+    #[test]
+    fn the_defaults_cover_every_action() {
+        let keymap = KeyMap::default();
+        for action in Action::iter() {
+            assert!(!keymap.key(action).is_empty(), "{}", action);
+        }
+        assert_eq!(keymap.action(KeyCode::Char('q')), Some(Action::Quit));
+        assert_eq!(keymap.action(KeyCode::Down), Some(Action::Down));
+    }
+
+    #[test]
+    fn a_binding_replaces_the_default_keys() {
+        let keymap = bindings(&[("down", "J end"), ("copy_seed", "F5")]).unwrap();
+        assert_eq!(keymap.action(KeyCode::Char('J')), Some(Action::Down));
+        assert_eq!(keymap.action(KeyCode::End), Some(Action::Down));
+        assert_eq!(keymap.action(KeyCode::Char('j')), None);
+        assert_eq!(keymap.action(KeyCode::Down), None);
+        assert_eq!(keymap.action(KeyCode::F(5)), Some(Action::CopySeed));
+        assert_eq!(keymap.key(Action::Down), "'J'");
+        assert_eq!(keymap.key(Action::CopySeed), "F5");
+    }
+
+    #[test]
+    fn reads_named_keys() {
+        assert_eq!(parse_key("x").unwrap(), KeyCode::Char('x'));
+        assert_eq!(parse_key("space").unwrap(), KeyCode::Char(' '));
+        assert_eq!(parse_key("Tab").unwrap(), KeyCode::Tab);
+        assert_eq!(parse_key("PAGEUP").unwrap(), KeyCode::PageUp);
+        assert_eq!(parse_key("f12").unwrap(), KeyCode::F(12));
+        assert!(parse_key("f13").is_err());
+        assert!(parse_key("f0").is_err());
+        assert!(parse_key("ctrl-x").is_err());
+    }
+
+    #[test]
+    fn rejects_bad_bindings() {
+        assert_eq!(error(&[("fly", "y")]), "no such action 'fly'");
+        assert_eq!(error(&[("undo", "   ")]), "no key for undo");
+        assert_eq!(error(&[("undo", "z shift")]), "unknown key 'shift'");
+    }
+
+    #[test]
+    fn rejects_two_actions_on_one_key() {
+        // a binding taking another action's default key
+        assert_eq!(
+            error(&[("pin", "s")]),
+            "'s' is bound to both copy_seed and pin"
+        );
+        // two bindings sharing a key
+        assert_eq!(
+            error(&[("approve", "y"), ("reject", "tab y")]),
+            "TAB is bound to both cycle_kind and reject"
+        );
+        assert_eq!(
+            error(&[("approve", "y"), ("reject", "y")]),
+            "'y' is bound to both approve and reject"
+        );
+        // moving the action that had the key out of the way
+        assert!(bindings(&[("pin", "s"), ("copy_seed", "S")]).is_ok());
+    }
+}
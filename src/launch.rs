@@ -0,0 +1,102 @@
+use log::{info, warn};
+use std::ffi::OsString;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// The program and arguments `template` calls for: split on whitespace, with `{path}` in any
+/// argument replaced by `path`. Without a `{path}`, the path goes on the end. `None` if
+/// `template` is empty.
+pub fn command_line(template: &str, path: &Path) -> Option<(String, Vec<OsString>)> {
+    let mut parts = template.split_whitespace();
+    let program = parts.next()?.to_string();
+    let mut substituted = false;
+    let mut args: Vec<OsString> = parts
+        .map(|part| match part.split_once("{path}") {
+            Some((before, after)) => {
+                substituted = true;
+                let mut arg = OsString::from(before);
+                arg.push(path);
+                arg.push(after);
+                arg
+            }
+            None => OsString::from(part),
+        })
+        .collect();
+    if !substituted {
+        args.push(path.into());
+    }
+    Some((program, args))
+}
+
+/// Starts the `play_cmd` on `path` and leaves it running; it gets no terminal, as brogue
+/// opens its own window. A thread waits for it so its exit ends up in the log.
+pub fn spawn(template: &str, path: &Path) -> std::io::Result<()> {
+    let (program, args) = match command_line(template, path) {
+        Some(command) => command,
+        None => return Ok(()),
+    };
+
+    info!("[PLAY] running {} on {}", program, path.display());
+    let mut child = Command::new(&program)
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    std::thread::spawn(move || match child.wait() {
+        Ok(status) if !status.success() => warn!("[PLAY] '{}' exited with {}", program, status),
+        Ok(_) => {}
+        Err(e) => warn!("[PLAY] could not wait for '{}': {}", program, e),
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(template: &str, path: &str) -> Option<(String, Vec<String>)> {
+        let (program, args) = command_line(template, Path::new(path))?;
+        let args = args
+            .into_iter()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        Some((program, args))
+    }
+
+    #[test]
+    fn fills_the_path_into_the_template() {
+        assert_eq!(
+            args("brogue --open {path}", "/saves/a game.broguesave"),
+            Some((
+                "brogue".to_string(),
+                vec!["--open".to_string(), "/saves/a game.broguesave".to_string()]
+            ))
+        );
+        assert_eq!(
+            args("brogue --open={path}!", "a.broguesave"),
+            Some((
+                "brogue".to_string(),
+                vec!["--open=a.broguesave!".to_string()]
+            ))
+        );
+    }
+
+    #[test]
+    fn puts_the_path_last_without_a_placeholder() {
+        assert_eq!(
+            args("  brogue   -v ", "a.broguesave"),
+            Some((
+                "brogue".to_string(),
+                vec!["-v".to_string(), "a.broguesave".to_string()]
+            ))
+        );
+    }
+
+    #[test]
+    fn an_empty_template_runs_nothing() {
+        assert_eq!(args("", "a.broguesave"), None);
+        assert_eq!(args("   ", "a.broguesave"), None);
+        assert!(spawn("", Path::new("a.broguesave")).is_ok());
+    }
+}
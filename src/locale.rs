@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+/// The languages the UI can format times and sizes in. Only the bits of text that carry
+/// numbers are localised; everything else is English.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Locale {
+    English,
+    German,
+    French,
+}
+
+impl Locale {
+    /// `setting` is a locale name like "de" or "fr_FR.UTF-8"; without one we go by the
+    /// usual environment variables. Anything we don't know is English.
+    pub fn resolve(setting: Option<&str>) -> Locale {
+        let name = match setting {
+            Some(name) => name.to_string(),
+            None => ["LC_ALL", "LC_MESSAGES", "LANG"]
+                .iter()
+                .filter_map(|var| std::env::var(var).ok())
+                .find(|value| !value.is_empty())
+                .unwrap_or_default(),
+        };
+        Locale::from_name(&name)
+    }
+
+    fn from_name(name: &str) -> Locale {
+        let language = name
+            .split(['_', '-', '.'])
+            .next()
+            .unwrap_or("")
+            .to_lowercase();
+        match language.as_str() {
+            "de" => Locale::German,
+            "fr" => Locale::French,
+            _ => Locale::English,
+        }
+    }
+
+    /// How long ago something happened, e.g. "5m ago", to the nearest whole unit.
+    pub fn format_relative(&self, elapsed: Duration) -> String {
+        let secs = elapsed.as_secs();
+        let (amount, unit) = match secs {
+            0..=59 => (secs, "s"),
+            60..=3599 => (secs / 60, "m"),
+            3600..=86399 => (secs / 3600, "h"),
+            _ => (secs / 86400, "d"),
+        };
+        match self {
+            Locale::English => format!("{}{} ago", amount, unit),
+            Locale::German => {
+                let unit = if unit == "d" { "T" } else { unit };
+                format!("vor {} {}", amount, unit)
+            }
+            Locale::French => {
+                let unit = if unit == "d" { "j" } else { unit };
+                format!("il y a {} {}", amount, unit)
+            }
+        }
+    }
+
+    /// A byte count in the biggest unit that keeps it at or above one, e.g. "1.5 MB".
+    pub fn format_size(&self, bytes: u64) -> String {
+        let units: [&str; 4] = match self {
+            Locale::French => ["o", "Ko", "Mo", "Go"],
+            _ => ["B", "KB", "MB", "GB"],
+        };
+        let mut size = bytes as f64;
+        let mut unit = 0;
+        while size >= 1024.0 && unit < units.len() - 1 {
+            size /= 1024.0;
+            unit += 1;
+        }
+        if unit == 0 {
+            format!("{} {}", bytes, units[0])
+        } else {
+            format!("{} {}", self.decimal(size), units[unit])
+        }
+    }
+
+    /// `n` to one decimal place, with this locale's decimal separator.
+    fn decimal(&self, n: f64) -> String {
+        let text = format!("{:.1}", n);
+        match self {
+            Locale::English => text,
+            Locale::German | Locale::French => text.replace('.', ","),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looks_up_the_language_of_a_locale_name() {
+        let cases = [
+            ("de", Locale::German),
+            ("de_AT.UTF-8", Locale::German),
+            ("fr-CA", Locale::French),
+            ("FR_fr", Locale::French),
+            ("en_GB.UTF-8", Locale::English),
+            // anything we don't know is English
+            ("ja_JP", Locale::English),
+            ("C", Locale::English),
+            ("", Locale::English),
+        ];
+        for (name, locale) in cases {
+            assert_eq!(Locale::resolve(Some(name)), locale, "{}", name);
+        }
+    }
+
+    #[test]
+    fn formats_relative_times_in_each_language() {
+        let minutes = Duration::from_secs(5 * 60);
+        let days = Duration::from_secs(2 * 86400);
+        assert_eq!(Locale::English.format_relative(minutes), "5m ago");
+        assert_eq!(Locale::English.format_relative(days), "2d ago");
+        assert_eq!(Locale::German.format_relative(minutes), "vor 5 m");
+        assert_eq!(Locale::German.format_relative(days), "vor 2 T");
+        assert_eq!(Locale::French.format_relative(days), "il y a 2 j");
+    }
+
+    #[test]
+    fn formats_sizes_with_the_local_units_and_decimal_separator() {
+        assert_eq!(Locale::English.format_size(512), "512 B");
+        assert_eq!(Locale::English.format_size(1536 * 1024), "1.5 MB");
+        assert_eq!(Locale::German.format_size(1536 * 1024), "1,5 MB");
+        assert_eq!(Locale::French.format_size(1536), "1,5 Ko");
+    }
+}
@@ -0,0 +1,64 @@
+use crate::Result;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+pub const LONG_NAMES_FILE: &str = "long-names.toml";
+
+/// The full names of saves whose names were too long to back up as-is, by the shortened name
+/// they're backed up under. Kept in `long-names.toml` in the backup dir, so a save restored
+/// from its backup gets its own name back rather than the shortened one.
+pub struct LongNames {
+    path: PathBuf,
+    names: BTreeMap<String, String>,
+}
+
+impl LongNames {
+    /// Reads the names in `backup_dir`; a missing file means there are none.
+    pub fn load(backup_dir: &Path) -> Result<LongNames> {
+        let path = backup_dir.join(LONG_NAMES_FILE);
+        let names = if path.exists() {
+            toml::from_str(&std::fs::read_to_string(&path)?)?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(LongNames { path, names })
+    }
+
+    /// The full name of the save backed up as `key`, if it was shortened.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.names.get(key).map(String::as_str)
+    }
+
+    /// Remembers that `full` is backed up as `key`, saving the change. Names that weren't
+    /// shortened aren't kept. Returns whether it's new.
+    pub fn record(&mut self, key: &str, full: &str) -> Result<bool> {
+        if key == full || self.get(key) == Some(full) {
+            return Ok(false);
+        }
+        self.names.insert(key.to_string(), full.to_string());
+        std::fs::write(&self.path, toml::to_string(&self.names)?)?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remembers_shortened_names_across_loads() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut names = LongNames::load(dir.path()).unwrap();
+        names
+            .record("short~1.broguesave", "a long name.broguesave")
+            .unwrap();
+        names.record("same.broguesave", "same.broguesave").unwrap();
+
+        let names = LongNames::load(dir.path()).unwrap();
+        assert_eq!(
+            names.get("short~1.broguesave"),
+            Some("a long name.broguesave")
+        );
+        assert_eq!(names.get("same.broguesave"), None);
+    }
+}
@@ -1,27 +1,186 @@
+mod activity;
+mod archive;
 mod backup;
+mod cache;
+mod clipboard;
+mod config;
+mod crypt;
+mod disk;
+mod doctor;
+mod extras;
+#[cfg(feature = "simulate-failure")]
+mod fault;
+mod hashes;
+mod history;
+mod keymap;
+mod launch;
+mod locale;
+mod long_names;
+mod manifest;
+mod metadata;
+mod notes;
+mod notification;
+mod paths;
+mod pins;
+mod plan;
+mod setup;
+mod snapshots;
+mod steam;
+mod tail;
+#[cfg(test)]
+mod testing;
+mod throttle;
+mod ui;
+mod verify;
+mod versions;
+mod watch;
 
 use crate::backup::*;
+use crate::config::{Config, Source};
+use crate::crypt::Cipher;
+use crate::keymap::Action;
+use crate::metadata::Difficulty;
+use crate::ui::ui;
+use clap::Parser;
 use crossterm::event::Event;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, KeyCode},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, KeyCode, KeyEvent, KeyModifiers,
+        MouseButton, MouseEvent, MouseEventKind,
+    },
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{
+        self, disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+    },
 };
-use std::io;
-use std::path::PathBuf;
+use fern::colors::ColoredLevelConfig;
+use std::collections::BTreeSet;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use std::time::{Duration, Instant};
 use thiserror::Error;
-use tui::{
-    backend::{Backend, CrosstermBackend},
-    layout::{Alignment, Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
-    text::{Span, Spans},
-    widgets::{Block, Borders, Paragraph, Wrap},
-    Frame, Terminal,
-};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
+use tui::{backend::CrosstermBackend, layout::Rect, Terminal};
 
 const BROGUE_SAVE_DIR: &str = "Library/Application Support/Brogue/Brogue CE";
-const LOCAL_BACKUP_DIR: &str = ".brogue";
+
+#[derive(Parser)]
+#[command(
+    about = "watches for suspended brogue games then backs them up for later loading, even after death"
+)]
+struct Opts {
+    /// where brogue keeps its saves; overrides BROGUE_SAVE_DIR, which overrides 'save_dir' in
+    /// the config
+    #[arg(long, value_name = "DIR")]
+    save_dir: Option<PathBuf>,
+
+    /// where to keep the backups; overrides BROGUE_BACKUP_DIR, which overrides the usual place
+    #[arg(long, value_name = "DIR")]
+    backup_dir: Option<PathBuf>,
+
+    /// what to do when a backed-up save disappears: 'mirror' restores it, 'archive' moves the backup into 'archive/', 'backup-only' leaves it be [default: mirror]
+    #[arg(long)]
+    mode: Option<SyncMode>,
+
+    /// copy saves from an existing backup folder into the backup dir, then exit
+    #[arg(long, value_name = "OLD_DIR")]
+    migrate: Option<PathBuf>,
+
+    /// pack the whole backup dir into a single .tar.gz, e.g. to keep a copy offsite, then exit
+    #[arg(long, value_name = "OUT")]
+    archive: Option<PathBuf>,
+
+    /// unpack a .tar.gz made by '--archive' into the backup dir, then exit
+    #[arg(long, value_name = "IN")]
+    restore_archive: Option<PathBuf>,
+
+    /// keep a separate set of backups, with its own config, in a 'profiles/<NAME>' dir
+    /// alongside the usual backups, e.g. for competition seeds; the save dir is shared
+    #[arg(long, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// make backups that match their saves hard links to them, to save space; see 'hardlink'
+    /// in the config
+    #[arg(long)]
+    hardlink: bool,
+
+    /// don't sync anything without asking; see 'manual' in the config
+    #[arg(long)]
+    manual: bool,
+
+    /// disable deleting saves from the UI
+    #[arg(long)]
+    no_delete: bool,
+
+    /// keep the most recently modified save selected
+    #[arg(long)]
+    follow: bool,
+
+    /// don't take over the screen; show a one-line status and print the log above it
+    #[arg(long)]
+    inline: bool,
+
+    /// show nothing but a single line of counts, updated in place, e.g. for capturing in
+    /// streaming software; every key but 'q' is ignored
+    #[arg(long)]
+    minimal: bool,
+
+    /// watch the save dir by polling, for network or cloud-synced folders where change events
+    /// don't arrive
+    #[arg(long)]
+    force_poll: bool,
+
+    /// check the save dir, backup dir, config and so on are all usable, then exit
+    #[arg(long)]
+    doctor: bool,
+
+    /// print the settings in effect and where each came from, then exit
+    #[arg(long)]
+    print_config: bool,
+
+    /// print what the next sync would do, and which saves have come and gone since last
+    /// session, then exit without changing anything
+    #[arg(long)]
+    plan: bool,
+
+    /// print the `--plan` as JSON
+    #[arg(long, requires = "plan")]
+    json: bool,
+
+    /// before starting, restore every save that's only backed up into the save dir, whatever
+    /// the sync mode; for getting brogue going on a new machine
+    #[arg(long)]
+    prime_saves: bool,
+
+    /// start syncing straight away instead of showing what the first sync will do
+    #[arg(long)]
+    yes: bool,
+
+    /// format times and sizes for this locale, e.g. 'de' or 'fr_FR' [default: from LANG]
+    #[arg(long)]
+    locale: Option<String>,
+
+    /// copy the settings files backed up by 'extra_files' back into the save dir, then exit
+    #[arg(long)]
+    restore_config: bool,
+
+    /// log more detail, like how long each scan of the save and backup dirs takes; set
+    /// RUST_LOG=trace instead for a timed tree of everything each sync does
+    #[arg(long)]
+    verbose: bool,
+
+    /// write the timed tree of everything each sync does here rather than to the log file;
+    /// RUST_LOG filters it, e.g. 'backup_brogue::backup=trace' [default: everything of ours]
+    #[arg(long)]
+    trace_file: Option<PathBuf>,
+
+    /// make this fraction of copies fail, from 0 to 1, to see how the app copes
+    #[cfg(feature = "simulate-failure")]
+    #[arg(long, hide = true)]
+    simulate_failure: Option<f64>,
+}
 
 type Result<T> = std::result::Result<T, AppError>;
 
@@ -31,9 +190,37 @@ pub enum AppError {
     NoHomeDir,
     #[error("missing save dir: {0}")]
     MissingDir(PathBuf),
+    #[error("invalid config: {0}")]
+    ConfigError(#[from] toml::de::Error),
+    #[error("could not write config: {0}")]
+    ConfigWriteError(#[from] toml::ser::Error),
+    #[error("could not write the state cache: {0}")]
+    CacheError(#[from] bincode::Error),
+    #[error("copy of {0} doesn't match the original")]
+    CopyMismatch(PathBuf),
+    #[error("no intact backup of {0}")]
+    NoIntactBackup(String),
+    #[error("the save dir already has a newer {0}; not restoring the older backup")]
+    NewerLiveSave(String),
+    #[error("there's already a save called {0}")]
+    NameTaken(String),
+    #[error("a save's comment can't contain {0:?}")]
+    InvalidComment(char),
+    #[error("{0} is encrypted but no key is set")]
+    NoEncryptionKey(PathBuf),
+    #[error("could not decrypt {0}; wrong key or damaged file")]
+    DecryptionFailed(PathBuf),
+    #[error("encryption failed: {0}")]
+    EncryptionError(String),
+    #[error("can't use '{0}' as a profile name")]
+    InvalidProfile(String),
+    #[error("invalid key binding: {0}")]
+    InvalidKeyBinding(String),
+    #[error("no clipboard tool found")]
+    ClipboardUnavailable,
     #[error("notify error")]
     NotifyError(#[from] notify::Error),
-    #[error("IO error")]
+    #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
     #[error("unknown error")]
     Unknown,
@@ -46,16 +233,248 @@ pub enum AppError {
 // When it disappears from the save dir, but exists in the backup dir, copy it over
 #[tokio::main(flavor = "multi_thread")]
 async fn main() -> Result<()> {
-    //setup_logger().expect("Could not set up logger");
+    let opts = Opts::parse();
+
+    if opts.doctor {
+        let ok = doctor::run(
+            BROGUE_SAVE_DIR,
+            opts.save_dir.as_deref(),
+            opts.backup_dir.as_deref(),
+        );
+        std::process::exit(if ok { 0 } else { 1 });
+    }
 
     let user_home = dirs::home_dir().ok_or(AppError::NoHomeDir)?;
-    let save_dir = user_home.join(BROGUE_SAVE_DIR);
-    let backup_dir = user_home.join(LOCAL_BACKUP_DIR);
+    let mut paths = paths::resolve(&user_home);
+    let backup_dir_source = paths::choose_dir(
+        &user_home,
+        opts.backup_dir.as_deref(),
+        std::env::var_os(paths::BACKUP_DIR_VAR),
+        None,
+    )
+    .map(|(dir, source)| {
+        paths.backup_dir = dir;
+        source
+    });
+    if let Some(name) = &opts.profile {
+        let main_config = paths.config_file.clone();
+        paths = paths::for_profile(&paths, name)
+            .ok_or_else(|| AppError::InvalidProfile(name.clone()))?;
+        // until a profile has a config of its own, it goes by the usual one
+        if !paths.config_file.exists() {
+            paths.config_file = main_config;
+        }
+    }
+    let backup_dir = paths.backup_dir;
+
+    // nothing of ours exists yet, so ask where the saves are rather than guess
+    let first_run = !paths.config_file.exists() && !backup_dir.exists();
+    // one-shot commands are scriptable, so they never stop to ask
+    let one_shot = opts.migrate.is_some()
+        || opts.archive.is_some()
+        || opts.restore_archive.is_some()
+        || opts.restore_config;
+    let interactive = !opts.yes && !opts.print_config && !opts.plan && !one_shot;
+    let given_save_dir = paths::choose_dir(
+        &user_home,
+        opts.save_dir.as_deref(),
+        std::env::var_os(paths::SAVE_DIR_VAR),
+        None,
+    );
+    if first_run && interactive && given_save_dir.is_none() && io::stdin().is_terminal() {
+        let default_save_dir =
+            steam::default_save_dir(&user_home, user_home.join(BROGUE_SAVE_DIR), None);
+        if setup::run(&default_save_dir, &backup_dir, &paths.config_file)?.is_none() {
+            return Ok(());
+        }
+    }
 
     if !backup_dir.exists() {
         std::fs::create_dir_all(&backup_dir)?;
     }
 
+    let mut config = Config::load(&paths.config_file)?;
+    let (save_dir, save_dir_source) = given_save_dir
+        .or_else(|| paths::choose_dir(&user_home, None, None, config.save_dir.as_deref()))
+        .unwrap_or_else(|| {
+            let standalone = user_home.join(BROGUE_SAVE_DIR);
+            let dir = steam::default_save_dir(&user_home, standalone, config.steam_dir.as_deref());
+            (dir, Source::Default)
+        });
+    let mut flags = vec![];
+    if let Some(mode) = opts.mode {
+        config.mode = mode;
+        flags.push("mode");
+    }
+    if opts.no_delete {
+        config.allow_delete = false;
+        flags.push("allow_delete");
+    }
+    if opts.follow {
+        config.follow = true;
+        flags.push("follow");
+    }
+    if opts.force_poll {
+        config.force_poll = true;
+        flags.push("force_poll");
+    }
+    if opts.hardlink {
+        config.hardlink = true;
+        flags.push("hardlink");
+    }
+    if opts.manual {
+        config.manual = true;
+        flags.push("manual");
+    }
+    if let Some(locale) = opts.locale {
+        config.locale = Some(locale);
+        flags.push("locale");
+    }
+
+    if opts.print_config {
+        let file_keys = Config::keys_in_file(&paths.config_file)?;
+        print_config(
+            (&save_dir, save_dir_source),
+            (&backup_dir, backup_dir_source),
+            paths.legacy,
+            &paths.config_file,
+            &config,
+            &file_keys,
+            &flags,
+        )?;
+        return Ok(());
+    }
+
+    if opts.plan {
+        let cipher = Cipher::load(&config)?;
+        let pins = pins::Pins::load(&backup_dir)?;
+        let notes = notes::Notes::load(&backup_dir)?;
+        let long_names = long_names::LongNames::load(&backup_dir)?;
+        let last_session = manifest::load(&backup_dir)?;
+        let mut app = App::new(
+            save_dir, backup_dir, config, cipher, pins, notes, long_names,
+        );
+        app.update_state()?;
+        let last_session: Option<BTreeSet<String>> =
+            last_session.map(|manifest| manifest.into_keys().collect());
+        let report = plan::Report::new(&app.tracked_saves(), last_session.as_ref(), app.plan());
+        if opts.json {
+            print!("{}", report.to_json());
+        } else {
+            print!("{}", report.to_text());
+        }
+        return Ok(());
+    }
+
+    // a bad binding should stop us before the terminal is taken over
+    let keymap = keymap::KeyMap::new(&config)?;
+
+    setup_logger(&config.log_file, opts.inline, opts.verbose).expect("Could not set up logger");
+    setup_tracing(&config.log_file, opts.trace_file.as_deref()).expect("Could not set up tracing");
+    #[cfg(feature = "simulate-failure")]
+    if let Some(rate) = opts.simulate_failure {
+        log::warn!("simulating failure of {}% of copies", rate * 100.0);
+        fault::set_rate(rate);
+    }
+    log::info!("backup-brogue - watches for suspended games then backs them up for later loading, even after death");
+
+    repair(&backup_dir)?;
+
+    if let Some(old_dir) = &opts.migrate {
+        if !old_dir.exists() {
+            return Err(AppError::MissingDir(old_dir.clone()));
+        }
+        let cipher = Cipher::load(&config)?;
+        let summary = migrate(old_dir, &backup_dir, cipher.as_ref())?;
+        println!("{}", summary);
+        return Ok(());
+    }
+
+    if let Some(out) = &opts.archive {
+        let count = archive::create(&backup_dir, out)?;
+        println!("archived {} files to {}", count, out.display());
+        return Ok(());
+    }
+
+    if let Some(archive) = &opts.restore_archive {
+        archive::restore(archive, &backup_dir)?;
+        repair(&backup_dir)?;
+        println!(
+            "restored {} into {}",
+            archive.display(),
+            backup_dir.display()
+        );
+        return Ok(());
+    }
+
+    if opts.restore_config {
+        if !save_dir.exists() {
+            return Err(AppError::MissingDir(save_dir));
+        }
+        let restored = extras::restore(&backup_dir, &save_dir)?;
+        println!("restored {} settings files", restored.len());
+        for path in restored {
+            println!("  {}", path.display());
+        }
+        return Ok(());
+    }
+
+    let render_interval = Duration::from_millis(config.render_interval_ms);
+    let reconcile_interval = Duration::from_millis(config.reconcile_interval_ms);
+    let cipher = Cipher::load(&config)?;
+    if cipher.is_some() {
+        log::info!("encrypting new backups");
+    }
+    let pins = pins::Pins::load(&backup_dir)?;
+    let notes = notes::Notes::load(&backup_dir)?;
+    let long_names = long_names::LongNames::load(&backup_dir)?;
+    let last_session = manifest::load(&backup_dir)?;
+    let mut app = App::new(
+        save_dir, backup_dir, config, cipher, pins, notes, long_names,
+    );
+    app.keymap = keymap;
+    app.profile = opts.profile;
+    app.show_cached_state();
+    if opts.prime_saves {
+        let restored = app.prime_saves()?;
+        log::info!("primed the save dir with {} saves", restored);
+    }
+    // in manual mode every action is reviewed anyway; and there's no confirming a preview with
+    // every key ignored
+    if app.config().manual {
+        app.start_manual();
+    } else if !opts.yes && !opts.minimal {
+        app.start_preview();
+    }
+    if let Some(last_session) = last_session {
+        app.recall_last_session(last_session);
+    }
+
+    if opts.minimal {
+        app.minimal = true;
+        enable_raw_mode()?;
+        let mut stdout = io::stdout();
+        let result = run_app(&mut app, render_interval, reconcile_interval, |app| {
+            Ok(ui::draw_minimal(&mut stdout, app)?)
+        });
+        let recorded = app.record_session();
+        disable_raw_mode()?;
+        println!();
+        return result.and(recorded);
+    }
+
+    if opts.inline {
+        enable_raw_mode()?;
+        let mut stdout = io::stdout();
+        let result = run_app(&mut app, render_interval, reconcile_interval, |app| {
+            Ok(ui::draw_inline(&mut stdout, app)?)
+        });
+        let recorded = app.record_session();
+        disable_raw_mode()?;
+        println!();
+        return result.and(recorded);
+    }
+
     // setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -63,10 +482,13 @@ async fn main() -> Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let app = App::new(save_dir, backup_dir);
-
-    run_app(&mut terminal, app, Duration::from_millis(250))?;
+    let result = run_app(&mut app, render_interval, reconcile_interval, |app| {
+        terminal.draw(|f| ui(f, app))?;
+        Ok(())
+    });
 
+    // recorded before restoring the terminal, which can fail too, so the session isn't lost
+    let recorded = app.record_session();
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
@@ -74,111 +496,438 @@ async fn main() -> Result<()> {
         DisableMouseCapture
     )?;
     terminal.show_cursor()?;
+    result.and(recorded)
+}
+
+fn print_config(
+    (save_dir, save_source): (&Path, Source),
+    (backup_dir, backup_source): (&Path, Option<Source>),
+    legacy: bool,
+    config_file: &Path,
+    config: &Config,
+    file_keys: &BTreeSet<String>,
+    flags: &[&str],
+) -> Result<()> {
+    let config_source = if config_file.exists() {
+        "found"
+    } else {
+        "not found; using defaults"
+    };
+    let backup_source = match backup_source {
+        Some(source) => source.to_string(),
+        None if legacy => "legacy ~/.brogue".to_string(),
+        None => "platform default".to_string(),
+    };
+    let mut rows = vec![
+        (
+            "save_dir".to_string(),
+            save_dir.display().to_string(),
+            save_source.to_string(),
+        ),
+        (
+            "backup_dir".to_string(),
+            backup_dir.display().to_string(),
+            backup_source,
+        ),
+        (
+            "config_file".to_string(),
+            config_file.display().to_string(),
+            config_source.to_string(),
+        ),
+    ];
+    for (key, value, source) in config.describe(file_keys, flags)? {
+        // already shown above, with the default filled in
+        if key == "save_dir" {
+            continue;
+        }
+        rows.push((key, value, source.to_string()));
+    }
+
+    let width = rows.iter().map(|(key, _, _)| key.len()).max().unwrap_or(0);
+    for (key, value, source) in rows {
+        println!("{:width$}  {}  ({})", key, value, source, width = width);
+    }
+    Ok(())
+}
+
+/// Logs to `log_file`; with `inline` set, also echoes to stdout above the status line. With
+/// `verbose` set, our own debug lines go to the log file too. `RUST_LOG` set to a plain level,
+/// like `debug`, picks how much of our own logging goes to the file instead.
+fn setup_logger(
+    log_file: &Path,
+    inline: bool,
+    verbose: bool,
+) -> std::result::Result<(), fern::InitError> {
+    let colors = ColoredLevelConfig::new()
+        .info(fern::colors::Color::Green)
+        .warn(fern::colors::Color::Yellow)
+        .error(fern::colors::Color::Red);
+
+    let mut dispatch = fern::Dispatch::new()
+        .format(move |out, message, record| {
+            out.finish(format_args!(
+                "[{}][{}] {}",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+                colors.color(record.level()),
+                message
+            ))
+        })
+        .level(log::LevelFilter::Info)
+        .chain(fern::log_file(log_file)?);
+    let level = std::env::var("RUST_LOG")
+        .ok()
+        .and_then(|level| level.parse::<log::LevelFilter>().ok());
+    if let Some(level) = level {
+        dispatch = dispatch.level_for(env!("CARGO_CRATE_NAME"), level);
+    } else if verbose {
+        dispatch = dispatch.level_for(env!("CARGO_CRATE_NAME"), log::LevelFilter::Debug);
+    }
+    if inline {
+        // the terminal is in raw mode, so wipe the status line first and end lines with
+        // "\r\n"; the next redraw puts the status line back underneath
+        dispatch = dispatch.chain(
+            fern::Dispatch::new()
+                // debug lines every tick would drown out everything else
+                .level(log::LevelFilter::Info)
+                .format(|out, message, _| out.finish(format_args!("\r\x1b[2K{}", message)))
+                .chain(fern::Output::stdout("\r\n")),
+        );
+    }
+    dispatch.apply()?;
+    Ok(())
+}
+
+/// Writes the spans around each tick's work, and how long each took, to `trace_file`, or to
+/// the log file when only `RUST_LOG` is set. `RUST_LOG` takes `EnvFilter` directives, like
+/// `trace` or `backup_brogue::backup=trace`; with a trace file and no `RUST_LOG`, all of ours
+/// are written. With neither, nothing is traced.
+fn setup_tracing(log_file: &Path, trace_file: Option<&Path>) -> io::Result<()> {
+    let filter = match std::env::var(EnvFilter::DEFAULT_ENV) {
+        Ok(directives) => EnvFilter::builder().parse_lossy(directives),
+        Err(_) if trace_file.is_some() => {
+            EnvFilter::new(format!("{}=trace", env!("CARGO_CRATE_NAME")))
+        }
+        Err(_) => return Ok(()),
+    };
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(trace_file.unwrap_or(log_file))?;
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(Mutex::new(file))
+        .with_ansi(false)
+        .with_span_events(FmtSpan::CLOSE)
+        .init();
     Ok(())
 }
 
-fn run_app<B: Backend>(
-    terminal: &mut Terminal<B>,
-    mut app: App,
-    tick_rate: Duration,
+/// Something that should happen every `period`.
+struct Interval {
+    period: Duration,
+    last: Instant,
+}
+
+impl Interval {
+    fn new(period: Duration, now: Instant) -> Interval {
+        Interval { period, last: now }
+    }
+
+    fn remaining(&self, now: Instant) -> Duration {
+        self.period
+            .checked_sub(now.duration_since(self.last))
+            .unwrap_or_else(|| Duration::from_secs(0))
+    }
+
+    fn is_due(&self, now: Instant) -> bool {
+        self.remaining(now) == Duration::from_secs(0)
+    }
+
+    fn reset(&mut self, now: Instant) {
+        self.last = now;
+    }
+}
+
+/// Works out how far a held-down key should scroll. Terminals only send repeated presses, so a
+/// press soon after the last one of the same key counts as holding it, and the longer it's
+/// held the further each press goes.
+struct Repeat {
+    // the key being held, when it went down, and when it last repeated
+    held: Option<(KeyCode, Instant, Instant)>,
+}
+
+impl Repeat {
+    fn new() -> Repeat {
+        Repeat { held: None }
+    }
+
+    fn step(&mut self, code: KeyCode, now: Instant, config: &Config) -> usize {
+        let repeat = Duration::from_millis(config.key_repeat_ms);
+        let since = match self.held {
+            Some((held, since, last)) if held == code && now - last < repeat => since,
+            _ => now,
+        };
+        self.held = Some((code, since, now));
+
+        let accel = config.scroll_accel_ms.max(1) as u128;
+        let step = 1 + ((now - since).as_millis() / accel) as usize;
+        step.min(config.scroll_max_step.max(1))
+    }
+}
+
+/// Redraws every `render_interval` (and after every key press), but only scans and syncs the
+/// dirs every `reconcile_interval`, so the UI can be snappy without hammering the disk.
+fn run_app(
+    app: &mut App,
+    render_interval: Duration,
+    reconcile_interval: Duration,
+    mut draw: impl FnMut(&App) -> Result<()>,
 ) -> Result<()> {
-    let mut last_tick = Instant::now();
+    let now = Instant::now();
+    let mut render = Interval::new(render_interval, now);
+    let mut reconcile = Interval::new(reconcile_interval, now);
+    let mut repeat = Repeat::new();
+    // show last session's saves, and handle keys, while the first scan runs
+    app.scan_in_background();
 
     loop {
-        app.update_state()?;
-
-        terminal.draw(|f| ui(f, &app))?;
+        draw(app)?;
+        render.reset(Instant::now());
 
-        let timeout = tick_rate
-            .checked_sub(last_tick.elapsed())
-            .unwrap_or_else(|| Duration::from_secs(0));
+        let now = Instant::now();
+        // while catching up, only stop to handle keys already waiting
+        let catching_up = app.is_catching_up() && !app.is_previewing();
+        let timeout = if catching_up {
+            Duration::ZERO
+        } else {
+            render.remaining(now).min(reconcile.remaining(now))
+        };
         if crossterm::event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Char('q') => {
-                        return Ok(());
-                    }
-                    KeyCode::Esc => {
-                        app.delete_state = DeleteState::NotDeleting;
-                    }
-                    KeyCode::Char('d') => {
-                        app.delete_state = DeleteState::AwaitingIndex;
-                    }
-                    KeyCode::Char(c) => {
-                        if app.delete_state == DeleteState::AwaitingIndex && c.is_ascii_alphabetic()
-                        {
-                            let idx = ((c as u8) - b'a') as usize;
-                            app.delete_state = DeleteState::Delete(idx);
+            match event::read()? {
+                // raw mode turns ctrl-c into a key press rather than a signal, so it gets the same
+                // clean shutdown as 'q' from anywhere, prompts included
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('c'),
+                    modifiers,
+                }) if modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+                Event::Key(key) => {
+                    if app.minimal {
+                        if app.keymap.action(key.code) == Some(Action::Quit) {
+                            return Ok(());
+                        }
+                    } else if app.since_last_launch.is_some() {
+                        app.since_last_launch = None;
+                    } else if app.show_stats {
+                        app.show_stats = false;
+                    } else if app.compared_saves().is_some() {
+                        match key.code {
+                            KeyCode::Char('a') => app.delete_compared(true),
+                            KeyCode::Char('b') => app.delete_compared(false),
+                            _ => app.compare = Compare::Off,
+                        }
+                    } else if app.is_previewing() {
+                        match key.code {
+                            KeyCode::Enter | KeyCode::Char('y') => app.confirm_preview(),
+                            code if app.keymap.action(code) == Some(Action::Quit) => return Ok(()),
+                            _ => {}
+                        }
+                    } else if app.prompt.is_some() {
+                        match key.code {
+                            KeyCode::Esc => app.prompt = None,
+                            KeyCode::Enter => app.submit_prompt(),
+                            KeyCode::Backspace => app.prompt_backspace(),
+                            KeyCode::Char(c) => app.prompt_char(c),
+                            _ => {}
+                        }
+                    } else {
+                        match key.code {
+                            code if matches!(app.delete_state, DeleteState::ConfirmLastCopy(_)) => {
+                                app.confirm_delete(code == KeyCode::Char('y'))
+                            }
+                            KeyCode::Char(c)
+                                if app.delete_state == DeleteState::AwaitingIndex
+                                    && c.is_ascii_lowercase() =>
+                            {
+                                if let Some(idx) = ui::letter_index(c) {
+                                    app.choose_delete(idx);
+                                }
+                            }
+                            KeyCode::Enter if app.delete_state == DeleteState::AwaitingIndex => {
+                                app.choose_selected_delete()
+                            }
+                            KeyCode::Enter => app.toggle_expanded(),
+                            KeyCode::Esc => {
+                                app.delete_state = DeleteState::NotDeleting;
+                                app.compare = Compare::Off;
+                                app.diff = None;
+                                app.search.clear();
+                                app.hide_log();
+                            }
+                            code => match app.keymap.action(code) {
+                                Some(Action::Quit) => return Ok(()),
+                                Some(action) => do_action(app, action, code, &mut repeat),
+                                None => {}
+                            },
                         }
                     }
-                    _ => {}
                 }
+                Event::Mouse(MouseEvent {
+                    kind: MouseEventKind::Down(MouseButton::Left),
+                    column,
+                    row,
+                    ..
+                }) if app.prompt.is_none() && !app.minimal => {
+                    let (width, height) = terminal::size()?;
+                    let size = Rect::new(0, 0, width, height);
+                    if let Some(row) = ui::row_at(size, app, column, row) {
+                        app.select_row(row);
+                    }
+                }
+                _ => {}
             }
         }
 
-        if last_tick.elapsed() >= tick_rate {
-            app.on_tick();
-            last_tick = Instant::now();
+        let now = Instant::now();
+        if reconcile.is_due(now) || app.files_changed() || catching_up || app.is_scanning() {
+            if app.scan() {
+                app.on_tick();
+            }
+            reconcile.reset(now);
         }
+        app.next_sync = Some(now + reconcile.remaining(now));
     }
 }
 
-fn letter(idx: usize) -> char {
-    (b'a' + idx as u8) as char
+fn do_action(app: &mut App, action: Action, code: KeyCode, repeat: &mut Repeat) {
+    match action {
+        Action::Quit => {}
+        Action::Delete => app.start_delete(),
+        Action::Down => {
+            for _ in 0..repeat.step(code, Instant::now(), app.config()) {
+                app.select_next();
+            }
+        }
+        Action::Up => {
+            for _ in 0..repeat.step(code, Instant::now(), app.config()) {
+                app.select_previous();
+            }
+        }
+        Action::Expand => app.expand(),
+        Action::Collapse => app.collapse(),
+        Action::CopySeed => copy_seed(app),
+        Action::Export => app.start_export(),
+        Action::Play => play(app),
+        Action::RestoreTo => app.start_restore_to(),
+        Action::Rename => app.start_rename(),
+        Action::Note => app.start_note(),
+        Action::Pin => app.toggle_pin(),
+        Action::Diff => app.toggle_diff(),
+        Action::Compare => app.pick_for_compare(),
+        Action::Follow => app.toggle_follow(),
+        Action::Log => app.toggle_log(),
+        Action::LogBack => app.scroll_log(true),
+        Action::LogForward => app.scroll_log(false),
+        Action::RetryRestores => app.retry_restores(),
+        Action::ResolveDiverged => app.start_resolve_diverged(),
+        Action::Stats => app.show_stats = true,
+        Action::Compact => app.compact = !app.compact,
+        Action::FullPaths => app.full_paths = !app.full_paths,
+        Action::GoToSeed => app.start_go_to_seed(),
+        Action::Search => app.start_search(),
+        Action::FlipSort => app.toggle_sort_direction(),
+        Action::CycleKind => app.cycle_kind_filter(),
+        Action::FilterEasy => app.toggle_difficulty(Difficulty::Easy),
+        Action::FilterNormal => app.toggle_difficulty(Difficulty::Normal),
+        Action::FilterWizard => app.toggle_difficulty(Difficulty::Wizard),
+        Action::CycleMode => app.cycle_mode(),
+        Action::ArchiveOldest => app.archive_oldest(),
+        Action::Undo => app.undo(),
+        Action::Approve => app.approve(),
+        Action::Reject => app.reject(),
+    }
 }
 
-fn ui<B: Backend>(f: &mut Frame<B>, app: &App) {
-    let size = f.size();
-
-    let block = Block::default().style(Style::default().bg(Color::White).fg(Color::Black));
-    f.render_widget(block, size);
+fn play(app: &mut App) {
+    let path = match app.selected_save() {
+        Some(save) => save.path().to_path_buf(),
+        None => {
+            app.set_status("nothing to play");
+            return;
+        }
+    };
+    let template = match &app.config().play_cmd {
+        Some(template) => template.clone(),
+        None => {
+            app.set_status("set play_cmd in the config to play saves from here");
+            return;
+        }
+    };
+    if crypt::is_encrypted(&path) {
+        app.set_status("this backup is encrypted; restore it to play it");
+        return;
+    }
 
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(2)
-        .constraints([Constraint::Percentage(75), Constraint::Percentage(25)].as_ref())
-        .split(size);
+    match launch::spawn(&template, &path) {
+        Ok(()) => app.set_status(format!("playing {}", path.display())),
+        Err(e) => app.set_status(format!("could not run '{}': {}", template, e)),
+    }
+}
 
-    let delete_state_description: String = match &app.delete_state {
-        DeleteState::NotDeleting => "press 'd' to delete a save game".to_string(),
-        DeleteState::AwaitingIndex => {
-            "press a number to choose a game to delete, or ESC to cancel".to_string()
+fn copy_seed(app: &mut App) {
+    let seed = match app.selected_save().and_then(|s| s.metadata()) {
+        Some(metadata) => metadata.seed,
+        None => {
+            app.set_status("no seed to copy");
+            return;
         }
-        DeleteState::Delete(idx) => format!("deleting {}", idx),
     };
 
-    let state_descrition = vec![
-        Spans::from(delete_state_description),
-        Spans::from("press 'q' to quit"),
-    ];
+    match clipboard::copy(&seed.to_string()) {
+        Ok(()) => app.set_status(format!("copied seed {}", seed)),
+        Err(e) => app.set_status(format!("could not copy seed: {}", e)),
+    }
+}
 
-    let file_spans: Vec<_> = app
-        .state
-        .saves
-        .iter()
-        .enumerate()
-        .map(|(idx, s)| Spans::from(Span::raw(format!("{}) {}", letter(idx), s.to_string()))))
-        .collect();
-
-    let create_block = |title| {
-        Block::default()
-            .borders(Borders::ALL)
-            .style(Style::default().bg(Color::White).fg(Color::Black))
-            .title(Span::styled(
-                title,
-                Style::default().add_modifier(Modifier::BOLD),
-            ))
-    };
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn an_interval_is_due_once_its_period_has_passed() {
+        let start = Instant::now();
+        let interval = Interval::new(Duration::from_millis(100), start);
+        assert_eq!(interval.remaining(start), Duration::from_millis(100));
+        assert!(!interval.is_due(start));
 
-    let paragraph = Paragraph::new(file_spans.clone())
-        .style(Style::default().bg(Color::White).fg(Color::Black))
-        .block(create_block("Saves"))
-        .alignment(Alignment::Left);
-    f.render_widget(paragraph, chunks[0]);
-    let paragraph = Paragraph::new(state_descrition.clone())
-        .style(Style::default().bg(Color::White).fg(Color::Black))
-        .block(create_block("Left, wrap"))
-        .alignment(Alignment::Left)
-        .wrap(Wrap { trim: true });
-    f.render_widget(paragraph, chunks[1]);
+        let later = start + Duration::from_millis(40);
+        assert_eq!(interval.remaining(later), Duration::from_millis(60));
+        assert!(!interval.is_due(later));
+
+        assert!(interval.is_due(start + Duration::from_millis(100)));
+        // and stays due, rather than wrapping round, until it's reset
+        let overdue = start + Duration::from_millis(250);
+        assert_eq!(interval.remaining(overdue), Duration::ZERO);
+        assert!(interval.is_due(overdue));
+    }
+
+    #[test]
+    fn resetting_an_interval_starts_the_period_again() {
+        let start = Instant::now();
+        let mut interval = Interval::new(Duration::from_millis(100), start);
+        let reset_at = start + Duration::from_millis(150);
+        interval.reset(reset_at);
+        assert!(!interval.is_due(reset_at));
+        assert_eq!(
+            interval.remaining(reset_at + Duration::from_millis(30)),
+            Duration::from_millis(70)
+        );
+        assert!(interval.is_due(reset_at + Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn a_zero_interval_is_always_due() {
+        let start = Instant::now();
+        let interval = Interval::new(Duration::ZERO, start);
+        assert!(interval.is_due(start));
+    }
 }
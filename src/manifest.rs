@@ -0,0 +1,160 @@
+use crate::Result;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+pub const MANIFEST_FILE: &str = "manifest.txt";
+
+/// Each save we're tracking, by name, with when it was last written in seconds since the epoch
+/// if we know.
+pub type Manifest = BTreeMap<String, Option<u64>>;
+
+/// The saves we were tracking when we last shut down, one per line in `manifest.txt` in the
+/// backup dir: the name, then a tab and when it was written. Manifests from before we kept the
+/// time have just the names. A missing file, as on the first run, means there's nothing to
+/// compare with.
+pub fn load(backup_dir: &Path) -> Result<Option<Manifest>> {
+    let path = backup_dir.join(MANIFEST_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let manifest = std::fs::read_to_string(&path)?
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| match line.rsplit_once('\t') {
+            Some((name, secs)) if secs.parse::<u64>().is_ok() => {
+                (name.to_string(), secs.parse().ok())
+            }
+            _ => (line.to_string(), None),
+        })
+        .collect();
+    Ok(Some(manifest))
+}
+
+/// Records the saves being tracked now, for the next run to compare against.
+pub fn save(backup_dir: &Path, manifest: &Manifest) -> Result<()> {
+    let mut text = String::new();
+    for (name, written) in manifest {
+        text.push_str(name);
+        if let Some(secs) = written {
+            text.push_str(&format!("\t{}", secs));
+        }
+        text.push('\n');
+    }
+    std::fs::write(backup_dir.join(MANIFEST_FILE), text)?;
+    Ok(())
+}
+
+/// How the saves have changed between two manifests, by name.
+#[derive(Debug, Default, PartialEq)]
+pub struct Changes {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// there both times, but written since; only known for saves with a time both times
+    pub modified: Vec<String>,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// "3 new saves, 1 deleted, 2 updated", leaving out whatever didn't happen.
+impl Display for Changes {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut parts = vec![];
+        match self.added.len() {
+            0 => {}
+            1 => parts.push("1 new save".to_string()),
+            n => parts.push(format!("{} new saves", n)),
+        }
+        if !self.removed.is_empty() {
+            parts.push(format!("{} deleted", self.removed.len()));
+        }
+        if !self.modified.is_empty() {
+            parts.push(format!("{} updated", self.modified.len()));
+        }
+        if parts.is_empty() {
+            write!(f, "nothing changed")
+        } else {
+            write!(f, "{}", parts.join(", "))
+        }
+    }
+}
+
+/// What's changed from `before` to `after`.
+pub fn diff(before: &Manifest, after: &Manifest) -> Changes {
+    let mut changes = Changes::default();
+    for (name, written) in after {
+        match before.get(name) {
+            None => changes.added.push(name.clone()),
+            Some(Some(was)) if matches!(written, Some(now) if now != was) => {
+                changes.modified.push(name.clone())
+            }
+            Some(_) => {}
+        }
+    }
+    changes.removed = before
+        .keys()
+        .filter(|name| !after.contains_key(*name))
+        .cloned()
+        .collect();
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(saves: &[(&str, Option<u64>)]) -> Manifest {
+        saves
+            .iter()
+            .map(|(name, written)| (name.to_string(), *written))
+            .collect()
+    }
+
+    #[test]
+    fn finds_added_removed_and_modified_saves() {
+        let before = manifest(&[
+            ("kept", Some(1)),
+            ("rewritten", Some(1)),
+            ("deleted", Some(1)),
+            ("untimed", None),
+        ]);
+        let after = manifest(&[
+            ("kept", Some(1)),
+            ("rewritten", Some(2)),
+            ("new", Some(2)),
+            ("untimed", Some(2)),
+        ]);
+        let changes = diff(&before, &after);
+        assert_eq!(
+            changes,
+            Changes {
+                added: vec!["new".to_string()],
+                removed: vec!["deleted".to_string()],
+                // without a time before, we can't tell
+                modified: vec!["rewritten".to_string()],
+            }
+        );
+        assert_eq!(changes.to_string(), "1 new save, 1 deleted, 1 updated");
+
+        let unchanged = diff(&after, &after);
+        assert!(unchanged.is_empty());
+        assert_eq!(unchanged.to_string(), "nothing changed");
+        assert_eq!(diff(&Manifest::new(), &after).to_string(), "4 new saves");
+    }
+
+    #[test]
+    fn round_trips_through_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(load(dir.path()).unwrap(), None);
+
+        let saves = manifest(&[("timed", Some(1_700_000_000)), ("untimed", None)]);
+        save(dir.path(), &saves).unwrap();
+        assert_eq!(load(dir.path()).unwrap(), Some(saves));
+    }
+}
@@ -0,0 +1,256 @@
+use std::path::Path;
+use strum_macros::{Display, EnumString};
+
+const SAVE_PREFIX: &str = "Saved #";
+const DEPTH_MARKER: &str = " at depth ";
+const COMMENT_START: &str = " [";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, EnumString, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Wizard,
+}
+
+/// What brogue tells us about a game through the save's file name, e.g.
+/// 'Saved #287997478 at depth 6 (easy) (3).broguesave'
+#[derive(Clone, Debug, PartialEq)]
+pub struct SaveMetadata {
+    pub seed: u64,
+    pub depth: u32,
+    /// `None` if the name carries a difficulty we don't recognise
+    pub difficulty: Option<Difficulty>,
+}
+
+/// Reads the game details out of a save's file name, or `None` if it isn't in brogue's usual
+/// format. Runs on every file we scan, so it never panics on odd names: seeds or depths too
+/// big for their type, a repeated " at depth", empty names and so on are all just `None`, and
+/// a difficulty word we don't know, in any script, is a `None` difficulty. A comment added by
+/// renaming the save is ignored.
+pub fn parse_save_name(name: &str) -> Option<SaveMetadata> {
+    let stem = Path::new(name).file_stem()?.to_str()?;
+    let (stem, _) = split_comment(stem);
+    let rest = stem.strip_prefix(SAVE_PREFIX)?;
+
+    let (seed, rest) = split_number(rest)?;
+    let rest = rest.strip_prefix(DEPTH_MARKER)?;
+    let (depth, rest) = split_number(rest)?;
+    if !rest.is_empty() && !rest.starts_with(" (") {
+        return None;
+    }
+
+    // what's left is a run of parenthesised suffixes; the first word, if any, is the
+    // difficulty. Numbers like '(2)' are added when the same game is saved more than once.
+    let mut difficulty = Some(Difficulty::Normal);
+    for suffix in rest.split(" (").skip(1) {
+        let word = suffix.strip_suffix(')')?;
+        if !word.chars().all(|c| c.is_ascii_digit()) {
+            difficulty = word.parse().ok();
+            break;
+        }
+    }
+
+    Some(SaveMetadata {
+        seed: seed.parse().ok()?,
+        depth: depth.parse().ok()?,
+        difficulty,
+    })
+}
+
+fn split_number(s: &str) -> Option<(&str, &str)> {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    if end == 0 {
+        return None;
+    }
+    Some(s.split_at(end))
+}
+
+/// Splits the " [comment]" that renaming a save adds to the end of its file stem, e.g.
+/// 'Saved #1 at depth 3 [almost won]' is 'Saved #1 at depth 3' and 'almost won'.
+pub fn split_comment(stem: &str) -> (&str, Option<&str>) {
+    if let Some(inner) = stem.strip_suffix(']') {
+        if let Some(start) = inner.rfind(COMMENT_START) {
+            return (&inner[..start], Some(&inner[start + COMMENT_START.len()..]));
+        }
+    }
+    (stem, None)
+}
+
+/// `name` with its comment replaced by `comment`, or removed if `comment` is empty.
+pub fn with_comment(name: &str, comment: &str) -> String {
+    let path = Path::new(name);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let (base, _) = split_comment(&stem);
+    let mut renamed = if comment.is_empty() {
+        base.to_string()
+    } else {
+        format!("{}{}{}]", base, COMMENT_START, comment)
+    };
+    if let Some(extension) = path.extension() {
+        renamed.push('.');
+        renamed.push_str(&extension.to_string_lossy());
+    }
+    renamed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn metadata(seed: u64, depth: u32, difficulty: Option<Difficulty>) -> Option<SaveMetadata> {
+        Some(SaveMetadata {
+            seed,
+            depth,
+            difficulty,
+        })
+    }
+
+    #[test]
+    fn parses_brogue_names() {
+        let cases = [
+            (
+                "Saved #287997478 at depth 6.broguesave",
+                287997478,
+                6,
+                Some(Difficulty::Normal),
+            ),
+            (
+                "Saved #1 at depth 2 (easy).broguesave",
+                1,
+                2,
+                Some(Difficulty::Easy),
+            ),
+            (
+                "Saved #1 at depth 2 (wizard) (3).broguesave",
+                1,
+                2,
+                Some(Difficulty::Wizard),
+            ),
+            (
+                "Saved #1 at depth 2 (2).broguesave",
+                1,
+                2,
+                Some(Difficulty::Normal),
+            ),
+            (
+                "Saved #1 at depth 2 (easy) [almost won].broguesave",
+                1,
+                2,
+                Some(Difficulty::Easy),
+            ),
+            ("Saved #1 at depth 2 (nightmare).broguesave", 1, 2, None),
+        ];
+        for (name, seed, depth, difficulty) in cases {
+            assert_eq!(
+                parse_save_name(name),
+                metadata(seed, depth, difficulty),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn hostile_names_are_none_not_a_panic() {
+        let too_big_seed = format!("Saved #{}0 at depth 1.broguesave", u64::MAX);
+        let too_big_depth = format!("Saved #1 at depth {}0.broguesave", u32::MAX);
+        let long = format!("Saved #1 at depth 1{}.broguesave", " (easy)".repeat(10_000));
+        let names = [
+            "",
+            ".",
+            "..",
+            ".broguesave",
+            "Saved #",
+            "Saved #.broguesave",
+            "Saved # at depth .broguesave",
+            "Saved #1.broguesave",
+            "Saved #1 at depth .broguesave",
+            "Saved #-1 at depth 1.broguesave",
+            "Saved #1 at depth -1.broguesave",
+            "Saved #1 at depth 1 at depth 2.broguesave",
+            "Saved #1 at depth 1 (.broguesave",
+            "Saved #1 at depth 1 ().broguesave",
+            "Saved #1 at depth 1 (easy.broguesave",
+            "Saved #1 at depth 1x.broguesave",
+            "Saved #１ at depth 1.broguesave",
+            "Saved #1 at depth 1 (简单).broguesave",
+            "Saved #1 at depth 1 (easy) [.broguesave",
+            "Saved #1 at depth 1 []].broguesave",
+            "Saved #1 at depth 1\0.broguesave",
+            "Saved #1 at depth 1\n(easy).broguesave",
+            &too_big_seed,
+            &too_big_depth,
+            &long,
+        ];
+        for name in names {
+            parse_save_name(name);
+        }
+        assert_eq!(parse_save_name(&too_big_seed), None);
+        assert_eq!(parse_save_name(&too_big_depth), None);
+        assert_eq!(
+            parse_save_name("Saved #1 at depth 1 at depth 2.broguesave"),
+            None
+        );
+    }
+
+    #[test]
+    fn splits_and_replaces_comments() {
+        assert_eq!(
+            split_comment("Saved #1 at depth 3 [almost won]"),
+            ("Saved #1 at depth 3", Some("almost won"))
+        );
+        assert_eq!(
+            split_comment("Saved #1 at depth 3"),
+            ("Saved #1 at depth 3", None)
+        );
+        assert_eq!(
+            with_comment("Saved #1 at depth 3 [old].broguesave", "new"),
+            "Saved #1 at depth 3 [new].broguesave"
+        );
+        assert_eq!(
+            with_comment("Saved #1 at depth 3 [old].broguesave", ""),
+            "Saved #1 at depth 3.broguesave"
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn never_panics_on_any_name(name in any::<String>()) {
+            parse_save_name(&name);
+        }
+
+        #[test]
+        fn never_panics_on_names_built_from_its_own_pieces(
+            pieces in prop::collection::vec(
+                prop::sample::select(vec![
+                    "Saved #", " at depth ", " (", ")", " [", "]", "easy", "wizard", "0",
+                    "18446744073709551616", "4294967296", "9", ".broguesave", ".", " ", "é",
+                ]),
+                0..16,
+            )
+        ) {
+            parse_save_name(&pieces.concat());
+        }
+
+        #[test]
+        fn reads_back_any_seed_and_depth(seed in any::<u64>(), depth in any::<u32>()) {
+            let name = format!("Saved #{} at depth {}.broguesave", seed, depth);
+            prop_assert_eq!(
+                parse_save_name(&name),
+                metadata(seed, depth, Some(Difficulty::Normal))
+            );
+        }
+
+        // with any comment rename allows
+        #[test]
+        fn a_comment_never_changes_what_is_read(comment in "[^\\[\\]/\\\\\0]{0,40}") {
+            let name = "Saved #12 at depth 4 (easy).broguesave";
+            prop_assert_eq!(
+                parse_save_name(&with_comment(name, &comment)),
+                parse_save_name(name)
+            );
+        }
+    }
+}
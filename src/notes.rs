@@ -0,0 +1,97 @@
+use crate::Result;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+pub const NOTES_FILE: &str = "notes.toml";
+
+/// Free-text notes on saves, like "got the staff of firebolt here", by name. Kept in
+/// `notes.toml` in the backup dir, so they outlast the save being restored or deleted.
+pub struct Notes {
+    path: PathBuf,
+    notes: BTreeMap<String, String>,
+}
+
+impl Notes {
+    /// Reads the notes in `backup_dir`; a missing file means there are none.
+    pub fn load(backup_dir: &Path) -> Result<Notes> {
+        let path = backup_dir.join(NOTES_FILE);
+        let notes = if path.exists() {
+            toml::from_str(&std::fs::read_to_string(&path)?)?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Notes { path, notes })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.notes.get(key).map(String::as_str)
+    }
+
+    /// Sets the note on `key`, or removes it if `note` is empty, and saves the change.
+    pub fn set(&mut self, key: &str, note: &str) -> Result<()> {
+        if note.is_empty() {
+            self.notes.remove(key);
+        } else {
+            self.notes.insert(key.to_string(), note.to_string());
+        }
+        self.save()
+    }
+
+    /// Moves the note on `from`, if there is one, to `to`.
+    pub fn rename(&mut self, from: &str, to: &str) -> Result<()> {
+        match self.notes.remove(from) {
+            Some(note) => {
+                self.notes.insert(to.to_string(), note);
+                self.save()
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        std::fs::write(&self.path, toml::to_string(&self.notes)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notes_survive_a_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut notes = Notes::load(dir.path()).unwrap();
+        assert_eq!(notes.get("a.broguesave"), None);
+
+        notes
+            .set(
+                "a.broguesave",
+                "got the staff of \"firebolt\" here\nand a ring",
+            )
+            .unwrap();
+        notes.set("b.broguesave", "stairs").unwrap();
+        notes.rename("b.broguesave", "c.broguesave").unwrap();
+        // nothing to move
+        notes.rename("x.broguesave", "y.broguesave").unwrap();
+
+        let notes = Notes::load(dir.path()).unwrap();
+        assert_eq!(
+            notes.get("a.broguesave"),
+            Some("got the staff of \"firebolt\" here\nand a ring")
+        );
+        assert_eq!(notes.get("b.broguesave"), None);
+        assert_eq!(notes.get("c.broguesave"), Some("stairs"));
+        assert_eq!(notes.get("y.broguesave"), None);
+    }
+
+    #[test]
+    fn an_empty_note_removes_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut notes = Notes::load(dir.path()).unwrap();
+        notes.set("a.broguesave", "stairs").unwrap();
+        notes.set("a.broguesave", "").unwrap();
+
+        assert_eq!(Notes::load(dir.path()).unwrap().get("a.broguesave"), None);
+    }
+}
@@ -0,0 +1,183 @@
+use crate::config::Config;
+use chrono::{Local, NaiveTime};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+
+// how quiet hours are written in the config, e.g. "22:00-07:00"
+const TIME_FORMAT: &str = "%H:%M";
+
+/// A stretch of each day, in local time, when no desktop notifications pop up, like
+/// "22:00-07:00". It can run past midnight; a start equal to its end is never quiet.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct QuietHours {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl QuietHours {
+    /// Whether `time` is within the quiet hours; they include their start but not their end.
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            self.start <= time || time < self.end
+        }
+    }
+}
+
+impl TryFrom<String> for QuietHours {
+    type Error = String;
+
+    fn try_from(text: String) -> std::result::Result<Self, Self::Error> {
+        let invalid = || {
+            format!(
+                "quiet hours should look like \"22:00-07:00\", not {:?}",
+                text
+            )
+        };
+        let (start, end) = text.split_once('-').ok_or_else(invalid)?;
+        let time =
+            |t: &str| NaiveTime::parse_from_str(t.trim(), TIME_FORMAT).map_err(|_| invalid());
+        Ok(QuietHours {
+            start: time(start)?,
+            end: time(end)?,
+        })
+    }
+}
+
+impl Display for QuietHours {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format(TIME_FORMAT),
+            self.end.format(TIME_FORMAT)
+        )
+    }
+}
+
+impl From<QuietHours> for String {
+    fn from(quiet_hours: QuietHours) -> String {
+        quiet_hours.to_string()
+    }
+}
+
+/// Pops up a desktop notification, if they're on and it isn't quiet hours; either way, it's
+/// logged. Shown in the background, so a slow notification daemon doesn't stall the UI.
+pub fn notify(config: &Config, message: &str) {
+    if !config.notifications {
+        return;
+    }
+    if is_quiet(config, Local::now().time()) {
+        info!(
+            "[NOTIFY] quiet hours; not showing '{}'",
+            first_line(message)
+        );
+        return;
+    }
+    info!("[NOTIFY] {}", first_line(message));
+    let message = message.to_string();
+    std::thread::spawn(move || {
+        let shown = notify_rust::Notification::new()
+            .appname("backup-brogue")
+            .summary("backup-brogue")
+            .body(&message)
+            .show();
+        if let Err(e) = shown {
+            warn!("[NOTIFY] could not show a notification: {}", e);
+        }
+    });
+}
+
+fn is_quiet(config: &Config, time: NaiveTime) -> bool {
+    config
+        .quiet_hours
+        .is_some_and(|quiet_hours| quiet_hours.contains(time))
+}
+
+fn first_line(message: &str) -> &str {
+    message.lines().next().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(time, TIME_FORMAT).unwrap()
+    }
+
+    fn quiet(hours: &str) -> QuietHours {
+        QuietHours::try_from(hours.to_string()).unwrap()
+    }
+
+    #[test]
+    fn quiet_hours_within_a_day() {
+        let lunch = quiet("12:00-13:30");
+        assert!(lunch.contains(at("12:00")));
+        assert!(lunch.contains(at("13:29")));
+        assert!(!lunch.contains(at("13:30")));
+        assert!(!lunch.contains(at("11:59")));
+        assert!(!lunch.contains(at("00:00")));
+    }
+
+    #[test]
+    fn quiet_hours_past_midnight() {
+        let night = quiet("22:00-07:00");
+        assert!(night.contains(at("22:00")));
+        assert!(night.contains(at("23:59")));
+        assert!(night.contains(at("00:00")));
+        assert!(night.contains(at("06:59")));
+        assert!(!night.contains(at("07:00")));
+        assert!(!night.contains(at("12:00")));
+        assert!(!night.contains(at("21:59")));
+    }
+
+    #[test]
+    fn quiet_hours_that_start_where_they_end_are_never_quiet() {
+        let never = quiet("09:00-09:00");
+        assert!(!never.contains(at("09:00")));
+        assert!(!never.contains(at("21:00")));
+    }
+
+    #[test]
+    fn reads_and_writes_quiet_hours() {
+        assert_eq!(quiet(" 22:00 - 7:05 ").to_string(), "22:00-07:05");
+        for bad in [
+            "",
+            "22:00",
+            "22:00-",
+            "late-early",
+            "25:00-07:00",
+            "22:00-07:00-08:00",
+        ] {
+            assert!(QuietHours::try_from(bad.to_string()).is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn reads_quiet_hours_from_the_config() {
+        let config: Config = toml::from_str("quiet_hours = \"23:00-06:00\"").unwrap();
+        assert_eq!(config.quiet_hours, Some(quiet("23:00-06:00")));
+        assert!(toml::from_str::<Config>("quiet_hours = \"bedtime\"").is_err());
+
+        let written = toml::to_string(&config).unwrap();
+        assert!(
+            written.contains("quiet_hours = \"23:00-06:00\""),
+            "{}",
+            written
+        );
+    }
+
+    #[test]
+    fn only_quiet_during_quiet_hours() {
+        let mut config = Config::default();
+        assert!(!is_quiet(&config, at("03:00")));
+        config.quiet_hours = Some(quiet("22:00-07:00"));
+        assert!(is_quiet(&config, at("03:00")));
+        assert!(!is_quiet(&config, at("15:00")));
+    }
+}
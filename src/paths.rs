@@ -0,0 +1,192 @@
+use crate::config::{Source, CONFIG_FILE};
+use directories::ProjectDirs;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+const LEGACY_BACKUP_DIR: &str = ".brogue";
+const PROFILES_DIR: &str = "profiles";
+/// where the saves are, if not set by `--save-dir`; overrides the config file
+pub const SAVE_DIR_VAR: &str = "BROGUE_SAVE_DIR";
+/// where to keep the backups, if not set by `--backup-dir`
+pub const BACKUP_DIR_VAR: &str = "BROGUE_BACKUP_DIR";
+
+/// Where the tool keeps its own files.
+#[derive(Debug, Clone)]
+pub struct Paths {
+    pub backup_dir: PathBuf,
+    pub config_file: PathBuf,
+    /// whether these are in `~/.brogue` from an older version
+    pub legacy: bool,
+}
+
+/// Uses the platform's conventions (XDG dirs on linux, `~/Library/Application Support` on
+/// macos, `AppData` on windows), unless there's already a `~/.brogue` from an older version,
+/// in which case everything stays there.
+pub fn resolve(home: &Path) -> Paths {
+    let legacy = home.join(LEGACY_BACKUP_DIR);
+    if legacy.exists() {
+        return legacy_paths(legacy);
+    }
+
+    match ProjectDirs::from("org", "stevecooper", "backup-brogue") {
+        Some(dirs) => Paths {
+            backup_dir: dirs.data_dir().to_path_buf(),
+            config_file: dirs.config_dir().join(CONFIG_FILE),
+            legacy: false,
+        },
+        None => legacy_paths(legacy),
+    }
+}
+
+fn legacy_paths(dir: PathBuf) -> Paths {
+    Paths {
+        config_file: dir.join(CONFIG_FILE),
+        backup_dir: dir,
+        legacy: true,
+    }
+}
+
+/// The paths for the profile called `name`: a backup dir of its own in `profiles/<name>` under
+/// the usual one, and likewise for its config. `None` if `name` isn't usable as a dir name.
+pub fn for_profile(paths: &Paths, name: &str) -> Option<Paths> {
+    let usable =
+        !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\', ':']);
+    if !usable {
+        return None;
+    }
+
+    let config_dir = paths.config_file.parent().unwrap_or(&paths.backup_dir);
+    Some(Paths {
+        backup_dir: paths.backup_dir.join(PROFILES_DIR).join(name),
+        config_file: config_dir.join(PROFILES_DIR).join(name).join(CONFIG_FILE),
+        legacy: paths.legacy,
+    })
+}
+
+/// Picks a dir from a flag, an environment variable or the config file, in that order of
+/// precedence, and says which it came from; `None` if none of them set one. An empty variable
+/// counts as unset. Whatever the source, the dir is expanded the same way; see `expand`.
+pub fn choose_dir(
+    home: &Path,
+    flag: Option<&Path>,
+    var: Option<OsString>,
+    config: Option<&Path>,
+) -> Option<(PathBuf, Source)> {
+    let var = var.filter(|var| !var.is_empty()).map(PathBuf::from);
+    let (dir, source) = match (flag, var, config) {
+        (Some(flag), _, _) => (flag.to_path_buf(), Source::Flag),
+        (None, Some(var), _) => (var, Source::Env),
+        (None, None, Some(config)) => (config.to_path_buf(), Source::Config),
+        (None, None, None) => return None,
+    };
+    Some((expand(home, &dir), source))
+}
+
+/// `path` with a leading `~` taken as the home dir, and a relative path taken from the current
+/// dir.
+pub fn expand(home: &Path, path: &Path) -> PathBuf {
+    let path = match path.strip_prefix("~") {
+        Ok(rest) => home.join(rest),
+        Err(_) => path.to_path_buf(),
+    };
+    if path.is_relative() {
+        if let Ok(cwd) = std::env::current_dir() {
+            return cwd.join(path);
+        }
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uses_the_platform_dirs_without_an_old_brogue_dir() {
+        let home = tempfile::tempdir().unwrap();
+        let paths = resolve(home.path());
+        assert!(!paths.legacy);
+        assert!(paths.config_file.ends_with(CONFIG_FILE));
+        assert!(!paths
+            .backup_dir
+            .starts_with(home.path().join(LEGACY_BACKUP_DIR)));
+        if cfg!(target_os = "linux") {
+            assert!(paths.backup_dir.ends_with("backup-brogue"));
+            assert!(paths.config_file.ends_with("backup-brogue/config.toml"));
+        }
+    }
+
+    #[test]
+    fn keeps_everything_in_an_old_brogue_dir() {
+        let home = tempfile::tempdir().unwrap();
+        let legacy = home.path().join(LEGACY_BACKUP_DIR);
+        std::fs::create_dir_all(&legacy).unwrap();
+        let paths = resolve(home.path());
+        assert!(paths.legacy);
+        assert_eq!(paths.backup_dir, legacy);
+        assert_eq!(paths.config_file, legacy.join(CONFIG_FILE));
+    }
+
+    #[test]
+    fn gives_each_profile_dirs_of_its_own() {
+        let paths = Paths {
+            backup_dir: PathBuf::from("/backups"),
+            config_file: PathBuf::from("/config/config.toml"),
+            legacy: false,
+        };
+        let profile = for_profile(&paths, "seeded runs").unwrap();
+        assert_eq!(
+            profile.backup_dir,
+            Path::new("/backups/profiles/seeded runs")
+        );
+        assert_eq!(
+            profile.config_file,
+            Path::new("/config/profiles/seeded runs/config.toml")
+        );
+        assert!(!profile.legacy);
+
+        for name in ["", ".", "..", "a/b", "a\\b", "c:"] {
+            assert!(for_profile(&paths, name).is_none(), "{:?}", name);
+        }
+    }
+
+    // absolute paths look different on windows
+    #[cfg(unix)]
+    #[test]
+    fn chooses_the_flag_then_the_variable_then_the_config() {
+        let home = Path::new("/home/player");
+        let flag = Path::new("/from/flag");
+        let config = Path::new("~/from/config");
+        let var = || Some(OsString::from("/from/var"));
+
+        assert_eq!(
+            choose_dir(home, Some(flag), var(), Some(config)),
+            Some((PathBuf::from("/from/flag"), Source::Flag))
+        );
+        assert_eq!(
+            choose_dir(home, None, var(), Some(config)),
+            Some((PathBuf::from("/from/var"), Source::Env))
+        );
+        // an empty variable is as good as unset
+        assert_eq!(
+            choose_dir(home, None, Some(OsString::new()), Some(config)),
+            Some((PathBuf::from("/home/player/from/config"), Source::Config))
+        );
+        assert_eq!(choose_dir(home, None, None, None), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn expands_home_and_relative_dirs() {
+        let home = Path::new("/home/player");
+        assert_eq!(
+            expand(home, Path::new("~/saves")),
+            Path::new("/home/player/saves")
+        );
+        assert_eq!(expand(home, Path::new("/saves")), Path::new("/saves"));
+        assert_eq!(
+            expand(home, Path::new("saves")),
+            std::env::current_dir().unwrap().join("saves")
+        );
+    }
+}
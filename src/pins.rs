@@ -0,0 +1,52 @@
+use crate::Result;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+pub const PINS_FILE: &str = "pins.txt";
+
+/// Saves the user has pinned, by name, so nothing we do automatically ever removes them.
+/// Kept as one name per line in `pins.txt` in the backup dir.
+pub struct Pins {
+    path: PathBuf,
+    names: BTreeSet<String>,
+}
+
+impl Pins {
+    /// Reads the pins in `backup_dir`; a missing file means nothing is pinned.
+    pub fn load(backup_dir: &Path) -> Result<Pins> {
+        let path = backup_dir.join(PINS_FILE);
+        let names = if path.exists() {
+            std::fs::read_to_string(&path)?
+                .lines()
+                .map(|line| line.trim().to_string())
+                .filter(|line| !line.is_empty())
+                .collect()
+        } else {
+            BTreeSet::new()
+        };
+        Ok(Pins { path, names })
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.names.contains(key)
+    }
+
+    /// Pins `key` if it isn't pinned, unpins it if it is, and saves the change. Returns whether
+    /// it's now pinned.
+    pub fn toggle(&mut self, key: &str) -> Result<bool> {
+        let pinned = if self.names.remove(key) {
+            false
+        } else {
+            self.names.insert(key.to_string());
+            true
+        };
+
+        let mut text = String::new();
+        for name in &self.names {
+            text.push_str(name);
+            text.push('\n');
+        }
+        std::fs::write(&self.path, text)?;
+        Ok(pinned)
+    }
+}
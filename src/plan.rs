@@ -0,0 +1,232 @@
+use crate::backup::{Save, SyncAction};
+use crate::locale::Locale;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// What `--plan` prints: the saves that have come and gone since the manifest was written at
+/// the end of last session, and what the next sync would do. Everything is sorted, so the same
+/// dirs always give the same output.
+pub struct Report {
+    /// saves tracked now that weren't last session
+    pub new: Vec<String>,
+    /// saves tracked last session that have gone from both dirs
+    pub gone: Vec<String>,
+    pub actions: Vec<SyncAction>,
+    pub space: SpaceChange,
+}
+
+impl Report {
+    /// With no manifest, as on the first run, nothing counts as new or gone.
+    pub fn new(
+        tracked: &BTreeSet<String>,
+        last_session: Option<&BTreeSet<String>>,
+        mut actions: Vec<SyncAction>,
+    ) -> Report {
+        let (new, gone) = match last_session {
+            Some(last_session) => (
+                tracked.difference(last_session).cloned().collect(),
+                last_session.difference(tracked).cloned().collect(),
+            ),
+            None => (vec![], vec![]),
+        };
+        // restores still come before backups, as they would when syncing
+        actions.sort_by_key(|action| (phase(action), action.key()));
+        let space = SpaceChange::estimate(&actions);
+        Report {
+            new,
+            gone,
+            actions,
+            space,
+        }
+    }
+
+    /// One line per save: `+` for new, `-` for gone, then each action as the preview shows it,
+    /// and the disk use they add up to.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for name in &self.new {
+            text.push_str(&format!("+ {}\n", name));
+        }
+        for name in &self.gone {
+            text.push_str(&format!("- {}\n", name));
+        }
+        for action in &self.actions {
+            text.push_str(&format!("{}\n", action));
+        }
+        if self.actions.is_empty() {
+            text.push_str("nothing to do\n");
+        } else {
+            text.push_str(&format!(
+                "disk use: {}\n",
+                self.space.describe(Locale::English)
+            ));
+        }
+        text
+    }
+
+    /// `{"new": [...], "gone": [...], "actions": [{"action": "backup", "save": "..."}],
+    /// "space_bytes": 1234}`
+    pub fn to_json(&self) -> String {
+        let names = |names: &[String]| {
+            let quoted: Vec<_> = names.iter().map(|name| json_string(name)).collect();
+            format!("[{}]", quoted.join(", "))
+        };
+        let actions: Vec<_> = self
+            .actions
+            .iter()
+            .map(|action| {
+                format!(
+                    "{{\"action\": {}, \"save\": {}}}",
+                    json_string(action.kind()),
+                    json_string(&action.key())
+                )
+            })
+            .collect();
+        format!(
+            "{{\"new\": {}, \"gone\": {}, \"actions\": [{}], \"space_bytes\": {}}}\n",
+            names(&self.new),
+            names(&self.gone),
+            actions.join(", "),
+            self.space.bytes
+        )
+    }
+}
+
+/// Roughly how much more disk, across the save and backup dirs, carrying out a plan would
+/// take; negative if it would free some. Encryption and checksums are left out.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SpaceChange {
+    pub bytes: i64,
+    /// how many of the actions change how much space is used
+    pub files: usize,
+}
+
+impl SpaceChange {
+    /// Sizes each action by the files it would write or remove, as they are now.
+    pub fn estimate(actions: &[SyncAction]) -> SpaceChange {
+        let mut change = SpaceChange::default();
+        for action in actions {
+            let bytes = match action {
+                // a changed save's old backup is kept as a version, so the new one is all extra
+                SyncAction::Backup { save, .. }
+                | SyncAction::UpdateBackup { save, .. }
+                | SyncAction::Unlink { save, .. } => len(save),
+                SyncAction::AppendRecording { save, backup } => len(save) - len(backup),
+                SyncAction::Restore(save) => match save {
+                    Save::BackupFileOnly(backup) | Save::Both(_, backup) => len(backup),
+                    Save::OriginalFileOnly(_) => 0,
+                },
+                // moved within the backup dir
+                SyncAction::Archive(_) => 0,
+                SyncAction::Link { backup, .. } => -len(backup),
+                SyncAction::Replicate { backup, copy } => len(backup) - len(copy),
+            };
+            if bytes != 0 {
+                change.bytes += bytes;
+                change.files += 1;
+            }
+        }
+        change
+    }
+
+    /// e.g. "+12 MB (18 files)", or "-40 MB (6 files)".
+    pub fn describe(&self, locale: Locale) -> String {
+        let sign = if self.bytes < 0 { "-" } else { "+" };
+        let files = if self.files == 1 { "file" } else { "files" };
+        format!(
+            "{}{} ({} {})",
+            sign,
+            locale.format_size(self.bytes.unsigned_abs()),
+            self.files,
+            files
+        )
+    }
+}
+
+// a missing file takes no space
+fn len(path: &Path) -> i64 {
+    path.metadata().map(|m| m.len() as i64).unwrap_or(0)
+}
+
+fn phase(action: &SyncAction) -> u8 {
+    match action {
+        SyncAction::Restore(_) | SyncAction::Archive(_) => 0,
+        _ => 1,
+    }
+}
+
+// save names are whatever brogue or the user called them, so escape anything JSON can't hold
+fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::write;
+
+    #[test]
+    fn estimates_the_space_a_plan_takes() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = |name: &str, len: usize| write(dir.path(), name, &"x".repeat(len));
+        let (new, backup) = (file("new", 100), file("backup", 40));
+        let (recording, recorded) = (file("recording", 70), file("recorded", 50));
+        let (linked, missing) = (file("linked", 30), dir.path().join("missing"));
+        let actions = [
+            SyncAction::Backup {
+                save: new.clone(),
+                backup: missing.clone(),
+            },
+            SyncAction::AppendRecording {
+                save: recording,
+                backup: recorded,
+            },
+            SyncAction::Restore(Save::BackupFileOnly(backup.clone())),
+            SyncAction::Archive(backup.clone()),
+            SyncAction::Link {
+                save: new,
+                backup: linked,
+            },
+            SyncAction::Replicate {
+                backup,
+                copy: missing,
+            },
+        ];
+        // 100 + 20 + 40 - 30 + 40; the archive only moves a file
+        assert_eq!(
+            SpaceChange::estimate(&actions),
+            SpaceChange {
+                bytes: 170,
+                files: 5
+            }
+        );
+        assert_eq!(
+            SpaceChange::estimate(&actions).describe(Locale::English),
+            "+170 B (5 files)"
+        );
+        assert_eq!(SpaceChange::estimate(&[]), SpaceChange::default());
+    }
+
+    #[test]
+    fn describes_freed_space() {
+        let change = SpaceChange {
+            bytes: -3 * 1024 * 1024,
+            files: 1,
+        };
+        assert_eq!(change.describe(Locale::English), "-3.0 MB (1 file)");
+    }
+}
@@ -0,0 +1,253 @@
+use crate::Result;
+use crossterm::{
+    event::{self, Event, KeyCode},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use std::path::{Path, PathBuf};
+use tui::{
+    backend::{Backend, CrosstermBackend},
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame, Terminal,
+};
+
+/// Where the wizard is up to.
+enum Step {
+    /// offering the save dir we'd use by default
+    Detected,
+    /// looking through `dir` for the save dir; `selected` indexes ".." then the subdirs
+    Browsing {
+        dir: PathBuf,
+        subdirs: Vec<PathBuf>,
+        selected: usize,
+    },
+    /// showing where everything will go before writing the config
+    Confirm,
+}
+
+struct Wizard {
+    save_dir: PathBuf,
+    backup_dir: PathBuf,
+    config_file: PathBuf,
+    step: Step,
+}
+
+/// Walks a first-time user through choosing the save dir, then writes it to `config_file`.
+/// Returns the chosen save dir, or `None` if they quit instead.
+pub fn run(
+    default_save_dir: &Path,
+    backup_dir: &Path,
+    config_file: &Path,
+) -> Result<Option<PathBuf>> {
+    enable_raw_mode()?;
+    let mut stdout = std::io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+    let mut wizard = Wizard {
+        save_dir: default_save_dir.to_path_buf(),
+        backup_dir: backup_dir.to_path_buf(),
+        config_file: config_file.to_path_buf(),
+        step: Step::Detected,
+    };
+    let result = wizard.run(&mut terminal);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+
+    let chosen = result?;
+    if chosen {
+        write_config(config_file, &wizard.save_dir)?;
+        Ok(Some(wizard.save_dir))
+    } else {
+        Ok(None)
+    }
+}
+
+impl Wizard {
+    // returns whether the user finished rather than quitting
+    fn run<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<bool> {
+        loop {
+            terminal.draw(|f| self.draw(f))?;
+            let key = match event::read()? {
+                Event::Key(key) => key.code,
+                _ => continue,
+            };
+            if key == KeyCode::Char('q') {
+                return Ok(false);
+            }
+
+            match &mut self.step {
+                Step::Detected => match key {
+                    KeyCode::Enter => self.step = Step::Confirm,
+                    KeyCode::Char('b') => {
+                        let start = nearest_existing(&self.save_dir);
+                        self.browse(start);
+                    }
+                    _ => {}
+                },
+                Step::Browsing {
+                    dir,
+                    subdirs,
+                    selected,
+                } => match key {
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        *selected = (*selected + 1).min(subdirs.len());
+                    }
+                    KeyCode::Char('k') | KeyCode::Up => *selected = selected.saturating_sub(1),
+                    KeyCode::Enter => {
+                        let next = match *selected {
+                            0 => dir.parent().map(Path::to_path_buf),
+                            n => subdirs.get(n - 1).cloned(),
+                        };
+                        if let Some(next) = next {
+                            self.browse(next);
+                        }
+                    }
+                    KeyCode::Char('s') => {
+                        self.save_dir = dir.clone();
+                        self.step = Step::Confirm;
+                    }
+                    KeyCode::Esc => self.step = Step::Detected,
+                    _ => {}
+                },
+                Step::Confirm => match key {
+                    KeyCode::Enter | KeyCode::Char('y') => return Ok(true),
+                    KeyCode::Esc => self.step = Step::Detected,
+                    _ => {}
+                },
+            }
+        }
+    }
+
+    fn browse(&mut self, dir: PathBuf) {
+        let mut subdirs: Vec<_> = std::fs::read_dir(&dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .filter(|path| path.is_dir())
+                    .collect()
+            })
+            .unwrap_or_default();
+        subdirs.sort();
+        self.step = Step::Browsing {
+            dir,
+            subdirs,
+            selected: 0,
+        };
+    }
+
+    fn draw<B: Backend>(&self, f: &mut Frame<B>) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([Constraint::Min(3), Constraint::Length(3)].as_ref())
+            .split(f.size());
+
+        let (title, lines, help) = match &self.step {
+            Step::Detected => {
+                let found = if self.save_dir.exists() {
+                    Span::styled("found", Style::default().fg(Color::Green))
+                } else {
+                    Span::styled(
+                        "not there yet; brogue makes it the first time it runs",
+                        Style::default().fg(Color::Yellow),
+                    )
+                };
+                (
+                    "Welcome to backup-brogue",
+                    vec![
+                        Spans::from("brogue usually keeps its saves in"),
+                        Spans::from(Span::styled(
+                            self.save_dir.display().to_string(),
+                            Style::default().add_modifier(Modifier::BOLD),
+                        )),
+                        Spans::from(found),
+                    ],
+                    "ENTER to use it, 'b' to pick another folder, 'q' to quit",
+                )
+            }
+            Step::Browsing {
+                dir,
+                subdirs,
+                selected,
+            } => {
+                let names = std::iter::once("..".to_string()).chain(
+                    subdirs
+                        .iter()
+                        .map(|d| d.file_name().unwrap_or_default().to_string_lossy().into()),
+                );
+                let mut lines = vec![Spans::from(Span::styled(
+                    dir.display().to_string(),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ))];
+                for (idx, name) in names.enumerate() {
+                    let style = if idx == *selected {
+                        Style::default().add_modifier(Modifier::REVERSED)
+                    } else {
+                        Style::default()
+                    };
+                    lines.push(Spans::from(Span::styled(format!("  {}", name), style)));
+                }
+                (
+                    "Pick the save folder",
+                    lines,
+                    "'j'/'k' to move, ENTER to open, 's' to choose this folder, ESC to go back",
+                )
+            }
+            Step::Confirm => (
+                "Ready",
+                vec![
+                    Spans::from(format!("saves:   {}", self.save_dir.display())),
+                    Spans::from(format!("backups: {}", self.backup_dir.display())),
+                    Spans::from(format!("config:  {}", self.config_file.display())),
+                ],
+                "ENTER to save these settings and start, ESC to go back, 'q' to quit",
+            ),
+        };
+
+        // keep the selected folder on screen; the first line is the folder's own path
+        let scroll = match &self.step {
+            Step::Browsing { selected, .. } => {
+                let height = chunks[0].height.saturating_sub(3);
+                (*selected as u16 + 1).saturating_sub(height)
+            }
+            _ => 0,
+        };
+        let body = Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .scroll((scroll, 0));
+        f.render_widget(body, chunks[0]);
+        let help = Paragraph::new(help)
+            .block(Block::default().borders(Borders::ALL))
+            .wrap(Wrap { trim: true });
+        f.render_widget(help, chunks[1]);
+    }
+}
+
+// browsing starts from the default save dir, or as near to it as exists
+fn nearest_existing(dir: &Path) -> PathBuf {
+    dir.ancestors()
+        .find(|d| d.is_dir())
+        .unwrap_or(dir)
+        .to_path_buf()
+}
+
+// just the save dir, so every other setting keeps following the defaults
+fn write_config(config_file: &Path, save_dir: &Path) -> Result<()> {
+    if let Some(parent) = config_file.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut table = toml::value::Table::new();
+    table.insert(
+        "save_dir".to_string(),
+        toml::Value::String(save_dir.display().to_string()),
+    );
+    std::fs::write(config_file, toml::to_string(&table)?)?;
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use crate::Result;
+use chrono::{Local, NaiveDateTime, TimeZone};
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+pub const SNAPSHOTS_DIR: &str = "snapshots";
+
+// Every `snapshot_interval_mins`, each save in the save dir is copied into
+// `snapshots/<local time>/`, apart from the sync and whatever it's doing. Only folders named
+// like a snapshot's are ever listed or pruned, so anything else put there is left alone.
+const TIME_FORMAT: &str = "%Y-%m-%d %H.%M.%S";
+
+/// A new, empty folder for a snapshot taken now.
+pub fn new_snapshot_dir(backup_dir: &Path) -> Result<PathBuf> {
+    let name = Local::now().format(TIME_FORMAT).to_string();
+    let dir = backup_dir.join(SNAPSHOTS_DIR).join(name);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// The snapshots in the backup dir and when each was taken, newest first.
+pub fn snapshots(backup_dir: &Path) -> Vec<(SystemTime, PathBuf)> {
+    let entries = match std::fs::read_dir(backup_dir.join(SNAPSHOTS_DIR)) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    let mut snapshots: Vec<(SystemTime, PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .filter_map(|path| {
+            let name = path.file_name()?.to_string_lossy().to_string();
+            let taken = NaiveDateTime::parse_from_str(&name, TIME_FORMAT).ok()?;
+            let taken = Local.from_local_datetime(&taken).earliest()?;
+            Some((SystemTime::from(taken), path))
+        })
+        .collect();
+    snapshots.sort_by_key(|(taken, _)| Reverse(*taken));
+    snapshots
+}
+
+/// When the newest snapshot was taken, if there is one.
+pub fn latest(backup_dir: &Path) -> Option<SystemTime> {
+    snapshots(backup_dir).first().map(|(taken, _)| *taken)
+}
+
+/// Deletes all but the newest `keep` snapshots, returning the ones it deleted.
+pub fn prune(backup_dir: &Path, keep: usize) -> Result<Vec<PathBuf>> {
+    let mut pruned = vec![];
+    for (_, dir) in snapshots(backup_dir).into_iter().skip(keep) {
+        std::fs::remove_dir_all(&dir)?;
+        pruned.push(dir);
+    }
+    Ok(pruned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot_dir(backup_dir: &Path, name: &str) -> PathBuf {
+        let dir = backup_dir.join(SNAPSHOTS_DIR).join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.broguesave"), "game").unwrap();
+        dir
+    }
+
+    #[test]
+    fn prunes_all_but_the_newest_snapshots() {
+        let backup_dir = tempfile::tempdir().unwrap();
+        let backup_dir = backup_dir.path();
+        assert!(snapshots(backup_dir).is_empty());
+        assert_eq!(latest(backup_dir), None);
+
+        let oldest = snapshot_dir(backup_dir, "2020-01-01 09.00.00");
+        let older = snapshot_dir(backup_dir, "2020-01-01 10.00.00");
+        let newest = snapshot_dir(backup_dir, "2021-06-30 23.59.59");
+        let other = snapshot_dir(backup_dir, "keep me");
+        let listed: Vec<_> = snapshots(backup_dir)
+            .into_iter()
+            .map(|(_, dir)| dir)
+            .collect();
+        assert_eq!(listed, [newest.clone(), older.clone(), oldest.clone()]);
+        assert_eq!(latest(backup_dir), Some(snapshots(backup_dir)[0].0));
+
+        assert_eq!(prune(backup_dir, 2).unwrap(), vec![oldest.clone()]);
+        assert!(!oldest.exists());
+        assert!(older.exists() && newest.exists());
+        // only folders named like a snapshot's are ever pruned
+        assert_eq!(prune(backup_dir, 0).unwrap(), [newest, older]);
+        assert!(other.exists());
+    }
+
+    #[test]
+    fn names_a_new_snapshot_for_when_it_was_taken() {
+        let backup_dir = tempfile::tempdir().unwrap();
+        let before = SystemTime::now() - std::time::Duration::from_secs(1);
+        let dir = new_snapshot_dir(backup_dir.path()).unwrap();
+        assert!(dir.is_dir());
+        let (taken, listed) = snapshots(backup_dir.path()).remove(0);
+        assert_eq!(listed, dir);
+        assert!(taken >= before);
+    }
+}
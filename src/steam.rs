@@ -0,0 +1,162 @@
+use crate::backup::is_tracked;
+use log::info;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// The save dir to use when the config doesn't set one: the standalone game's, if it's there;
+/// failing that, a Steam one with brogue saves in it; failing that, the standalone game's,
+/// for brogue to create. `steam_dir` is where Steam is installed, if it's not somewhere usual.
+pub fn default_save_dir(home: &Path, standalone: PathBuf, steam_dir: Option<&Path>) -> PathBuf {
+    if standalone.exists() {
+        return standalone;
+    }
+    match find_save_dir(home, steam_dir) {
+        Some(dir) => {
+            info!("found brogue's Steam saves in {}", dir.display());
+            dir
+        }
+        None => standalone,
+    }
+}
+
+/// Steam keeps each game's files in `userdata/<account id>/<app id>/`, the cloud-synced ones
+/// in `remote/` under that. Rather than know brogue's app id, this looks for the dir with
+/// brogue saves or recordings in it; if there's more than one, e.g. for two accounts, the one
+/// written to most recently.
+pub fn find_save_dir(home: &Path, steam_dir: Option<&Path>) -> Option<PathBuf> {
+    let roots = match steam_dir {
+        Some(dir) => vec![dir.to_path_buf()],
+        None => steam_roots(home),
+    };
+
+    let mut found: Option<(SystemTime, PathBuf)> = None;
+    for root in roots {
+        for account in subdirs(&root.join("userdata")) {
+            for app in subdirs(&account) {
+                for dir in [app.join("remote"), app] {
+                    let newest = match newest_brogue_file(&dir) {
+                        Some(newest) => newest,
+                        None => continue,
+                    };
+                    if found.as_ref().is_none_or(|(time, _)| newest > *time) {
+                        found = Some((newest, dir));
+                    }
+                }
+            }
+        }
+    }
+    found.map(|(_, dir)| dir)
+}
+
+// where Steam usually lives on each platform, flatpak included
+fn steam_roots(home: &Path) -> Vec<PathBuf> {
+    vec![
+        home.join(".steam/steam"),
+        home.join(".local/share/Steam"),
+        home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"),
+        home.join("Library/Application Support/Steam"),
+        PathBuf::from(r"C:\Program Files (x86)\Steam"),
+    ]
+}
+
+fn subdirs(dir: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_dir())
+            .collect(),
+        Err(_) => return vec![],
+    };
+    dirs.sort();
+    dirs
+}
+
+// when the newest save or recording in `dir` was written; `None` if there aren't any
+fn newest_brogue_file(dir: &Path) -> Option<SystemTime> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| is_tracked(path))
+        .filter_map(|path| path.metadata().and_then(|m| m.modified()).ok())
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{save_name, set_age, write};
+    use std::time::Duration;
+
+    // makes `userdata/<account>/<app>/<dir>` under `steam` and returns it
+    fn app_dir(steam: &Path, account: &str, app: &str, dir: &str) -> PathBuf {
+        let dir = steam.join("userdata").join(account).join(app).join(dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn finds_the_dir_with_brogue_saves_in_it() {
+        let steam = tempfile::tempdir().unwrap();
+        let home = tempfile::tempdir().unwrap();
+        let other_game = app_dir(steam.path(), "1234", "440", "remote");
+        write(&other_game, "settings.cfg", "");
+        let brogue = app_dir(steam.path(), "1234", "2239930", "remote");
+        write(&brogue, &save_name(1, 1), "game");
+
+        assert_eq!(find_save_dir(home.path(), Some(steam.path())), Some(brogue));
+    }
+
+    #[test]
+    fn picks_the_account_written_to_most_recently() {
+        let steam = tempfile::tempdir().unwrap();
+        let home = tempfile::tempdir().unwrap();
+        let old = app_dir(steam.path(), "1111", "2239930", "remote");
+        set_age(
+            &write(&old, &save_name(1, 1), "game"),
+            Duration::from_secs(3600),
+        );
+        // saves straight in the app dir, not in remote/, count too
+        let new = app_dir(steam.path(), "2222", "2239930", "");
+        write(&new, &save_name(2, 1), "game");
+
+        assert_eq!(
+            find_save_dir(home.path(), Some(steam.path())),
+            Some(steam.path().join("userdata/2222/2239930"))
+        );
+    }
+
+    #[test]
+    fn looks_where_steam_usually_lives() {
+        let home = tempfile::tempdir().unwrap();
+        let steam = home.path().join(".local/share/Steam");
+        let brogue = app_dir(&steam, "1234", "2239930", "remote");
+        write(&brogue, &save_name(1, 1), "game");
+
+        assert_eq!(find_save_dir(home.path(), None), Some(brogue.clone()));
+        // but not when pointed somewhere else
+        let elsewhere = tempfile::tempdir().unwrap();
+        assert_eq!(find_save_dir(home.path(), Some(elsewhere.path())), None);
+
+        let standalone = home.path().join("brogue");
+        assert_eq!(
+            default_save_dir(home.path(), standalone.clone(), None),
+            brogue
+        );
+        std::fs::create_dir(&standalone).unwrap();
+        assert_eq!(
+            default_save_dir(home.path(), standalone.clone(), None),
+            standalone
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_standalone_dir_without_steam_saves() {
+        let home = tempfile::tempdir().unwrap();
+        app_dir(home.path(), "1234", "2239930", "remote");
+        let standalone = home.path().join("brogue");
+        assert_eq!(
+            default_save_dir(home.path(), standalone.clone(), Some(home.path())),
+            standalone
+        );
+    }
+}
@@ -0,0 +1,47 @@
+use crate::Result;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+const CHUNK: u64 = 8 * 1024;
+
+/// The last `n` lines of a file, read backwards from the end so big logs stay cheap.
+pub fn tail(path: &Path, n: usize) -> Result<Vec<String>> {
+    let mut file = std::fs::File::open(path)?;
+    let len = file.seek(SeekFrom::End(0))?;
+
+    let mut start = len;
+    let mut buf = vec![];
+    // one more newline than lines wanted, since the file usually ends with one
+    while start > 0 && buf.iter().filter(|&&b| b == b'\n').count() <= n {
+        let read_from = start.saturating_sub(CHUNK);
+        let mut chunk = vec![0; (start - read_from) as usize];
+        file.seek(SeekFrom::Start(read_from))?;
+        file.read_exact(&mut chunk)?;
+        chunk.extend(buf);
+        buf = chunk;
+        start = read_from;
+    }
+
+    let text = String::from_utf8_lossy(&buf);
+    let lines: Vec<_> = text.lines().map(strip_ansi).collect();
+    let skip = lines.len().saturating_sub(n);
+    Ok(lines.into_iter().skip(skip).collect())
+}
+
+// the log file has coloured levels, which would otherwise show up as junk
+fn strip_ansi(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
@@ -0,0 +1,104 @@
+//! Fixtures for tests that sync real files: a save dir and a backup dir in a temporary folder,
+//! and an `App` over them.
+
+use crate::backup::App;
+use crate::config::Config;
+use crate::long_names::LongNames;
+use crate::notes::Notes;
+use crate::pins::Pins;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+
+pub struct Dirs {
+    // removed when dropped
+    _root: TempDir,
+    pub save_dir: PathBuf,
+    pub backup_dir: PathBuf,
+}
+
+impl Dirs {
+    pub fn new() -> Dirs {
+        let root = tempfile::tempdir().unwrap();
+        let save_dir = root.path().join("saves");
+        let backup_dir = root.path().join("backups");
+        std::fs::create_dir_all(&save_dir).unwrap();
+        std::fs::create_dir_all(&backup_dir).unwrap();
+        Dirs {
+            _root: root,
+            save_dir,
+            backup_dir,
+        }
+    }
+
+    /// An app over these dirs, scanned once but not synced yet.
+    pub fn app(&self, config: Config) -> App {
+        let mut app = self.unscanned_app(config);
+        app.update_state().unwrap();
+        app
+    }
+
+    /// An app over these dirs, as it is at startup, before the first scan.
+    pub fn unscanned_app(&self, config: Config) -> App {
+        App::new(
+            self.save_dir.clone(),
+            self.backup_dir.clone(),
+            config,
+            None,
+            Pins::load(&self.backup_dir).unwrap(),
+            Notes::load(&self.backup_dir).unwrap(),
+            LongNames::load(&self.backup_dir).unwrap(),
+        )
+    }
+
+    pub fn save(&self, name: &str, contents: &str) -> PathBuf {
+        write(&self.save_dir, name, contents)
+    }
+
+    pub fn backup(&self, name: &str, contents: &str) -> PathBuf {
+        write(&self.backup_dir, name, contents)
+    }
+
+    /// The file names at the top of the backup dir, sorted; no folders.
+    pub fn backups(&self) -> Vec<String> {
+        names(&self.backup_dir)
+    }
+}
+
+/// A name brogue would give the `n`th save, at depth `depth`.
+pub fn save_name(n: u32, depth: u32) -> String {
+    format!("Saved #{} at depth {}.broguesave", n, depth)
+}
+
+/// Writes `contents` to `dir/name`, last modified a minute ago so it doesn't look like brogue
+/// is still writing it.
+pub fn write(dir: &Path, name: &str, contents: &str) -> PathBuf {
+    let path = dir.join(name);
+    std::fs::write(&path, contents).unwrap();
+    set_age(&path, Duration::from_secs(60));
+    path
+}
+
+pub fn set_age(path: &Path, age: Duration) {
+    File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(SystemTime::now() - age)
+        .unwrap();
+}
+
+/// The files, not folders, in `dir`, sorted.
+pub fn names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_file())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect(),
+        Err(_) => vec![],
+    };
+    names.sort();
+    names
+}
@@ -0,0 +1,122 @@
+use std::cell::Cell;
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
+
+// how much each copy moves the average; the rest is the copies before it
+const SMOOTHING: f64 = 0.3;
+
+// the most we read or write at once; small enough that the rate stays smooth
+const MAX_CHUNK: u64 = 1024 * 1024;
+const MIN_CHUNK: u64 = 4 * 1024;
+
+/// Limits how fast we copy, so a burst of backups or restores doesn't take all the disk's
+/// attention away from a running game.
+#[derive(Clone, Copy, Debug)]
+pub struct Throttle {
+    bytes_per_sec: u64,
+}
+
+impl Throttle {
+    /// `None` unless a limit is set.
+    pub fn new(bytes_per_sec: Option<u64>) -> Option<Throttle> {
+        bytes_per_sec
+            .filter(|rate| *rate > 0)
+            .map(|bytes_per_sec| Throttle { bytes_per_sec })
+    }
+
+    /// Like `std::io::copy`, but sleeps between chunks so that on average no more than the
+    /// limit is copied each second.
+    pub fn copy(&self, reader: &mut impl Read, writer: &mut impl Write) -> std::io::Result<u64> {
+        // about ten chunks a second
+        let chunk = (self.bytes_per_sec / 10).clamp(MIN_CHUNK, MAX_CHUNK) as usize;
+        let mut buf = vec![0; chunk];
+        let started = Instant::now();
+        let mut copied = 0;
+        loop {
+            let read = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            writer.write_all(&buf[..read])?;
+            copied += read as u64;
+
+            let due = Duration::from_secs_f64(copied as f64 / self.bytes_per_sec as f64);
+            if let Some(ahead) = due.checked_sub(started.elapsed()) {
+                std::thread::sleep(ahead);
+            }
+        }
+        writer.flush()?;
+        Ok(copied)
+    }
+}
+
+/// How fast copies have been going lately, as an exponential moving average in bytes a second,
+/// so the effect of a change to `max_copy_bytes_per_sec` shows after a few copies.
+#[derive(Debug, Default)]
+pub struct Throughput {
+    average: Cell<Option<f64>>,
+}
+
+impl Throughput {
+    /// Counts a copy of `bytes` that took `took`. Copies too quick to time are left out.
+    pub fn record(&self, bytes: u64, took: Duration) {
+        if took.is_zero() {
+            return;
+        }
+        let rate = bytes as f64 / took.as_secs_f64();
+        let average = match self.average.get() {
+            Some(average) => average + SMOOTHING * (rate - average),
+            None => rate,
+        };
+        self.average.set(Some(average));
+    }
+
+    /// `None` until something's been copied.
+    pub fn bytes_per_sec(&self) -> Option<f64> {
+        self.average.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_throttles_with_a_limit() {
+        assert!(Throttle::new(None).is_none());
+        assert!(Throttle::new(Some(0)).is_none());
+        assert!(Throttle::new(Some(1024)).is_some());
+    }
+
+    #[test]
+    fn copies_everything_no_faster_than_the_limit() {
+        let throttle = Throttle::new(Some(40_000)).unwrap();
+        let source: Vec<u8> = (0..20_000).map(|i| (i % 251) as u8).collect();
+        let mut copy = vec![];
+
+        let started = Instant::now();
+        let copied = throttle.copy(&mut source.as_slice(), &mut copy).unwrap();
+        assert_eq!(copied, 20_000);
+        assert_eq!(copy, source);
+        // half a second's worth at the limit, less a little for the clock
+        assert!(started.elapsed() >= Duration::from_millis(450));
+    }
+
+    #[test]
+    fn averages_recent_copy_rates() {
+        let throughput = Throughput::default();
+        assert_eq!(throughput.bytes_per_sec(), None);
+        throughput.record(1000, Duration::ZERO);
+        assert_eq!(throughput.bytes_per_sec(), None);
+
+        throughput.record(1000, Duration::from_secs(1));
+        assert_eq!(throughput.bytes_per_sec(), Some(1000.0));
+        // each copy moves the average part of the way towards its own rate
+        throughput.record(2000, Duration::from_secs(1));
+        assert_eq!(throughput.bytes_per_sec(), Some(1300.0));
+        throughput.record(500, Duration::from_millis(500));
+        assert_eq!(throughput.bytes_per_sec(), Some(1210.0));
+    }
+}
@@ -0,0 +1,1506 @@
+use crate::activity::Activity;
+use crate::backup::{
+    App, AppMode, DeleteState, DiffSummary, FailedRestore, Health, ListRow, Prompt, Save, Side,
+    Stats, SyncAction,
+};
+use crate::keymap::Action;
+use crate::locale::Locale;
+use crate::manifest::Changes;
+use crate::metadata::Difficulty;
+use crate::plan::SpaceChange;
+use crate::verify::Verification;
+use crate::versions::version_time;
+use crossterm::{
+    cursor, queue,
+    terminal::{self, ClearType},
+};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+// below this the two-pane layout doesn't fit, so fall back to the compact one
+const MIN_FULL_HEIGHT: u16 = 18;
+const MIN_FULL_WIDTH: u16 = 40;
+// below this the compact status line only has room for the quit key or a short prompt
+const MIN_STATUS_WIDTH: u16 = 40;
+// the fewest saves the full layout shows before it gives way to the compact one
+const MIN_SAVE_ROWS: usize = 3;
+// the most saves the since-last-launch popup lists by name
+const MAX_CHANGES_LISTED: usize = 10;
+
+/// The letter that picks the save at `idx` when deleting; only the first 26 have one.
+pub fn letter(idx: usize) -> Option<char> {
+    (idx < 26).then(|| char::from(b'a' + idx as u8))
+}
+
+/// The save at index `letter` picks, if it's one of ours.
+pub fn letter_index(letter: char) -> Option<usize> {
+    letter
+        .is_ascii_lowercase()
+        .then(|| usize::from(letter as u8 - b'a'))
+}
+
+fn difficulty_style(difficulty: Option<Difficulty>) -> Style {
+    match difficulty {
+        Some(Difficulty::Easy) => Style::default().fg(Color::Green),
+        Some(Difficulty::Normal) => Style::default(),
+        Some(Difficulty::Wizard) => Style::default().fg(Color::Magenta),
+        None => Style::default().fg(Color::DarkGray),
+    }
+}
+
+/// How to mark the selected save, from the `highlight` config setting. Anything we can't
+/// parse falls back to reverse video, which reads fine on light and dark terminals alike.
+fn highlight_style(highlight: &str) -> Style {
+    match parse_color(highlight) {
+        Some(color) => Style::default().bg(color).fg(contrasting(color)),
+        None => Style::default().add_modifier(Modifier::REVERSED),
+    }
+}
+
+// a colour name like "yellow", or "#rrggbb"
+fn parse_color(name: &str) -> Option<Color> {
+    let color = match name.to_lowercase().as_str() {
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "white" => Color::White,
+        hex => {
+            let (r, g, b) = parse_hex(hex)?;
+            Color::Rgb(r, g, b)
+        }
+    };
+    Some(color)
+}
+
+fn parse_hex(s: &str) -> Option<(u8, u8, u8)> {
+    let s = s.strip_prefix('#')?;
+    if s.len() != 6 || !s.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&s[i..i + 2], 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Black or white, whichever is easier to read on `background`.
+fn contrasting(background: Color) -> Color {
+    let light = match background {
+        Color::Yellow | Color::Cyan | Color::Gray | Color::White | Color::Green => true,
+        Color::Rgb(r, g, b) => 299 * r as u32 + 587 * g as u32 + 114 * b as u32 > 128_000,
+        _ => false,
+    };
+    if light {
+        Color::Black
+    } else {
+        Color::White
+    }
+}
+
+/// The prompts, messages and key hints shown under the saves list, one per line.
+fn status_lines(app: &App) -> Vec<String> {
+    let delete_state_description: String = match &app.delete_state {
+        DeleteState::NotDeleting if !app.config().allow_delete => String::new(),
+        DeleteState::NotDeleting => {
+            format!(
+                "press {} to delete a save game",
+                app.keymap.key(Action::Delete)
+            )
+        }
+        DeleteState::AwaitingIndex => {
+            "press a letter, or Enter for the selected save, to choose a game to delete, or ESC to cancel".to_string()
+        }
+        DeleteState::ConfirmLastCopy(idx) => format!(
+            "'{}' is the last copy of that save; press y to delete it anyway, or any other key to keep it",
+            app.delete_label(*idx)
+        ),
+        DeleteState::Delete(idx) => format!("deleting '{}'", app.delete_label(*idx)),
+    };
+
+    let filter_description = if app.difficulty_filter.is_empty() {
+        format!(
+            "showing all difficulties; press {}/{}/{} to filter by easy/normal/wizard",
+            app.keymap.key(Action::FilterEasy),
+            app.keymap.key(Action::FilterNormal),
+            app.keymap.key(Action::FilterWizard)
+        )
+    } else {
+        let shown: Vec<_> = app
+            .difficulty_filter
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+        format!(
+            "showing {} only; press {}/{}/{} to toggle",
+            shown.join(", "),
+            app.keymap.key(Action::FilterEasy),
+            app.keymap.key(Action::FilterNormal),
+            app.keymap.key(Action::FilterWizard)
+        )
+    };
+
+    let order = if app.sort_descending {
+        "newest"
+    } else {
+        "oldest"
+    };
+    let mut filter_description = format!(
+        "{}; showing {} ({} to change), {} first ({} to flip)",
+        filter_description,
+        app.kind_filter,
+        app.keymap.key(Action::CycleKind),
+        order,
+        app.keymap.key(Action::FlipSort)
+    );
+    if app.search.is_empty() {
+        filter_description.push_str(&format!(
+            "; press {} to search names and notes",
+            app.keymap.key(Action::Search)
+        ));
+    } else {
+        filter_description.push_str(&format!(
+            "; matching '{}' ({} to change, ESC to clear)",
+            app.search,
+            app.keymap.key(Action::Search)
+        ));
+    }
+
+    let delete_state_description = match app.status() {
+        Some(status) if delete_state_description.is_empty() => status.to_string(),
+        Some(status) => format!("{} - {}", status, delete_state_description),
+        None => delete_state_description,
+    };
+
+    let mut lines = vec![];
+    if let Some(e) = app.unwritable_backup_dir() {
+        lines.push(format!(
+            "(!) can't write to the backup dir ({}); only watching until it's writable again",
+            e
+        ));
+    }
+    let excess = app.excess_live_saves();
+    if excess > 0 {
+        lines.push(format!(
+            "(!) more than {} saves in the save dir; press {} to archive the oldest {}",
+            app.config().max_live_saves,
+            app.keymap.key(Action::ArchiveOldest),
+            excess
+        ));
+    }
+    match &app.prompt {
+        Some(Prompt::ExportPath { input, .. }) => lines.push(format!(
+            "export to (ENTER to confirm, ESC to cancel): {}_",
+            input
+        )),
+        Some(Prompt::ConfirmOverwrite { destination, .. }) => lines.push(format!(
+            "{} already exists; overwrite it? (y/n)",
+            destination.display()
+        )),
+        Some(Prompt::RestorePath { input, version, .. }) => lines.push(format!(
+            "restore {} into folder (ENTER to confirm, ESC to cancel): {}_",
+            if version.is_some() {
+                "this version"
+            } else {
+                "it"
+            },
+            input
+        )),
+        Some(Prompt::Rename { input, .. }) => lines.push(format!(
+            "comment to add to the name (ENTER to confirm, ESC to cancel): {}_",
+            input
+        )),
+        Some(Prompt::Note { input, .. }) => lines.push(format!(
+            "note (ENTER to confirm, empty to remove, ESC to cancel): {}_",
+            input
+        )),
+        Some(Prompt::Search { input }) => lines.push(format!(
+            "search names and notes (ENTER to confirm, empty to show all, ESC to cancel): {}_",
+            input
+        )),
+        Some(Prompt::GoToSeed { input }) => lines.push(format!(
+            "go to seed (ENTER to confirm, ESC to cancel): {}_",
+            input
+        )),
+        Some(Prompt::Recover { missing, found }) => lines.push(recover_message(missing, found)),
+        Some(Prompt::ResolveDiverged { resolutions }) => lines.push(resolve_message(resolutions)),
+        None => {}
+    }
+    if !delete_state_description.is_empty() {
+        lines.push(delete_state_description);
+    }
+    for (key, failed) in app.failed_restores() {
+        lines.push(failed_restore_line(app, key, failed));
+    }
+    let watching = match app.watch_strategy() {
+        Some(strategy) => format!("watching the save dir using {}", strategy),
+        // without a watcher, changes wait for the next sync, so say when that is
+        None => {
+            let next = app
+                .next_sync
+                .map(|at| at.saturating_duration_since(Instant::now()))
+                .unwrap_or_default();
+            format!(
+                "checking the save dir every {}ms; next sync in {:.1}s",
+                app.config().reconcile_interval_ms,
+                next.as_secs_f64()
+            )
+        }
+    };
+    let mut watching = format!(
+        "{}; sync mode is {} (press {} to change)",
+        watching,
+        app.config().mode,
+        app.keymap.key(Action::CycleMode)
+    );
+    if app.is_manual() {
+        watching.push_str("; manual: nothing is synced until you approve it");
+    }
+    if app.is_cross_volume() {
+        watching.push_str("; backup on external volume");
+    }
+    lines.extend([
+        watching,
+        filter_description,
+        help_line(
+            app,
+            &[
+                (Action::Down, "/"),
+                (Action::Up, " to move, "),
+                (Action::Expand, "/"),
+                (
+                    Action::Collapse,
+                    " or ENTER to show or hide older versions, ",
+                ),
+                (Action::CopySeed, " to copy the selected seed, "),
+                (Action::Export, " to export it, "),
+                (Action::Play, " to play it, "),
+                (Action::RestoreTo, " to restore it elsewhere, "),
+                (Action::Rename, " to rename it, "),
+                (Action::Note, " to add a note, "),
+                (Action::Pin, " to pin it, "),
+                (Action::Diff, " to compare it with its backup, "),
+                (Action::Compare, " to compare it with another save"),
+            ],
+        ),
+        help_line(
+            app,
+            &[
+                (Action::Follow, " to follow the newest save, "),
+                (Action::Log, " to show the log, "),
+                (Action::Stats, " for stats, "),
+                (Action::Compact, " to toggle compact view, "),
+                (Action::GoToSeed, " to go to a seed, "),
+                (Action::FullPaths, " to show full paths, "),
+                (Action::Undo, " to undo, "),
+                (Action::ResolveDiverged, " to resolve diverged saves, "),
+                (Action::Quit, " to quit"),
+            ],
+        ),
+    ]);
+    if app.log_tail().is_some() {
+        lines.push(help_line(
+            app,
+            &[
+                (Action::LogBack, "/"),
+                (Action::LogForward, " to scroll the log back and forth"),
+            ],
+        ));
+    }
+    lines
+}
+
+/// Whether the newest save is backed up: a coloured dot, and how long it's been waiting if
+/// it isn't.
+fn health_span(app: &App) -> Span<'static> {
+    let text = match app.health() {
+        Health::Good => "● backed up".to_string(),
+        Health::Unbacked(_) => "● unbacked progress; backing up".to_string(),
+        Health::Warning(waiting) | Health::Alarm(waiting) => health_text(app, waiting),
+    };
+    Span::styled(text, health_style(app))
+}
+
+fn health_style(app: &App) -> Style {
+    let color = match app.health() {
+        Health::Good => Color::Green,
+        Health::Unbacked(_) => Color::Blue,
+        Health::Warning(_) => Color::Yellow,
+        Health::Alarm(_) => Color::Red,
+    };
+    Style::default().fg(color).add_modifier(Modifier::BOLD)
+}
+
+// the compact status line for a narrow screen: just the health dot, then the prompt if
+// there is one, or else how to quit
+fn narrow_status(app: &App) -> Spans<'static> {
+    let text =
+        short_prompt(app).unwrap_or_else(|| format!("{} quit", app.keymap.key(Action::Quit)));
+    Spans::from(vec![
+        Span::styled("●", health_style(app)),
+        Span::raw(format!(" {}", text)),
+    ])
+}
+
+fn health_text(app: &App, waiting: Duration) -> String {
+    format!(
+        "● newest save not backed up (written {})",
+        app.locale().format_relative(waiting)
+    )
+}
+
+// "press " then each action's key followed by what it does
+fn help_line(app: &App, parts: &[(Action, &str)]) -> String {
+    let mut line = "press ".to_string();
+    for (action, what) in parts {
+        line.push_str(&app.keymap.key(*action));
+        line.push_str(what);
+    }
+    line
+}
+
+// why a restore failed, and when it'll be tried again
+fn failed_restore_line(app: &App, key: &str, failed: &FailedRestore) -> String {
+    let retry = app.keymap.key(Action::RetryRestores);
+    let when = match failed.retry_at {
+        Some(at) => format!(
+            "trying again in {}s (press {} to retry now)",
+            at.saturating_duration_since(Instant::now()).as_secs(),
+            retry
+        ),
+        None => format!("not trying again (press {} to retry)", retry),
+    };
+    format!(
+        "could not restore '{}' ({} tries): {}; {}",
+        key, failed.attempts, failed.error, when
+    )
+}
+
+// "resolve 3 diverged saves, keeping the backup of 'a', 'b', and the live save of 'c'? (y/n)"
+fn resolve_message(resolutions: &[(String, Side)]) -> String {
+    let kept = |side: Side| -> Vec<String> {
+        resolutions
+            .iter()
+            .filter(|(_, s)| *s == side)
+            .map(|(key, _)| format!("'{}'", key))
+            .collect()
+    };
+    let mut parts = vec![];
+    for side in [Side::Backup, Side::Live] {
+        let keys = kept(side);
+        if !keys.is_empty() {
+            parts.push(format!("the {} of {}", side, keys.join(", ")));
+        }
+    }
+    format!(
+        "resolve {} diverged saves, keeping {}? (y/n)",
+        resolutions.len(),
+        parts.join(", and ")
+    )
+}
+
+fn recover_message(missing: &[String], found: &[(String, PathBuf)]) -> String {
+    let message = format!(
+        "{} saves have gone since last session: {}",
+        missing.len(),
+        missing.join(", ")
+    );
+    if found.is_empty() {
+        format!(
+            "{}; no copies left to recover (any key to dismiss)",
+            message
+        )
+    } else {
+        format!(
+            "{}; recover the {} found in trash/archive? (y/n)",
+            message,
+            found.len()
+        )
+    }
+}
+
+// "backup-brogue", with the `--profile` if there is one
+fn app_name(app: &App) -> String {
+    match &app.profile {
+        Some(profile) => format!("backup-brogue [{}]", profile),
+        None => "backup-brogue".to_string(),
+    }
+}
+
+/// A single line summarising what's going on, for `--inline` mode.
+pub fn inline_status(app: &App) -> String {
+    let saves = app.visible_saves().len();
+    let mut parts = vec![format!(
+        "{}: {} saves, {}",
+        app_name(app),
+        saves,
+        app.config().mode
+    )];
+    if let AppMode::Preview(plan) = &app.mode {
+        parts.push(format!(
+            "{} changes to make; press ENTER to start syncing",
+            plan.len()
+        ));
+    }
+    if let Some(save_dir) = app.awaited_save_dir() {
+        parts.push(format!("waiting for {}", save_dir.display()));
+    }
+    if let Some(action) = app.review_queue().front() {
+        parts.push(format!(
+            "{} to review; {} ({}/{})",
+            app.review_queue().len(),
+            action,
+            app.keymap.key(Action::Approve),
+            app.keymap.key(Action::Reject)
+        ));
+    }
+    if app.unwritable_backup_dir().is_some() {
+        parts.push("(!) backup dir not writable; only watching".to_string());
+    }
+    match app.health() {
+        Health::Good => {}
+        Health::Unbacked(_) => parts.push("unbacked progress; backing up".to_string()),
+        _ => parts.push("(!) newest save not backed up".to_string()),
+    }
+    parts.extend(short_prompt(app));
+    if let Some(status) = app.status() {
+        parts.push(status.to_string());
+    }
+    parts.push(format!("press {} to quit", app.keymap.key(Action::Quit)));
+    parts.join(" | ")
+}
+
+// the prompt being answered, if any, without the instructions the full status pane gives
+fn short_prompt(app: &App) -> Option<String> {
+    let prompt = match app.prompt.as_ref()? {
+        Prompt::ExportPath { input, .. } => format!("export to: {}_", input),
+        Prompt::ConfirmOverwrite { destination, .. } => {
+            format!("overwrite {}? (y/n)", destination.display())
+        }
+        Prompt::RestorePath { input, .. } => format!("restore into: {}_", input),
+        Prompt::Rename { input, .. } => format!("comment: {}_", input),
+        Prompt::Note { input, .. } => format!("note: {}_", input),
+        Prompt::Search { input } => format!("search: {}_", input),
+        Prompt::GoToSeed { input } => format!("seed: {}_", input),
+        Prompt::Recover { missing, found } => recover_message(missing, found),
+        Prompt::ResolveDiverged { resolutions } => resolve_message(resolutions),
+    };
+    Some(prompt)
+}
+
+/// Redraws the `--inline` status line in place, leaving everything above it alone.
+pub fn draw_inline(out: &mut impl Write, app: &App) -> std::io::Result<()> {
+    draw_line(out, &inline_status(app))
+}
+
+/// The whole of the `--minimal` display, e.g. "backup-brogue: 12 saves, 3 pending, last sync
+/// 2s ago"; short enough for a small capture in streaming software.
+pub fn minimal_status(app: &App) -> String {
+    let counts = app.state.counts();
+    let last_sync = match app.since_last_sync() {
+        Some(elapsed) => app.locale().format_relative(elapsed),
+        None => "not yet".to_string(),
+    };
+    let status = format!(
+        "{}: {} saves, {} pending, last sync {}",
+        app_name(app),
+        counts.saves,
+        app.pending(),
+        last_sync
+    );
+    if app.unwritable_backup_dir().is_some() {
+        format!("{} (!) backup dir not writable", status)
+    } else if matches!(app.health(), Health::Unbacked(_)) {
+        format!("{} (unbacked progress)", status)
+    } else if app.health() != Health::Good {
+        format!("{} (!) newest save not backed up", status)
+    } else {
+        status
+    }
+}
+
+/// Redraws the `--minimal` line in place.
+pub fn draw_minimal(out: &mut impl Write, app: &App) -> std::io::Result<()> {
+    draw_line(out, &minimal_status(app))
+}
+
+fn draw_line(out: &mut impl Write, line: &str) -> std::io::Result<()> {
+    let width = terminal::size().map(|(w, _)| w as usize).unwrap_or(80);
+    let line: String = line.chars().take(width.saturating_sub(1)).collect();
+    queue!(
+        out,
+        cursor::MoveToColumn(0),
+        terminal::Clear(ClearType::CurrentLine)
+    )?;
+    write!(out, "{}", line)?;
+    out.flush()
+}
+
+/// Draws the whole screen. Generic over the backend so it can be rendered into tui's
+/// `TestBackend` as well as a real terminal; it has to cope with any size, down to 1x1,
+/// without panicking.
+pub fn ui<B: Backend>(f: &mut Frame<B>, app: &App) {
+    match &app.mode {
+        AppMode::Preview(plan) if app.awaited_save_dir().is_none() && !app.is_stale() => {
+            draw_preview(f, plan, app.locale())
+        }
+        _ => draw_saves(f, app),
+    }
+    if let Some(changes) = &app.since_last_launch {
+        draw_popup(
+            f,
+            "Since last launch (any key to close)",
+            changes_lines(changes),
+        );
+    } else if app.show_stats {
+        draw_popup(
+            f,
+            "Stats (any key to close)",
+            stats_lines(&app.stats(), app.locale()),
+        );
+    } else if let Some((a, b)) = app.compared_saves() {
+        draw_popup(
+            f,
+            "Compare ('a'/'b' to delete one, any other key to close)",
+            compare_lines(a, b, app.locale()),
+        );
+    }
+}
+
+fn draw_saves<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let size = f.size();
+
+    let block = Block::default().style(Style::default().bg(Color::White).fg(Color::Black));
+    f.render_widget(block, size);
+
+    let state_descrition = status_lines(app);
+
+    let create_block = |title: Spans<'static>| {
+        Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default().bg(Color::White).fg(Color::Black))
+            .title(title)
+    };
+    let bold = |title| Span::styled(title, Style::default().add_modifier(Modifier::BOLD));
+
+    if is_compact(size, app) {
+        let chunks = compact_layout(size);
+
+        let file_spans = match app.log_tail() {
+            Some(lines) => log_lines(app, lines),
+            None => save_rows(app, size.width, chunks[0].height),
+        };
+        let paragraph = Paragraph::new(file_spans)
+            .style(Style::default().bg(Color::White).fg(Color::Black))
+            .alignment(Alignment::Left);
+        f.render_widget(paragraph, chunks[0]);
+        let status = if size.width < MIN_STATUS_WIDTH {
+            narrow_status(app)
+        } else {
+            Spans::from(vec![
+                health_span(app),
+                Span::raw(format!(" | {}", state_descrition.join(" | "))),
+            ])
+        };
+        let paragraph = Paragraph::new(status)
+            .style(Style::default().bg(Color::Black).fg(Color::White))
+            .alignment(Alignment::Left);
+        f.render_widget(paragraph, chunks[1]);
+        return;
+    }
+
+    let (details_title, details) = details_pane(app);
+    let chunks = full_layout(size, details.len(), status_rows(size, &state_descrition));
+
+    let saves_title = if app.is_stale() {
+        "Saves (as of last session; scanning...)"
+    } else if app.is_scanning() {
+        "Saves (scanning...)"
+    } else if app.follow {
+        "Saves (following newest)"
+    } else {
+        "Saves"
+    };
+    let mut saves_title = vec![bold(saves_title)];
+    if let Some(profile) = &app.profile {
+        saves_title.push(Span::raw(format!(" [profile: {}]", profile)));
+    }
+    saves_title.extend([Span::raw(" "), health_span(app)]);
+    let saves_title = Spans::from(saves_title);
+    // the full layout loses two columns of margin and one of border on each side
+    let file_spans = save_rows(
+        app,
+        size.width.saturating_sub(6),
+        chunks[0].height.saturating_sub(2),
+    );
+    let paragraph = Paragraph::new(file_spans)
+        .style(Style::default().bg(Color::White).fg(Color::Black))
+        .block(create_block(saves_title))
+        .alignment(Alignment::Left);
+    f.render_widget(paragraph, chunks[0]);
+    let paragraph = Paragraph::new(details)
+        .style(Style::default().bg(Color::White).fg(Color::Black))
+        .block(create_block(Spans::from(bold(details_title))))
+        .alignment(Alignment::Left);
+    f.render_widget(paragraph, chunks[1]);
+    let state_descrition: Vec<_> = state_descrition.into_iter().map(Spans::from).collect();
+    let paragraph = Paragraph::new(state_descrition)
+        .style(Style::default().bg(Color::White).fg(Color::Black))
+        .block(create_block(Spans::from(bold("Left, wrap"))))
+        .alignment(Alignment::Left)
+        .wrap(Wrap { trim: true });
+    f.render_widget(paragraph, chunks[2]);
+}
+
+/// The rows of the save list that fit in `height` lines, scrolled to keep the selection in
+/// view. Only these rows are built, so a long list costs no more to draw than a short one.
+fn save_rows(app: &App, width: u16, height: u16) -> Vec<Spans<'static>> {
+    if let Some(save_dir) = app.awaited_save_dir() {
+        let mut rows = vec![
+            Spans::from("waiting for Brogue save directory..."),
+            Spans::from(format!("({} doesn't exist yet)", save_dir.display())),
+        ];
+        rows.truncate(height as usize);
+        return rows;
+    }
+
+    let first = scroll_offset(app.selected_row(), height) as usize;
+    let saves = app.visible_saves();
+    app.rows()
+        .into_iter()
+        .skip(first)
+        .take(height as usize)
+        .map(|row| match row {
+            ListRow::Save(idx) => save_row(app, idx, saves[idx], width),
+            ListRow::Version(idx, version) => version_row(app, idx, &version),
+        })
+        .collect()
+}
+
+fn save_row(app: &App, idx: usize, s: &Save, list_width: u16) -> Spans<'static> {
+    let selected = idx == app.selected && app.selected_version().is_none();
+    let cursor = if selected { ">" } else { " " };
+    let stale = app.is_stale_backup(s);
+    let style = if stale {
+        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+    } else {
+        difficulty_style(s.difficulty())
+    };
+    let pin = if app.is_pinned(s) { "*" } else { " " };
+    let mut spans = vec![Span::styled(
+        format!(
+            "{} {}){} {}",
+            cursor,
+            letter(idx).unwrap_or(' '),
+            pin,
+            save_label(s, app.full_paths, list_width as usize)
+        ),
+        style,
+    )];
+    if stale {
+        spans.push(Span::styled(
+            format!(" (!) not restored for {}m", s.age().as_secs() / 60),
+            style,
+        ));
+    }
+    if app.is_writing(s) {
+        spans.push(Span::styled(
+            " writing...",
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    if app.note(s).is_some() {
+        spans.push(Span::styled(" ✎", Style::default().fg(Color::Gray)));
+    }
+    if app.failed_restores().contains_key(&s.key()) {
+        spans.push(Span::styled(
+            " (!) restore failed",
+            Style::default().fg(Color::Red),
+        ));
+    }
+    if app.is_oversized(s) {
+        spans.push(Span::styled(
+            " (!) too big to back up",
+            Style::default().fg(Color::Red),
+        ));
+    }
+    if !app.config().extra_backup_dirs.is_empty() && !matches!(s, Save::OriginalFileOnly(_)) {
+        let (copies, needed) = app.copies(s);
+        if copies < needed {
+            spans.push(Span::styled(
+                format!(" [{}/{} copies]", copies, needed),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+    }
+    if let Save::Both(_, _) = s {
+        let verification = app.verification(s);
+        let color = match verification {
+            Verification::Pending => Color::Gray,
+            Verification::Verified => Color::Green,
+            Verification::Mismatch => Color::Red,
+        };
+        spans.push(Span::styled(
+            format!(" [{}]", verification),
+            Style::default().fg(color),
+        ));
+    }
+    if let Some((kind, ago)) = app.recent_action(s) {
+        spans.push(Span::styled(
+            format!(" ({} {})", kind, app.locale().format_relative(ago)),
+            Style::default().fg(Color::Gray),
+        ));
+    }
+    if selected {
+        let highlight = highlight_style(&app.config().highlight);
+        for span in spans.iter_mut() {
+            span.style = span.style.patch(highlight);
+        }
+    }
+    Spans::from(spans)
+}
+
+// an older version of the `idx`th save, indented under it: when it was kept, and its size
+fn version_row(app: &App, idx: usize, version: &Path) -> Spans<'static> {
+    let selected = idx == app.selected && app.selected_version() == Some(version);
+    let cursor = if selected { ">" } else { " " };
+    let time = match version_time(version) {
+        Some(t) => chrono::DateTime::<chrono::Local>::from(t)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string(),
+        None => "?".to_string(),
+    };
+    let size = version
+        .metadata()
+        .map(|m| app.locale().format_size(m.len()))
+        .unwrap_or_default();
+    let mut style = Style::default().fg(Color::DarkGray);
+    if selected {
+        style = style.patch(highlight_style(&app.config().highlight));
+    }
+    Spans::from(Span::styled(
+        format!("{}     └ {}  {}", cursor, time, size),
+        style,
+    ))
+}
+
+/// How a save is named in the list: its file name, or with `full_paths` set, the full path
+/// of each copy, cut short from the front to fit in about `width` columns.
+fn save_label(save: &Save, full_paths: bool, width: usize) -> String {
+    let label = save.to_string();
+    if !full_paths {
+        return label;
+    }
+
+    let key = save.key();
+    let tag = label.strip_suffix(&key).unwrap_or(&label);
+    // leave room for the cursor, letter and pin before the label
+    let room = width.saturating_sub(tag.len() + 6);
+    let paths = match save {
+        Save::OriginalFileOnly(path) | Save::BackupFileOnly(path) => shorten(path, room),
+        Save::Both(live, backup) => {
+            let half = room.saturating_sub(5) / 2;
+            format!("{} <-> {}", shorten(live, half), shorten(backup, half))
+        }
+    };
+    format!("{}{}", tag, paths)
+}
+
+// keeps the end of the path, where the file name is, when it's too long
+fn shorten(path: &Path, max: usize) -> String {
+    let path = path.display().to_string();
+    let len = path.chars().count();
+    if len <= max {
+        return path;
+    }
+    let tail: String = path.chars().skip(len - max.saturating_sub(1)).collect();
+    format!("…{}", tail)
+}
+
+fn is_compact(size: Rect, app: &App) -> bool {
+    app.compact
+        || size.height < MIN_FULL_HEIGHT
+        || size.width < MIN_FULL_WIDTH
+        || !full_layout_fits(size, app)
+}
+
+// whether the details and help panes, with the help wrapped to fit, leave room for a few saves
+fn full_layout_fits(size: Rect, app: &App) -> bool {
+    let panes = details_pane(app).1.len() + status_rows(size, &status_lines(app));
+    // two rows of margin above and below, and a border above and below each of three panes
+    panes + 4 + 6 + MIN_SAVE_ROWS <= size.height as usize
+}
+
+// the log pane, or in manual mode the review queue, takes the place of the details pane while
+// there's anything in it
+fn details_pane(app: &App) -> (&'static str, Vec<Spans<'static>>) {
+    match app.log_tail() {
+        Some(lines) if app.is_log_paused() => ("Log (paused)", log_lines(app, lines)),
+        Some(lines) => ("Log", log_lines(app, lines)),
+        None if !app.review_queue().is_empty() => ("Review", review(app)),
+        None => ("Details", details(app)),
+    }
+}
+
+// each line marked with the icon for what it's about, in that kind's colour; lines about
+// anything else are indented to match
+fn log_lines(app: &App, lines: &[String]) -> Vec<Spans<'static>> {
+    let config = app.config();
+    lines
+        .iter()
+        .map(|line| match Activity::of(line) {
+            Some(activity) => {
+                let color = parse_color(config.log_color(activity)).unwrap_or(Color::Reset);
+                Spans::from(Span::styled(
+                    format!("{} {}", config.log_icon(activity), line),
+                    Style::default().fg(color),
+                ))
+            }
+            None => Spans::from(format!("  {}", line)),
+        })
+        .collect()
+}
+
+// how many rows the help pane needs once its lines wrap; the full layout loses six columns to
+// margins and borders
+fn status_rows(size: Rect, lines: &[String]) -> usize {
+    let width = size.width.saturating_sub(6) as usize;
+    lines.iter().map(|line| wrapped_rows(line, width)).sum()
+}
+
+/// A borderless list with a single status line underneath.
+fn compact_layout(size: Rect) -> Vec<Rect> {
+    Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+        .split(size)
+}
+
+/// Bordered saves, details and help panes, the last two sized to fit their lines.
+fn full_layout(size: Rect, details_len: usize, status_len: usize) -> Vec<Rect> {
+    // with a border above and below; a long review queue mustn't overflow the u16
+    let pane = |lines: usize| lines.min(u16::MAX as usize - 2) as u16 + 2;
+    Layout::default()
+        .direction(Direction::Vertical)
+        .margin(2)
+        .constraints(
+            [
+                Constraint::Min(3),
+                Constraint::Length(pane(details_len)),
+                Constraint::Length(pane(status_len)),
+            ]
+            .as_ref(),
+        )
+        .split(size)
+}
+
+// how many rows `line` takes when word-wrapped to `width` columns
+fn wrapped_rows(line: &str, width: usize) -> usize {
+    if width == 0 {
+        return 1;
+    }
+    let mut rows = 1;
+    let mut used = 0;
+    for word in line.split_whitespace() {
+        let len = word.chars().count();
+        let needed = if used == 0 { len } else { used + 1 + len };
+        if needed <= width {
+            used = needed;
+        } else {
+            rows += 1;
+            // a word longer than the line is broken across as many rows as it takes
+            rows += len.saturating_sub(1) / width;
+            used = len % width;
+            if used == 0 {
+                used = width;
+            }
+        }
+    }
+    rows
+}
+
+/// The row of the list drawn at (`column`, `row`) on a screen of `size`, for selecting saves
+/// and versions with the mouse.
+pub fn row_at(size: Rect, app: &App, column: u16, row: u16) -> Option<ListRow> {
+    if app.awaited_save_dir().is_some() {
+        return None;
+    }
+    let list = if is_compact(size, app) {
+        if app.log_tail().is_some() {
+            return None;
+        }
+        compact_layout(size)[0]
+    } else {
+        let details_len = details_pane(app).1.len();
+        let area = full_layout(size, details_len, status_rows(size, &status_lines(app)))[0];
+        // inside the border
+        Rect::new(
+            area.x + 1,
+            area.y + 1,
+            area.width.saturating_sub(2),
+            area.height.saturating_sub(2),
+        )
+    };
+    let inside = column >= list.x
+        && column < list.x + list.width
+        && row >= list.y
+        && row < list.y + list.height;
+    if !inside {
+        return None;
+    }
+    let idx = (row - list.y + scroll_offset(app.selected_row(), list.height)) as usize;
+    app.rows().into_iter().nth(idx)
+}
+
+/// What the first sync will do, before we do it.
+fn draw_preview<B: Backend>(f: &mut Frame<B>, plan: &[SyncAction], locale: Locale) {
+    let size = f.size();
+    let space = SpaceChange::estimate(plan);
+    let mut lines = vec![
+        Spans::from(format!("the first sync will make {} changes:", plan.len())),
+        Spans::from(format!("disk use: {}", space.describe(locale))),
+        Spans::from(""),
+    ];
+    lines.extend(
+        plan.iter()
+            .map(|action| Spans::from(format!("  {}", action))),
+    );
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .style(Style::default().bg(Color::White).fg(Color::Black))
+        .title(Span::styled(
+            "Preview (ENTER to start syncing, q to quit)",
+            Style::default().add_modifier(Modifier::BOLD),
+        ));
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .alignment(Alignment::Left)
+        .wrap(Wrap { trim: false });
+    f.render_widget(paragraph, size);
+}
+
+/// A popup over the middle of the screen, sized to fit `lines`.
+fn draw_popup<B: Backend>(f: &mut Frame<B>, title: &'static str, lines: Vec<Spans<'static>>) {
+    let size = f.size();
+    let content_width = lines
+        .iter()
+        .map(|l| l.width())
+        .max()
+        .unwrap_or(0)
+        .max(title.len());
+    let width = (content_width as u16 + 4).min(size.width);
+    let height = (lines.len() as u16 + 2).min(size.height);
+    let area = Rect::new(
+        size.x + (size.width - width) / 2,
+        size.y + (size.height - height) / 2,
+        width,
+        height,
+    );
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .style(Style::default().bg(Color::White).fg(Color::Black))
+        .title(Span::styled(
+            title,
+            Style::default().add_modifier(Modifier::BOLD),
+        ));
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .alignment(Alignment::Left);
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+// the summary, then the saves themselves, as many as are worth reading through
+fn changes_lines(changes: &Changes) -> Vec<Spans<'static>> {
+    let mut lines = vec![Spans::from(changes.to_string()), Spans::from("")];
+    let names = changes
+        .added
+        .iter()
+        .map(|name| ("+", name, Color::Green))
+        .chain(changes.removed.iter().map(|name| ("-", name, Color::Red)))
+        .chain(changes.modified.iter().map(|name| ("~", name, Color::Blue)));
+    let total = changes.added.len() + changes.removed.len() + changes.modified.len();
+    for (mark, name, color) in names.take(MAX_CHANGES_LISTED) {
+        lines.push(Spans::from(Span::styled(
+            format!("{} {}", mark, name),
+            Style::default().fg(color),
+        )));
+    }
+    if total > MAX_CHANGES_LISTED {
+        lines.push(Spans::from(format!(
+            "...and {} more",
+            total - MAX_CHANGES_LISTED
+        )));
+    }
+    lines
+}
+
+fn stats_lines(stats: &Stats, locale: Locale) -> Vec<Spans<'static>> {
+    let time = |t: SystemTime| {
+        chrono::DateTime::<chrono::Local>::from(t)
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    };
+
+    let mut lines = vec![
+        Spans::from(format!("{} saves tracked", stats.saves)),
+        Spans::from(format!(
+            "{} of backups",
+            locale.format_size(stats.backup_bytes)
+        )),
+        Spans::from(""),
+    ];
+    for (difficulty, count) in &stats.by_difficulty {
+        let difficulty = difficulty
+            .map(|d| d.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        lines.push(Spans::from(format!("{:>8}: {}", difficulty, count)));
+    }
+    lines.push(Spans::from(""));
+    if let Some((name, modified)) = &stats.oldest_backup {
+        lines.push(Spans::from(format!(
+            "oldest backup: {} ({})",
+            name,
+            time(*modified)
+        )));
+    }
+    if let Some((name, modified)) = &stats.newest_backup {
+        lines.push(Spans::from(format!(
+            "newest backup: {} ({})",
+            name,
+            time(*modified)
+        )));
+    }
+    let actions: Vec<_> = stats
+        .actions
+        .iter()
+        .map(|(kind, count)| format!("{} {}", kind, count))
+        .collect();
+    if actions.is_empty() {
+        lines.push(Spans::from("nothing done yet this session"));
+    } else {
+        lines.push(Spans::from(format!("this session: {}", actions.join(", "))));
+    }
+    if let Some(rate) = stats.copy_bytes_per_sec {
+        lines.push(Spans::from(format!(
+            "copying at {}/s lately",
+            locale.format_size(rate as u64)
+        )));
+    }
+    lines
+}
+
+/// Two saves side by side, one row per field.
+fn compare_lines(a: &Save, b: &Save, locale: Locale) -> Vec<Spans<'static>> {
+    let fields = |save: &Save| {
+        let metadata = save.metadata();
+        let file = save.path().metadata().ok();
+        vec![
+            save.key(),
+            metadata
+                .as_ref()
+                .map_or("?".to_string(), |m| m.seed.to_string()),
+            metadata
+                .as_ref()
+                .map_or("?".to_string(), |m| m.depth.to_string()),
+            save.difficulty().map_or("?".to_string(), |d| d.to_string()),
+            file.as_ref()
+                .map_or("?".to_string(), |m| locale.format_size(m.len())),
+            file.and_then(|m| m.modified().ok())
+                .map_or("?".to_string(), |t| {
+                    chrono::DateTime::<chrono::Local>::from(t)
+                        .format("%Y-%m-%d %H:%M")
+                        .to_string()
+                }),
+        ]
+    };
+    let names = ["", "seed", "depth", "difficulty", "size", "modified"];
+    let (a, b) = (fields(a), fields(b));
+    let width = a.iter().map(|s| s.len()).max().unwrap_or(0);
+
+    let mut lines = vec![Spans::from(vec![
+        Span::raw(format!("{:10}  ", "")),
+        Span::styled(
+            format!("{:width$}", "a", width = width),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Span::raw("  "),
+        Span::styled("b", Style::default().add_modifier(Modifier::BOLD)),
+    ])];
+    for ((name, a), b) in names.iter().zip(a).zip(b) {
+        let style = if a == b {
+            Style::default()
+        } else {
+            Style::default().add_modifier(Modifier::BOLD)
+        };
+        lines.push(Spans::from(vec![
+            Span::raw(format!("{:10}  ", name)),
+            Span::styled(format!("{:width$}", a, width = width), style),
+            Span::raw("  "),
+            Span::styled(b, style),
+        ]));
+    }
+    lines
+}
+
+/// How far to scroll a list `height` rows tall so the `selected` row is on screen.
+fn scroll_offset(selected: usize, height: u16) -> u16 {
+    (selected as u16).saturating_sub(height.saturating_sub(1))
+}
+
+// the actions waiting for approval in manual mode, oldest first, which is the one the keys act on
+fn review(app: &App) -> Vec<Spans<'static>> {
+    let queue = app.review_queue();
+    let mut lines = vec![Spans::from(format!(
+        "{} waiting; {}",
+        queue.len(),
+        help_line(
+            app,
+            &[
+                (Action::Approve, " to approve or "),
+                (Action::Reject, " to reject the first"),
+            ],
+        )
+    ))];
+    for (i, action) in queue.iter().enumerate() {
+        let line = format!("{} {}", if i == 0 { ">" } else { " " }, action);
+        lines.push(if i == 0 {
+            Spans::from(Span::styled(
+                line,
+                Style::default().add_modifier(Modifier::BOLD),
+            ))
+        } else {
+            Spans::from(line)
+        });
+    }
+    lines
+}
+
+fn details(app: &App) -> Vec<Spans<'static>> {
+    let save = match app.selected_save() {
+        Some(save) => save,
+        None => return vec![Spans::from("no save selected")],
+    };
+
+    let mut lines = match save.metadata() {
+        Some(metadata) => {
+            let difficulty = metadata
+                .difficulty
+                .map(|d| d.to_string())
+                .unwrap_or_else(|| "unknown difficulty".to_string());
+            vec![
+                Spans::from(vec![
+                    Span::raw("seed "),
+                    Span::styled(
+                        metadata.seed.to_string(),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(format!(
+                        " - press {} to copy it",
+                        app.keymap.key(Action::CopySeed)
+                    )),
+                ]),
+                Spans::from(format!("depth {}, {}", metadata.depth, difficulty)),
+            ]
+        }
+        None => vec![
+            Spans::from("seed unknown; copying is disabled"),
+            Spans::from("(the file name isn't in brogue's usual format)"),
+        ],
+    };
+
+    if let Some(note) = app.note(save) {
+        lines.push(Spans::from(format!("note: {}", note)));
+    }
+
+    if let Some((key, diff)) = &app.diff {
+        if *key == save.key() {
+            lines.extend(diff_lines(diff));
+        }
+    }
+    lines
+}
+
+fn diff_lines(diff: &DiffSummary) -> Vec<Spans<'static>> {
+    let time = |t: SystemTime| {
+        chrono::DateTime::<chrono::Local>::from(t)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string()
+    };
+    let verdict = match diff.first_difference {
+        None => Span::styled("contents match", Style::default().fg(Color::Green)),
+        Some(offset) => Span::styled(
+            format!("contents differ from byte {}", offset),
+            Style::default().fg(Color::Red),
+        ),
+    };
+    vec![
+        Spans::from(format!(
+            "save:   {} bytes, modified {}",
+            diff.save_len,
+            time(diff.save_modified)
+        )),
+        Spans::from(format!(
+            "backup: {} bytes, modified {}",
+            diff.backup_len,
+            time(diff.backup_modified)
+        )),
+        Spans::from(verdict),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::testing::{save_name, Dirs};
+    use crate::versions::VERSIONS_DIR;
+    use tui::backend::TestBackend;
+    use tui::Terminal;
+
+    // the screen as drawn at `width` x `height`, a line per row
+    fn render(app: &App, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|f| ui(f, app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| buffer.get(x, y).symbol.as_str())
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn app_with_saves(dirs: &Dirs) -> App {
+        dirs.save(&save_name(1, 3), "one");
+        dirs.save(&save_name(2, 7), "two");
+        dirs.app(Config::default())
+    }
+
+    #[test]
+    fn draws_at_1x1() {
+        let dirs = Dirs::new();
+        let app = app_with_saves(&dirs);
+        let screen = render(&app, 1, 1);
+        assert_eq!(screen.len(), 1);
+        assert_eq!(screen[0].chars().count(), 1);
+    }
+
+    #[test]
+    fn draws_at_every_tiny_size() {
+        let dirs = Dirs::new();
+        let app = app_with_saves(&dirs);
+        for width in 1..=MIN_FULL_WIDTH + 2 {
+            for height in 1..=MIN_FULL_HEIGHT + 2 {
+                render(&app, width, height);
+            }
+        }
+    }
+
+    #[test]
+    fn a_narrow_screen_shows_the_health_dot_and_how_to_quit() {
+        let dirs = Dirs::new();
+        let app = app_with_saves(&dirs);
+        let screen = render(&app, MIN_STATUS_WIDTH - 10, 10);
+        let status = screen.last().unwrap();
+        assert!(status.starts_with("● 'q' quit"), "{:?}", status);
+        assert!(screen[0].contains("a)"), "{:?}", screen);
+    }
+
+    #[test]
+    fn compact_view_lists_saves_without_borders() {
+        let dirs = Dirs::new();
+        let mut app = app_with_saves(&dirs);
+        app.compact = true;
+        let screen = render(&app, 100, 30);
+        assert!(screen[0].starts_with("> a)"), "{:?}", screen);
+        assert!(screen.iter().any(|line| line.contains(&save_name(1, 3))));
+        assert!(screen.iter().any(|line| line.contains(&save_name(2, 7))));
+        assert!(!screen.concat().contains('┌'));
+        assert!(screen.last().unwrap().contains("backed up"));
+    }
+
+    #[test]
+    fn full_view_has_saves_details_and_help_panes() {
+        let dirs = Dirs::new();
+        let app = app_with_saves(&dirs);
+        let screen = render(&app, 160, 50);
+        let titles: Vec<_> = ["┌Saves", "┌Details", "┌Left, wrap"]
+            .iter()
+            .map(|title| screen.iter().position(|line| line.contains(title)))
+            .collect();
+        assert!(titles.iter().all(Option::is_some), "{:?}", screen);
+        assert!(titles.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(screen.iter().any(|line| line.contains("> a)")));
+        assert!(screen.iter().any(|line| line.contains("to quit")));
+    }
+
+    #[test]
+    fn hints_at_the_delete_key_unless_deleting_is_off() {
+        let dirs = Dirs::new();
+        let app = app_with_saves(&dirs);
+        assert!(status_lines(&app).contains(&"press 'd' to delete a save game".to_string()));
+
+        let app = dirs.app(Config {
+            allow_delete: false,
+            ..Config::default()
+        });
+        assert!(!status_lines(&app)
+            .iter()
+            .any(|line| line.contains("delete")));
+    }
+
+    #[test]
+    fn builds_no_more_save_rows_than_fit() {
+        let dirs = Dirs::new();
+        for n in 0..500 {
+            dirs.save(&save_name(n, 1), "game");
+        }
+        let mut app = dirs.app(Config::default());
+        for selected in [0, 250, 499] {
+            app.selected = selected;
+            for height in [0, 1, 2, 10, 60] {
+                let rows = save_rows(&app, 80, height);
+                assert_eq!(rows.len(), usize::from(height));
+                if height > 0 {
+                    let cursor = rows.iter().filter(|row| row.0[0].content.starts_with('>'));
+                    assert_eq!(
+                        cursor.count(),
+                        1,
+                        "selected {} in {} rows",
+                        selected,
+                        height
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn builds_no_more_rows_than_fit_while_waiting_for_the_save_dir() {
+        let dirs = Dirs::new();
+        std::fs::remove_dir(&dirs.save_dir).unwrap();
+        let app = dirs.app(Config::default());
+        assert!(app.awaited_save_dir().is_some());
+        for height in 0..4 {
+            assert!(save_rows(&app, 80, height).len() <= usize::from(height));
+        }
+    }
+
+    #[test]
+    fn only_the_first_26_saves_have_letters() {
+        assert_eq!(letter(0), Some('a'));
+        assert_eq!(letter(25), Some('z'));
+        // 'a' + 159 would overflow a u8
+        for idx in [26, 159, 255, 256, usize::MAX] {
+            assert_eq!(letter(idx), None);
+        }
+        for idx in 0..26 {
+            assert_eq!(letter(idx).and_then(letter_index), Some(idx));
+        }
+        assert_eq!(letter_index('A'), None);
+        assert_eq!(letter_index('{'), None);
+    }
+
+    #[test]
+    fn lists_saves_past_z_without_a_letter() {
+        let dirs = Dirs::new();
+        for n in 0..200 {
+            dirs.save(&save_name(n, 1), "game");
+        }
+        let mut app = dirs.app(Config::default());
+        app.compact = true;
+        let screen = render(&app, 100, 220);
+        assert!(screen[25].starts_with("  z)"), "{:?}", screen[25]);
+        assert!(screen[26].starts_with("   )"), "{:?}", screen[26]);
+        assert!(screen[199].starts_with("   )"), "{:?}", screen[199]);
+    }
+
+    #[test]
+    fn marks_log_lines_with_their_icon_and_colour() {
+        let dirs = Dirs::new();
+        let app = dirs.app(Config {
+            ascii_icons: true,
+            log_icons: [("restore".to_string(), "R".to_string())].into(),
+            log_colors: [("backup".to_string(), "magenta".to_string())].into(),
+            ..Config::default()
+        });
+        let lines = [
+            "[BACKUP] copying a => b".to_string(),
+            "[RESTORE] copying b => a".to_string(),
+            "started".to_string(),
+        ];
+        let spans = log_lines(&app, &lines);
+        let text: Vec<_> = spans
+            .iter()
+            .map(|spans| spans.0[0].content.to_string())
+            .collect();
+        assert_eq!(
+            text,
+            [
+                "^ [BACKUP] copying a => b",
+                "R [RESTORE] copying b => a",
+                "  started"
+            ]
+        );
+        assert_eq!(spans[0].0[0].style.fg, Some(Color::Magenta));
+        assert_eq!(spans[1].0[0].style.fg, Some(Color::Blue));
+        assert_eq!(spans[2].0[0].style.fg, None);
+    }
+
+    #[test]
+    fn lists_an_expanded_saves_versions_under_it() {
+        let dirs = Dirs::new();
+        let mut app = app_with_saves(&dirs);
+        let key = app.visible_saves()[0].key();
+        let versions = dirs.backup_dir.join(VERSIONS_DIR).join(&key);
+        std::fs::create_dir_all(&versions).unwrap();
+        for millis in ["1600000000000", "1700000000000"] {
+            std::fs::write(versions.join(format!("{} {}", millis, key)), "older").unwrap();
+        }
+        let text = |app: &App| -> Vec<String> {
+            save_rows(app, 80, 10)
+                .iter()
+                .map(|row| row.0.iter().map(|span| span.content.as_ref()).collect())
+                .collect()
+        };
+
+        app.expand();
+        let rows = text(&app);
+        assert!(rows[0].starts_with("> a)"), "{:?}", rows);
+        // newest first, under the save and before the next one
+        let newest = chrono::DateTime::<chrono::Local>::from(
+            SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_000),
+        )
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string();
+        assert_eq!(rows[1], format!("      └ {}  5 B", newest));
+        assert!(rows[2].starts_with("      └ "), "{:?}", rows);
+        assert!(rows[3].contains("b)"), "{:?}", rows);
+
+        app.select_next();
+        let rows = text(&app);
+        assert!(rows[0].starts_with("  a)"), "{:?}", rows);
+        assert!(rows[1].starts_with(">     └ "), "{:?}", rows);
+
+        app.collapse();
+        let rows = text(&app);
+        assert!(rows[0].starts_with("> a)"), "{:?}", rows);
+        assert!(rows[1].contains("b)"), "{:?}", rows);
+    }
+}
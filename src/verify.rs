@@ -0,0 +1,118 @@
+use crate::backup::{Save, State};
+use crate::hashes::{fingerprint, Fingerprint, HashCache};
+use log::warn;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use strum_macros::Display;
+
+/// Whether a synced save's backup has the same contents as the live save.
+#[derive(Clone, Copy, Debug, PartialEq, Display)]
+pub enum Verification {
+    #[strum(serialize = "verifying")]
+    Pending,
+    #[strum(serialize = "verified")]
+    Verified,
+    #[strum(serialize = "mismatch")]
+    Mismatch,
+}
+
+type Pair = (String, PathBuf, PathBuf);
+
+// the outcome for each save key, and which versions of the save and backup it applies to
+type Results = Arc<Mutex<HashMap<String, (Fingerprint, Fingerprint, Verification)>>>;
+
+/// Compares synced saves against their backups on a background thread, so hashing never holds
+/// up the render loop. Hashes come from the shared `HashCache`, so files that haven't changed
+/// aren't read again.
+pub struct Verifier {
+    requests: Sender<Vec<Pair>>,
+    results: Results,
+}
+
+impl Verifier {
+    pub fn new(hashes: Arc<HashCache>) -> Verifier {
+        let (requests, rx) = channel();
+        let results = Arc::new(Mutex::new(HashMap::new()));
+
+        let worker_results = results.clone();
+        std::thread::spawn(move || verify_loop(rx, worker_results, hashes));
+
+        Verifier { requests, results }
+    }
+
+    /// Queues every synced save in `state` for checking; only the latest submission matters.
+    pub fn submit(&self, state: &State) {
+        let pairs = state
+            .saves
+            .iter()
+            .filter_map(|s| match s {
+                Save::Both(save, backup) => Some((s.key(), save.clone(), backup.clone())),
+                _ => None,
+            })
+            .collect();
+        // the worker only goes away if it panicked, in which case everything stays pending
+        let _ = self.requests.send(pairs);
+    }
+
+    pub fn status(&self, save: &Save) -> Verification {
+        let key = save.key();
+        let (save, backup) = match save {
+            Save::Both(save, backup) => (save, backup),
+            _ => return Verification::Pending,
+        };
+
+        let results = self.results.lock().expect("verification results lock");
+        match results.get(&key) {
+            // only trust a result for the versions of the files we're looking at now
+            Some((s, b, verification))
+                if Some(s) == fingerprint(save).as_ref()
+                    && Some(b) == fingerprint(backup).as_ref() =>
+            {
+                *verification
+            }
+            _ => Verification::Pending,
+        }
+    }
+}
+
+fn verify_loop(rx: Receiver<Vec<Pair>>, results: Results, hashes: Arc<HashCache>) {
+    while let Ok(mut pairs) = rx.recv() {
+        // skip straight to the newest request if we've fallen behind
+        while let Ok(newer) = rx.try_recv() {
+            pairs = newer;
+        }
+
+        let mut verified = HashMap::new();
+        for (key, save, backup) in pairs {
+            let (save, backup) = match (fingerprint(&save), fingerprint(&backup)) {
+                (Some(save), Some(backup)) => (save, backup),
+                // one side vanished mid-check; it'll be picked up next time round
+                _ => continue,
+            };
+
+            let save_hash = cached_hash(&hashes, &save);
+            let backup_hash = cached_hash(&hashes, &backup);
+            let verification = match (save_hash, backup_hash) {
+                (Some(a), Some(b)) if a == b => Verification::Verified,
+                (Some(_), Some(_)) => Verification::Mismatch,
+                _ => Verification::Pending,
+            };
+
+            verified.insert(key, (save, backup, verification));
+        }
+
+        *results.lock().expect("verification results lock") = verified;
+    }
+}
+
+fn cached_hash(hashes: &HashCache, fingerprint: &Fingerprint) -> Option<u64> {
+    match hashes.hash(&fingerprint.0) {
+        Ok(hash) => Some(hash),
+        Err(e) => {
+            warn!("[VERIFY] could not read {}: {}", fingerprint.0.display(), e);
+            None
+        }
+    }
+}
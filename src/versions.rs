@@ -0,0 +1,143 @@
+use crate::backup::{hash_file, move_file};
+use crate::crypt::plain_name;
+use crate::Result;
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+pub const VERSIONS_DIR: &str = "versions";
+pub const CHECKSUM_EXTENSION: &str = "sum";
+// a checksum says which hash it is. One without this was written by an older build with a hash
+// that can change with the compiler, so it proves nothing either way and is ignored
+const CHECKSUM_TAG: &str = "fnv1a:";
+
+// Older backups of each save live in `versions/<save name>/<millis> <file name>` (the file
+// name keeps any `.age` suffix, so we know to decrypt it), and every
+// backup we write gets a `.sum` file alongside it holding the hash of what we wrote, so we can
+// tell later whether it's been truncated or corrupted.
+
+pub fn checksum_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".");
+    file_name.push(CHECKSUM_EXTENSION);
+    path.with_file_name(file_name)
+}
+
+pub fn write_checksum(path: &Path) -> Result<()> {
+    let hash = hash_file(path)?;
+    std::fs::write(
+        checksum_path(path),
+        format!("{}{:016x}\n", CHECKSUM_TAG, hash),
+    )?;
+    Ok(())
+}
+
+/// Whether a backup can be read and still matches the checksum recorded when it was written.
+/// Backups without a checksum, or with one from before checksums were stable across builds, only
+/// have to be readable and non-empty.
+pub fn is_intact(path: &Path) -> bool {
+    let len = match path.metadata() {
+        Ok(metadata) => metadata.len(),
+        Err(_) => return false,
+    };
+    if len == 0 {
+        return false;
+    }
+
+    let hash = match hash_file(path) {
+        Ok(hash) => hash,
+        Err(_) => return false,
+    };
+    match std::fs::read_to_string(checksum_path(path)) {
+        Ok(expected) => match expected.trim().strip_prefix(CHECKSUM_TAG) {
+            Some(expected) => u64::from_str_radix(expected, 16).ok() == Some(hash),
+            None => true,
+        },
+        Err(_) => true,
+    }
+}
+
+/// Moves `backup` (and its checksum) into the versions dir, stamped with the current time.
+pub fn store_version(backup: &Path, backup_dir: &Path) -> Result<PathBuf> {
+    let version = new_version_path(backup, backup_dir)?;
+    move_file(backup, &version)?;
+    let checksum = checksum_path(backup);
+    if checksum.exists() {
+        move_file(&checksum, &checksum_path(&version))?;
+    }
+    Ok(version)
+}
+
+/// Where a new version of the file `backup` should go, stamped with the current time. It
+/// doesn't have to exist yet; the versions dir is created if need be.
+pub fn new_version_path(backup: &Path, backup_dir: &Path) -> Result<PathBuf> {
+    let file_name = backup.file_name().unwrap_or_default();
+    let dir = backup_dir.join(VERSIONS_DIR).join(plain_name(backup));
+    if !dir.exists() {
+        std::fs::create_dir_all(&dir)?;
+    }
+
+    let millis = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    Ok(dir.join(format!("{} {}", millis, file_name.to_string_lossy())))
+}
+
+/// Older backups of the save called `file_name`, newest first.
+pub fn versions(backup_dir: &Path, file_name: &str) -> Vec<PathBuf> {
+    let dir = backup_dir.join(VERSIONS_DIR).join(file_name);
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    let mut versions: Vec<(SystemTime, PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().unwrap_or_default() != CHECKSUM_EXTENSION)
+        .filter_map(|path| Some((version_time(&path)?, path)))
+        .collect();
+    versions.sort_by_key(|(time, _)| Reverse(*time));
+    versions.into_iter().map(|(_, path)| path).collect()
+}
+
+/// When a version was kept, from the time it's stamped with.
+pub fn version_time(version: &Path) -> Option<SystemTime> {
+    let name = version.file_name()?.to_string_lossy();
+    let millis = name.split(' ').next()?.parse().ok()?;
+    Some(SystemTime::UNIX_EPOCH + Duration::from_millis(millis))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::write;
+
+    #[test]
+    fn checksums_are_the_same_from_one_build_to_the_next() {
+        let dir = tempfile::tempdir().unwrap();
+        let backup = write(dir.path(), "Saved #1 at depth 1.broguesave", "abc");
+        write_checksum(&backup).unwrap();
+        // FNV-1a of "abc"
+        assert_eq!(
+            std::fs::read_to_string(checksum_path(&backup)).unwrap(),
+            "fnv1a:e71fa2190541574b\n"
+        );
+        assert!(is_intact(&backup));
+
+        std::fs::write(&backup, "abd").unwrap();
+        assert!(!is_intact(&backup));
+    }
+
+    #[test]
+    fn ignores_a_checksum_from_before_they_were_stable() {
+        let dir = tempfile::tempdir().unwrap();
+        let backup = write(dir.path(), "Saved #1 at depth 1.broguesave", "abc");
+        std::fs::write(checksum_path(&backup), "0123456789abcdef\n").unwrap();
+        assert!(is_intact(&backup));
+
+        std::fs::write(&backup, "").unwrap();
+        assert!(!is_intact(&backup));
+    }
+}
@@ -0,0 +1,95 @@
+use crate::Result;
+use notify::{DebouncedEvent, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::Path;
+use std::sync::mpsc::{channel, Receiver};
+use std::time::Duration;
+use strum_macros::Display;
+
+// how long notify waits for a burst of events to settle before telling us
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+// file system types whose change events don't reliably reach us
+const NETWORK_FS_TYPES: &[&str] = &[
+    "nfs", "nfs4", "cifs", "smbfs", "smb3", "afpfs", "webdav", "davfs", "sshfs", "9p",
+];
+
+/// How we find out the save dir has changed, shown in the status bar.
+#[derive(Clone, Copy, Debug, PartialEq, Display)]
+pub enum WatchStrategy {
+    /// the OS tells us (inotify, FSEvents, ...)
+    #[strum(serialize = "native events")]
+    Native,
+    /// notify's `PollWatcher` rescans the dir, for mounts where events don't fire
+    #[strum(serialize = "polling")]
+    Polling,
+}
+
+/// Watches the save dir so changes are synced straight away rather than on the next
+/// reconcile tick.
+pub struct DirWatcher {
+    // never read, but a watcher stops when it's dropped; only one of these is ever set
+    _native: Option<RecommendedWatcher>,
+    _polling: Option<PollWatcher>,
+    events: Receiver<DebouncedEvent>,
+    pub strategy: WatchStrategy,
+}
+
+impl DirWatcher {
+    /// Watches `dir`, polling if `force_poll` is set or `dir` looks like a network mount.
+    pub fn new(dir: &Path, force_poll: bool) -> Result<DirWatcher> {
+        let (tx, events) = channel();
+        if force_poll || is_network_mount(dir) {
+            let mut watcher = PollWatcher::new(tx, DEBOUNCE)?;
+            watcher.watch(dir, RecursiveMode::NonRecursive)?;
+            Ok(DirWatcher {
+                _native: None,
+                _polling: Some(watcher),
+                events,
+                strategy: WatchStrategy::Polling,
+            })
+        } else {
+            let mut watcher = notify::watcher(tx, DEBOUNCE)?;
+            watcher.watch(dir, RecursiveMode::NonRecursive)?;
+            Ok(DirWatcher {
+                _native: Some(watcher),
+                _polling: None,
+                events,
+                strategy: WatchStrategy::Native,
+            })
+        }
+    }
+
+    /// Whether anything has changed since we last asked.
+    pub fn changed(&self) -> bool {
+        self.events.try_iter().count() > 0
+    }
+}
+
+/// A best guess at whether `dir` is on a network or cloud-synced volume. On Linux we look the
+/// mount up in `/proc/mounts`; elsewhere we go by the well-known cloud storage folders.
+pub fn is_network_mount(dir: &Path) -> bool {
+    let path = dir.to_string_lossy();
+    if path.contains("/Library/CloudStorage/") || path.contains("/Library/Mobile Documents/") {
+        return true;
+    }
+
+    let mounts = match std::fs::read_to_string("/proc/mounts") {
+        Ok(mounts) => mounts,
+        Err(_) => return false,
+    };
+    // the longest mount point that contains `dir` is the one it's on
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let _device = fields.next()?;
+            let mount_point = fields.next()?.replace("\\040", " ");
+            let fs_type = fields.next()?;
+            dir.starts_with(&mount_point)
+                .then(|| (mount_point.len(), fs_type.to_string()))
+        })
+        .max_by_key(|(len, _)| *len)
+        .is_some_and(|(_, fs_type)| {
+            NETWORK_FS_TYPES.contains(&fs_type.as_str()) || fs_type.starts_with("fuse.sshfs")
+        })
+}